@@ -25,7 +25,8 @@ fn main() {
         spec.spline_to(None, None, *pt, true);
     }
     let spline = spec.solve();
-    let path = spline.render();
+    let mut d = String::new();
+    spline.write_svg_path(&mut d, 2).unwrap();
     let mut auto_pts = Vec::new();
     for seg in spline.segments() {
         auto_pts.push(seg.p1);
@@ -38,7 +39,7 @@ fn main() {
     <svg height="500" width="500">
       <path d="{}" fill="none" stroke="#000" />"
     "##,
-        path.to_svg()
+        d
     );
     for pt in &pts {
         println!(
@@ -0,0 +1,33 @@
+//! Print samples of solved splines, in the format of the research spline
+//! comparison fixtures.
+//!
+//! The input is a json list of `SplineSpec` objects, as for the `json`
+//! example. Pass `--per-segment N` to change the number of steps in each
+//! segment.
+
+use serde_json::json;
+use spline::SplineSpec;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().expect("needs filename");
+    let mut per_segment = 8;
+    while let Some(arg) = args.next() {
+        if arg == "--per-segment" {
+            per_segment = args
+                .next()
+                .and_then(|n| n.parse().ok())
+                .expect("needs a count");
+        }
+    }
+    let data = std::fs::read_to_string(path).unwrap();
+    let specs: Vec<SplineSpec> = serde_json::from_str(&data).unwrap();
+    let cases: Vec<_> = specs
+        .into_iter()
+        .map(|mut spec| {
+            let samples = spec.solve().samples(per_segment);
+            json!({ "spec": spec, "samples": samples })
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&cases).unwrap());
+}
@@ -0,0 +1,1039 @@
+//! The hyperbezier curve family.
+//!
+//! This is the curve underlying the [`spline`] crate, published separately
+//! for renderers and other users who need the curve but not the spline
+//! solver. It depends only on [`kurbo`].
+//!
+//! [`spline`]: https://docs.rs/spline
+
+use std::fmt;
+
+use kurbo::common as coeffs;
+use kurbo::{Affine, BezPath, CubicBez, ParamCurve, PathEl, Point, Vec2};
+
+mod scalar;
+pub mod wgsl;
+
+use scalar::Scalar;
+
+/// Evaluating the curve math with dual numbers, for exact derivatives.
+///
+/// This is for experimenting with solver objectives, and is only built
+/// with the `research` feature.
+#[cfg(feature = "research")]
+pub mod research {
+    pub use crate::scalar::{Dual, Scalar};
+    use crate::HyperBezierResult;
+
+    /// The tangent angle at arclength parameter `s`, for the parameters
+    /// `[k0, bias0, k1, bias1]`.
+    ///
+    /// This is [`HyperBezier::compute_theta`](crate::HyperBezier::compute_theta)
+    /// over any scalar type.
+    pub fn compute_theta<T: Scalar>(params: [T; 4], s: T) -> T {
+        crate::theta(params, s)
+    }
+
+    /// Measure the curve with parameters `[k0, bias0, k1, bias1]`.
+    ///
+    /// This is [`HyperBezier::compute`](crate::HyperBezier::compute) over
+    /// any scalar type.
+    pub fn compute<T: Scalar>(params: [T; 4]) -> HyperBezierResult<T> {
+        crate::measure(params)
+    }
+}
+
+/// The lowest legal bias value.
+pub const MIN_BIAS: f64 = -1.0;
+
+/// The bias value of a cusp. Legal bias values are less than this.
+pub const MAX_BIAS: f64 = 2.0;
+
+/// The largest legal magnitude of `k0` and `k1`.
+///
+/// These are in units of the curve's own arclength, so this corresponds to
+/// the tangent turning through many full circles.
+pub const MAX_K: f64 = 100.0;
+
+/// The lowest bias used by the solver when matching curvature.
+///
+/// This leaves a margin above [`MIN_BIAS`], where the curve is poorly
+/// behaved.
+pub const MIN_MATCH_BIAS: f64 = -0.9;
+
+/// The tolerance for [`RenderOptions::stable_topology`] when none is given,
+/// relative to the length of the chord.
+pub const STABLE_TOLERANCE: f64 = 1e-3;
+
+/// The weight of turning relative to arclength when placing subdivisions
+/// for [`Parameterization::CurvatureWeighted`].
+const CURVATURE_WEIGHT: f64 = 0.25;
+
+/// How close to [`MAX_BIAS`] the basis function computations go.
+const CUSP_MARGIN: f64 = 1e-4;
+
+/// A mapping from a tension control, from 0 to 1, onto bias values.
+///
+/// This is for user interfaces, so that sliders don't hard-code the bias
+/// range. Tension 0 is the loosest bias, 1 the tightest, short of a cusp,
+/// and 0.5 is an Euler spiral, with a bias of 1. The bias is linear in the
+/// tension on either side of 0.5.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TensionScale {
+    min: f64,
+    neutral: f64,
+    max: f64,
+}
+
+impl Default for TensionScale {
+    fn default() -> TensionScale {
+        TensionScale {
+            min: MIN_MATCH_BIAS,
+            neutral: 1.0,
+            max: HyperBezier::MAX_CLAMPED_BIAS,
+        }
+    }
+}
+
+impl TensionScale {
+    /// Create a scale with the biases at tensions 0, 0.5 and 1.
+    ///
+    /// The biases must be legal, as for [`HyperBezier::new_checked`], and
+    /// increasing.
+    pub fn new(min: f64, neutral: f64, max: f64) -> Result<TensionScale, ParamError> {
+        for &bias in &[min, neutral, max] {
+            if !bias.is_finite() {
+                return Err(ParamError::NotFinite);
+            }
+            if !(MIN_BIAS..MAX_BIAS).contains(&bias) {
+                return Err(ParamError::Bias(bias));
+            }
+        }
+        if !(min < neutral && neutral < max) {
+            return Err(ParamError::NotIncreasing);
+        }
+        Ok(TensionScale { min, neutral, max })
+    }
+
+    /// The bias at tension 0.
+    pub fn min(&self) -> f64 {
+        self.min
+    }
+
+    /// The bias at tension 0.5.
+    pub fn neutral(&self) -> f64 {
+        self.neutral
+    }
+
+    /// The bias at tension 1.
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    /// The bias for a tension, which is clamped to the range 0 to 1.
+    pub fn bias(&self, tension: f64) -> f64 {
+        let t = tension.clamp(0.0, 1.0);
+        if t < 0.5 {
+            self.min + 2.0 * t * (self.neutral - self.min)
+        } else {
+            self.neutral + (2.0 * t - 1.0) * (self.max - self.neutral)
+        }
+    }
+
+    /// The tension for a bias, which is clamped to the range of the scale.
+    pub fn tension(&self, bias: f64) -> f64 {
+        let b = bias.clamp(self.min, self.max);
+        if b < self.neutral {
+            0.5 * (b - self.min) / (self.neutral - self.min)
+        } else {
+            0.5 + 0.5 * (b - self.neutral) / (self.max - self.neutral)
+        }
+    }
+}
+
+/// The reason hyperbezier parameters are out of range.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ParamError {
+    /// A parameter is NaN or infinite.
+    NotFinite,
+    /// A bias is below [`MIN_BIAS`] or not below [`MAX_BIAS`].
+    Bias(f64),
+    /// A curvature has magnitude greater than [`MAX_K`].
+    Curvature(f64),
+    /// The biases of a [`TensionScale`] are not increasing.
+    NotIncreasing,
+}
+
+impl fmt::Display for ParamError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParamError::NotFinite => write!(f, "parameter is not finite"),
+            ParamError::Bias(b) => write!(f, "bias {} is out of range", b),
+            ParamError::Curvature(k) => write!(f, "curvature {} is out of range", k),
+            ParamError::NotIncreasing => write!(f, "tension scale biases are not increasing"),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+/// Options for rendering hyperbeziers to cubic Béziers.
+#[derive(Clone, Copy, Debug)]
+pub struct RenderOptions {
+    /// The number of cubic Béziers per segment, or `None` to choose it
+    /// from `tolerance`.
+    pub subdivisions: Option<usize>,
+    /// The largest error allowed, or `None` to use
+    /// [`HyperBezier::render_subdivisions`].
+    ///
+    /// With [`stable_topology`](RenderOptions::stable_topology), this
+    /// chooses the number of cubic Béziers, as by
+    /// [`HyperBezier::subdivisions_for_tolerance`], relative to the length
+    /// of the chord. Without it, a bare hyperbezier ignores the tolerance,
+    /// while spline segments are subdivided adaptively until they are
+    /// within it, in the units of the path.
+    pub tolerance: Option<f64>,
+    /// The most cubic Béziers per segment.
+    pub max_subdivisions: usize,
+    /// How the subdivisions are placed along the curve.
+    pub parameterization: Parameterization,
+    /// Keep the structure of the output stable as the curve changes
+    /// slightly, such as while dragging.
+    ///
+    /// Unless a count is given, the number of cubic Béziers is the fewest
+    /// that meet `tolerance`, or [`STABLE_TOLERANCE`] of the chord if there
+    /// is none, as a power of 2. It changes only when the curve bends too
+    /// far from what that count renders within the tolerance, or
+    /// straightens enough for half as many, rather than with every change
+    /// to the curvature.
+    ///
+    /// The cubics always cover equal arclengths: `parameterization` is
+    /// ignored, and [`Parameterization::FlatSpeed`] used, so that their
+    /// parameter locations don't move with the biases either. Spline
+    /// segments that happen to be straight are rendered as cubics like any
+    /// other. This costs some accuracy for the same number of cubics.
+    pub stable_topology: bool,
+}
+
+/// How subdivisions are placed along a hyperbezier when rendering.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parameterization {
+    /// Each cubic covers an equal arclength.
+    FlatSpeed,
+    /// Cubics are shorter near an end with high tension (bias above 1).
+    ///
+    /// This is a heuristic based only on the biases, and is the default.
+    EndpointSpeed,
+    /// Cubics are shorter where the curvature is high, so each covers a
+    /// similar combination of arclength and turning.
+    ///
+    /// This is at least as accurate as `EndpointSpeed` for the same number
+    /// of cubics, and much more so at high tension, but costs more to
+    /// place.
+    CurvatureWeighted,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            subdivisions: None,
+            tolerance: None,
+            max_subdivisions: usize::MAX,
+            parameterization: Parameterization::EndpointSpeed,
+            stable_topology: false,
+        }
+    }
+}
+
+/// Parameters for a hyperbezier curve.
+///
+/// A hyperbezier is a curve defined by curvature as a function of arclength.
+/// It is similar to the Spiro curve in this way, but for some of the parameter
+/// space the function is different.
+///
+/// The parameter space is four dimensional. It is broken down symmetrically
+/// into two parameters that predominantly affect one side of the curve, and
+/// the curvature contributions are added:
+///
+/// k(s) = k0 * f(bias0, 1 - s) + k1 * f(bias1, s)
+///
+/// The "f" function takes a bias parameter, which can also be thought of as
+/// tension. This value ranges from about -1 to exactly 2, with 2 representing
+/// a cusp (infinitely high tension at the endpoint). For bias values less than
+/// 1, it is defined thus:
+///
+/// f(bias, s) = s + 6 * (1 - bias) * (s^2 - s^3 - s)
+///
+/// For bias values greater than one, it is defined thus:
+///
+/// f(bias, s) = c * s / (1 + (bias - 1) * s) ^ 2
+///
+/// Here, c is a normalization term chosen so that the integral of f from s=0
+/// to s=1 is 1.
+///
+/// A few observation. If both bias values are 1, then the curve is an Euler
+/// spiral. If both bias values are less than 1, then curvature is a cubic
+/// polynomial as a function of arclength, so it is a Spiro curve.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct HyperBezier {
+    pub k0: f64,
+    pub bias0: f64,
+    pub k1: f64,
+    pub bias1: f64,
+}
+
+/// An intermediate parametrization of the curve family.
+///
+/// Here, angles are given relative to the chord, but the bias parameters
+/// are the same as for `HyperBezier`.
+#[derive(Copy, Clone, Debug)]
+pub struct ThetaParams {
+    pub th0: f64,
+    pub bias0: f64,
+    pub th1: f64,
+    pub bias1: f64,
+}
+
+/// Result of measuring the curve.
+///
+/// The `th0` and `th1` values are defined so that if they are have the
+/// same sign, the curve is convex, but if they are opposite signs, it is
+/// an "s" shape.
+#[derive(Copy, Clone, Debug)]
+pub struct HyperBezierResult<T = f64> {
+    /// Tangent angle from the chord to the curve at the start point.
+    pub th0: T,
+    /// Tangent angle from the chord to the curve at the end point.
+    pub th1: T,
+    /// Length of the chord assuming total arclength = 1.
+    pub chord: T,
+    pub k0: T,
+    pub k1: T,
+}
+
+impl HyperBezier {
+    /// The lowest legal bias value, as [`MIN_BIAS`].
+    pub const MIN_BIAS: f64 = MIN_BIAS;
+
+    /// The bias value of a cusp, as [`MAX_BIAS`].
+    pub const MAX_BIAS: f64 = MAX_BIAS;
+
+    /// The highest bias that [`new_clamped`](HyperBezier::new_clamped)
+    /// leaves, just short of a cusp.
+    pub const MAX_CLAMPED_BIAS: f64 = MAX_BIAS - CUSP_MARGIN;
+
+    /// Create a hyperbezier, checking that the parameters are in range.
+    pub fn new_checked(
+        k0: f64,
+        bias0: f64,
+        k1: f64,
+        bias1: f64,
+    ) -> Result<HyperBezier, ParamError> {
+        for &x in &[k0, bias0, k1, bias1] {
+            if !x.is_finite() {
+                return Err(ParamError::NotFinite);
+            }
+        }
+        for &bias in &[bias0, bias1] {
+            if !(MIN_BIAS..MAX_BIAS).contains(&bias) {
+                return Err(ParamError::Bias(bias));
+            }
+        }
+        for &k in &[k0, k1] {
+            if k.abs() > MAX_K {
+                return Err(ParamError::Curvature(k));
+            }
+        }
+        Ok(HyperBezier {
+            k0,
+            bias0,
+            k1,
+            bias1,
+        })
+    }
+
+    /// Create a hyperbezier, clamping the parameters into range.
+    ///
+    /// Curvatures that are not finite become zero, and biases that are not
+    /// finite become 1.
+    pub fn new_clamped(k0: f64, bias0: f64, k1: f64, bias1: f64) -> HyperBezier {
+        let clamp_k = |k: f64| {
+            if k.is_finite() {
+                k.clamp(-MAX_K, MAX_K)
+            } else {
+                0.0
+            }
+        };
+        let clamp_bias = |bias: f64| {
+            if bias.is_finite() {
+                bias.clamp(MIN_BIAS, HyperBezier::MAX_CLAMPED_BIAS)
+            } else {
+                1.0
+            }
+        };
+        HyperBezier {
+            k0: clamp_k(k0),
+            bias0: clamp_bias(bias0),
+            k1: clamp_k(k1),
+            bias1: clamp_bias(bias1),
+        }
+    }
+
+    /// Compute the angle for the given parameter.
+    ///
+    /// The argument is an arclength parametrization, ranging from 0 to 1.
+    ///
+    /// The returned angle is relative only, in other words there could be an
+    /// arbitrary rotation of the entire curve.
+    pub fn compute_theta(&self, s: f64) -> f64 {
+        theta(self.params(), s)
+    }
+
+    /// Compute the endpoint tangent angles and the chord length.
+    pub fn compute(&self) -> HyperBezierResult {
+        measure(self.params())
+    }
+
+    fn params(&self) -> [f64; 4] {
+        [self.k0, self.bias0, self.k1, self.bias1]
+    }
+
+    /// Integrate the unit tangent from `t0` to `t1`, giving the displacement.
+    ///
+    /// The integral is done by Gauss-Legendre quadrature. Available orders
+    /// are 3, 5, 7, 9, 11 and 24; other orders are rounded up to one of
+    /// these, and orders above 24 use 24.
+    pub fn integrate(&self, t0: f64, t1: f64, order: usize) -> Vec2 {
+        let c = gauss_legendre_coeffs(order);
+        let mut result = Vec2::ZERO;
+        let tm = 0.5 * (t1 + t0);
+        let dt = 0.5 * (t1 - t0);
+        for (wi, xi) in c {
+            let t = tm + dt * xi;
+            let th = self.compute_theta(t);
+            result += *wi * Vec2::from_angle(th);
+        }
+        dt * result
+    }
+
+    /// Render to a [`BezPath`].
+    pub fn render(&self, n: usize) -> BezPath {
+        self.render_elements(n).collect()
+    }
+
+    /// Render to bezier elements.
+    ///
+    /// This uses `n` subdivisions and the default parameterization; see
+    /// [`HyperBezier::render_elements_with`] for more control.
+    pub fn render_elements(&self, n: usize) -> impl Iterator<Item = PathEl> + '_ {
+        let options = RenderOptions {
+            subdivisions: Some(n),
+            ..RenderOptions::default()
+        };
+        self.render_elements_with(&options)
+    }
+
+    /// Render to bezier elements, with the given options.
+    ///
+    /// The curve is divided into pieces, each rendered as a cubic Bézier
+    /// whose control points lie along the tangents at its ends. Unless a
+    /// count is given, the number of pieces comes from the tolerance when
+    /// the topology is kept stable, and from a simple heuristic otherwise.
+    pub fn render_elements_with(
+        &self,
+        options: &RenderOptions,
+    ) -> impl Iterator<Item = PathEl> + '_ {
+        let order = 24;
+        let v = self.integrate(0.0, 1.0, order);
+        let a = Affine::new([v.x, v.y, -v.y, v.x, 0.0, 0.0]).inverse();
+        let (n, parameterization) = match (options.subdivisions, options.stable_topology) {
+            (Some(n), false) => (n, options.parameterization),
+            (None, false) => (self.render_subdivisions(), options.parameterization),
+            (Some(n), true) => (n, Parameterization::FlatSpeed),
+            (None, true) => {
+                let tolerance = options.tolerance.unwrap_or(STABLE_TOLERANCE);
+                let parameterization = Parameterization::FlatSpeed;
+                (
+                    self.subdivisions_for_tolerance(tolerance, parameterization),
+                    parameterization,
+                )
+            }
+        };
+        let n = n.min(options.max_subdivisions).max(1);
+        let step = 1.0 / (n as f64);
+        // Pairs of arclength parameter and its derivative, at each boundary.
+        let mapping = self.t_mapping(n, parameterization);
+        let mut last_p = Point::ZERO;
+        let mut last_v =
+            step * (1.0 / 3.0) * mapping[0].1 * Vec2::from_angle(self.compute_theta(0.0));
+        let mut i = 0;
+        let mut first = Some(PathEl::MoveTo(last_p));
+        std::iter::from_fn(move || {
+            if let Some(first) = first.take() {
+                return Some(first);
+            }
+            i += 1;
+            if i <= n {
+                let (t, dt) = mapping[i];
+                let p = self.integrate(0.0, t, order).to_point();
+                let p1 = last_p + last_v;
+                let v = step * (1.0 / 3.0) * dt * Vec2::from_angle(self.compute_theta(t));
+                let p2 = p - v;
+                let next = PathEl::CurveTo(a * p1, a * p2, a * p);
+                last_v = v;
+                last_p = p;
+                Some(next)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Map `n` equal subdivisions to arclength parameters.
+    ///
+    /// Returns the parameter and its derivative at each of the `n + 1`
+    /// boundaries.
+    fn t_mapping(&self, n: usize, parameterization: Parameterization) -> Vec<(f64, f64)> {
+        let step = 1.0 / (n as f64);
+        match parameterization {
+            Parameterization::FlatSpeed => (0..=n).map(|i| (i as f64 * step, 1.0)).collect(),
+            Parameterization::EndpointSpeed => {
+                fn calc_t(bias: f64) -> f64 {
+                    if bias >= 1.0 {
+                        (2.0 - bias).sqrt() * (1.0 / 3.0)
+                    } else {
+                        // Possibly this should increase for low tension curves, but that's not
+                        // obvious.
+                        1.0 / 3.0
+                    }
+                }
+                let t1 = calc_t(self.bias0);
+                let t2 = 1.0 - calc_t(self.bias1);
+                (0..=n)
+                    .map(|i| {
+                        let u = (i as f64) * step;
+                        let um = 1.0 - u;
+                        let t = 3.0 * u * um * (um * t1 + u * t2) + u.powi(3);
+                        let dt = um * um * t1 + 2.0 * u * um * (t2 - t1) + u * u * (1.0 - t2);
+                        (t, 3.0 * dt)
+                    })
+                    .collect()
+            }
+            Parameterization::CurvatureWeighted => {
+                // Tabulate a density which is 1 plus a weighted amount of
+                // turning per unit arclength, and place the subdivisions at
+                // equal steps of its integral. The density is interpolated
+                // linearly, so the speeds at the subdivisions are smooth.
+                const M: usize = 64;
+                let h = 1.0 / M as f64;
+                let w: Vec<f64> = (0..=M)
+                    .map(|j| {
+                        let t = j as f64 * h;
+                        let (a, b) = ((t - 0.5 * h).max(0.0), (t + 0.5 * h).min(1.0));
+                        let k = (self.compute_theta(b) - self.compute_theta(a)) / (b - a);
+                        1.0 + CURVATURE_WEIGHT * k.abs()
+                    })
+                    .collect();
+                let mut cum = vec![0.0; M + 1];
+                for j in 0..M {
+                    cum[j + 1] = cum[j] + 0.5 * h * (w[j] + w[j + 1]);
+                }
+                let total = cum[M];
+                let mut j = 0;
+                (0..=n)
+                    .map(|i| {
+                        let target = i as f64 * step * total;
+                        while j + 1 < M && cum[j + 1] < target {
+                            j += 1;
+                        }
+                        let (w0, w1) = (w[j], w[j + 1]);
+                        let dc = target - cum[j];
+                        // Invert the integral of the linear density in this cell.
+                        let a = 0.5 * (w1 - w0) / h;
+                        let x = if a.abs() < 1e-12 {
+                            dc / w0
+                        } else {
+                            (-w0 + (w0 * w0 + 4.0 * a * dc).max(0.0).sqrt()) / (2.0 * a)
+                        };
+                        let x = x.clamp(0.0, h);
+                        let wt = w0 + (w1 - w0) * x / h;
+                        (j as f64 * h + x, total / wt)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Suggest a number of subdivisions for rendering.
+    ///
+    /// This is a bit of a hacky heuristic.
+    pub fn render_subdivisions(&self) -> usize {
+        2 + (self.k0.abs() + self.k1.abs()).floor() as usize
+    }
+
+    /// The number of subdivisions needed to render within a tolerance.
+    ///
+    /// The tolerance is relative to the length of the chord. The result is
+    /// a power of 2, at most 64. The error is estimated at the middle of
+    /// each cubic Bézier, normal to the curve.
+    pub fn subdivisions_for_tolerance(
+        &self,
+        tolerance: f64,
+        parameterization: Parameterization,
+    ) -> usize {
+        const MAX_SUBDIVISIONS: usize = 64;
+        let order = 24;
+        let chord = self.integrate(0.0, 1.0, order).hypot();
+        let mut n = 1;
+        while n < MAX_SUBDIVISIONS {
+            // The mapping at twice the resolution includes the middles.
+            let mapping = self.t_mapping(2 * n, parameterization);
+            let step = 1.0 / (n as f64);
+            let within = (0..n).all(|i| {
+                let (t0, dt0) = mapping[2 * i];
+                let (t_mid, _) = mapping[2 * i + 1];
+                let (t1, dt1) = mapping[2 * i + 2];
+                let p0 = self.integrate(0.0, t0, order).to_point();
+                let p3 = p0 + self.integrate(t0, t1, order);
+                let v0 = step * (1.0 / 3.0) * dt0 * Vec2::from_angle(self.compute_theta(t0));
+                let v1 = step * (1.0 / 3.0) * dt1 * Vec2::from_angle(self.compute_theta(t1));
+                let mid = CubicBez::new(p0, p0 + v0, p3 - v1, p3).eval(0.5);
+                let exact = p0 + self.integrate(t0, t_mid, order);
+                let normal =
+                    Vec2::from_angle(self.compute_theta(t_mid) + 0.5 * std::f64::consts::PI);
+                (mid - exact).dot(normal).abs() <= tolerance * chord
+            });
+            if within {
+                break;
+            }
+            n *= 2;
+        }
+        n
+    }
+
+    /// Solve for curve params, given theta params.
+    pub fn solve_for_theta(params: &ThetaParams) -> HyperBezier {
+        Self::solve_for_theta_mod(params, false)
+    }
+
+    /// Solve for curve params, given theta params, for a curve that turns
+    /// by more than a full turn.
+    ///
+    /// The difference of the end angles is only known up to 4pi from the
+    /// chord angle. [`solve_for_theta`](HyperBezier::solve_for_theta)
+    /// matches it up to 2pi, which is enough for curves within a turn,
+    /// but can settle on a curve with both end tangents reversed once the
+    /// angles include whole turns.
+    pub fn solve_for_theta_wound(params: &ThetaParams) -> HyperBezier {
+        Self::solve_for_theta_mod(params, true)
+    }
+
+    /// Solve for curve params, matching the difference of the end angles
+    /// up to 4pi if `wound`, or 2pi if not.
+    fn solve_for_theta_mod(params: &ThetaParams, wound: bool) -> HyperBezier {
+        let ThetaParams {
+            th0,
+            bias0,
+            th1,
+            bias1,
+        } = *params;
+        let mut dth = 0.0;
+        let mut lastxy: Option<(f64, f64)> = None;
+        const N: usize = 10;
+        for i in 0..N {
+            let params = HyperBezier::new_clamped(th0 + 0.5 * dth, bias0, th1 - 0.5 * dth, bias1);
+            if i == N - 1 {
+                return params;
+            }
+            let result = params.compute();
+            let err = th0 - th1 - (result.th0 - result.th1);
+            let th_err = if wound {
+                2.0 * mod_tau(0.5 * err)
+            } else {
+                mod_tau(err)
+            };
+            if th_err.abs() < 1e-3 {
+                return params;
+            }
+            // Secant method
+            let nextxy = (dth, th_err);
+            let delta = if let Some(lastxy) = lastxy {
+                (nextxy.0 - lastxy.0) / (nextxy.1 - lastxy.1)
+            } else {
+                -0.5
+            };
+            dth -= delta * th_err;
+            lastxy = Some(nextxy);
+        }
+        unreachable!()
+    }
+
+    /// Solve for curve params, given bezier control points.
+    ///
+    /// The points are given relative to p0 at (0, 0) and p3 at
+    /// (1, 0).
+    pub fn solve(p1: Point, p2: Point) -> HyperBezier {
+        let (th0, bias0) = Self::params_for_v(p1.to_vec2());
+        let (th1, bias1) = Self::params_for_v(Point::new(1.0, 0.0) - p2);
+        // TODO: signs feel reversed here, but it all works out in the end.
+        let theta_params = ThetaParams {
+            th0: -th0,
+            bias0,
+            th1,
+            bias1,
+        };
+        Self::solve_for_theta(&theta_params)
+    }
+
+    /// Determine params for a control arm.
+    ///
+    /// The arm is relative to a chord from (0, 0) to (1, 0). Return values
+    /// are theta and bias.
+    ///
+    /// Together with [`v_for_params`](HyperBezier::v_for_params), this is a
+    /// bijection between arms and pairs of theta in `-PI..=PI` and bias in
+    /// `MIN_BIAS..=MAX_BIAS`, with the zero arm at `MAX_BIAS`. In floating
+    /// point, precision is lost for arms pointing almost exactly backwards
+    /// along the chord, which approach `MAX_BIAS`, and for arms much longer
+    /// than the chord, which approach `MIN_BIAS`.
+    pub fn params_for_v(v: Vec2) -> (f64, f64) {
+        let th = v.atan2();
+        // This formula ensures that bezier parameters approximating
+        // a circular arc map to a bias of 1.0.
+        let a = v.hypot() * 1.5 * arm_scale(th);
+        let bias = if a < 1.0 {
+            2.0 - a * a
+        } else {
+            1.0 + 2.0 * (0.5 * (1.0 - a)).tanh()
+        };
+        (th, bias)
+    }
+
+    /// Determine control arm position from params.
+    ///
+    /// This is the inverse of [`params_for_v`](HyperBezier::params_for_v).
+    /// Bias values outside `MIN_BIAS..=MAX_BIAS` are clamped to it.
+    pub fn v_for_params(th: f64, bias: f64) -> Vec2 {
+        let bias = bias.clamp(MIN_BIAS, MAX_BIAS);
+        let a = if bias >= 1.0 {
+            (2.0 - bias).sqrt()
+        } else {
+            // At MIN_BIAS the arm would be infinitely long.
+            let x = (0.5 * (bias - 1.0)).max(-1.0 + ARM_EPSILON);
+            1.0 - 2.0 * x.atanh()
+        };
+        let len = a / (1.5 * arm_scale(th));
+        len * Vec2::from_angle(th)
+    }
+}
+
+/// Keeps the mapping between control arms and parameters finite and
+/// invertible at its extremes.
+const ARM_EPSILON: f64 = 1e-12;
+
+/// The factor relating a control arm's length to its bias at angle `th`.
+///
+/// This is zero for an arm pointing back along the chord, so it is kept
+/// just above zero there.
+fn arm_scale(th: f64) -> f64 {
+    (th.cos() + 1.0).max(ARM_EPSILON)
+}
+
+const MAX_A: f64 = MAX_BIAS - 1.0 - CUSP_MARGIN;
+
+/// The Gauss-Legendre weights and abscissae of the lowest available order
+/// at least `order`, or of the highest.
+fn gauss_legendre_coeffs(order: usize) -> &'static [(f64, f64)] {
+    match order {
+        0..=3 => coeffs::GAUSS_LEGENDRE_COEFFS_3,
+        4..=5 => coeffs::GAUSS_LEGENDRE_COEFFS_5,
+        6..=7 => coeffs::GAUSS_LEGENDRE_COEFFS_7,
+        8..=9 => coeffs::GAUSS_LEGENDRE_COEFFS_9,
+        10..=11 => coeffs::GAUSS_LEGENDRE_COEFFS_11,
+        _ => coeffs::GAUSS_LEGENDRE_COEFFS_24,
+    }
+}
+
+/// Normalize an angle to the range -PI..PI.
+pub fn mod_tau(x: f64) -> f64 {
+    x - std::f64::consts::TAU * (x * (1.0 / std::f64::consts::TAU)).round()
+}
+
+/// The tangent angle at `s`, for the parameters `[k0, bias0, k1, bias1]`.
+fn theta<T: Scalar>(params: [T; 4], s: T) -> T {
+    let [k0, bias0, k1, bias1] = params;
+    k1 * integrate_basis(bias1, s) - k0 * integrate_basis(bias0, T::from_f64(1.0) - s)
+}
+
+/// Measure the curve with parameters `[k0, bias0, k1, bias1]`.
+fn measure<T: Scalar>(params: [T; 4]) -> HyperBezierResult<T> {
+    let [k0, bias0, k1, bias1] = params;
+    let c = T::from_f64;
+    // Integrate the unit tangent over the whole curve, as in `integrate`.
+    let (mut x, mut y) = (c(0.0), c(0.0));
+    for (wi, xi) in coeffs::GAUSS_LEGENDRE_COEFFS_24 {
+        let th = theta(params, c(0.5 + 0.5 * xi));
+        x = x + c(*wi) * th.cos();
+        y = y + c(*wi) * th.sin();
+    }
+    let (x, y) = (c(0.5) * x, c(0.5) * y);
+    let th_chord = y.atan2(x);
+    let chord = x.hypot(y);
+    HyperBezierResult {
+        th0: th_chord - theta(params, c(0.0)),
+        th1: theta(params, c(1.0)) - th_chord,
+        chord,
+        k0: chord * k0 * endpoint_k(bias0),
+        k1: chord * k1 * endpoint_k(bias1),
+    }
+}
+
+/// Compute integral of basis function.
+///
+/// The integral of the basis function can be represented as a reasonably
+/// simple closed-form analytic formula.
+///
+/// Note: this is normalized so that f(1) - f(0) = 1.
+///
+/// This is oriented for the rightmost control point.
+fn integrate_basis<T: Scalar>(bias: T, s: T) -> T {
+    let c = T::from_f64;
+    if bias.re() <= 1.0 {
+        let iy0 = c(4.0) * s.powi(3) - c(3.0) * s.powi(4);
+        let iy1 = s.powi(2);
+        iy0 + bias * (iy1 - iy0)
+    } else if bias.re() < 1.0002 {
+        // This is a more numerically robust approximation to the
+        // exact analytical formula in the next clause.
+        let b = (bias - c(1.0)) * c(4.0 / 3.0);
+        (c(1.0) - b) * s.powi(2) + b * s.powi(3)
+    } else {
+        let a = clamp_a(bias - c(1.0));
+        let norm = c(1.0) / (c(1.0) - a) + (c(1.0) - a).ln() - c(1.0);
+        (c(1.0) / (c(1.0) - a * s) + (c(1.0) - a * s).ln() - c(1.0)) / norm
+    }
+}
+
+/// Limit `a` to [`MAX_A`], away from the cusp.
+fn clamp_a<T: Scalar>(a: T) -> T {
+    if a.re() > MAX_A {
+        T::from_f64(MAX_A)
+    } else {
+        a
+    }
+}
+
+/// Compute curvature at endpoint.
+pub fn compute_k(bias: f64) -> f64 {
+    endpoint_k(bias)
+}
+
+fn endpoint_k<T: Scalar>(bias: T) -> T {
+    let c = T::from_f64;
+    if bias.re() <= 1.0 {
+        bias * c(2.0)
+    } else if bias.re() < 1.0007 {
+        let a = bias - c(1.0);
+        // A few terms of the Taylors series expansion of the formula below.
+        c(2.0) + c(4.0 / 3.0) * a + c(11.0 / 9.0) * a * a
+    } else {
+        let a = clamp_a(bias - c(1.0));
+        // Reciprocal of integral
+        let sr = (a * a) / (c(1.0) / (c(1.0) - a) + (c(1.0) - a).ln() - c(1.0));
+        sr / (c(1.0) - a).powi(2)
+    }
+}
+
+/// Compute bias given relative endpoint curvature.
+///
+/// This is the inverse of compute_k.
+pub fn compute_k_inv(k: f64) -> f64 {
+    if k <= 2.0 {
+        k * 0.5
+    } else {
+        // We'll solve this by bisection for now, just for simplicity.
+        // I'm sure there are much better approximations.
+        let mut est_lo = 2.0 - 2.0 / k;
+        let mut est_hi = 2.0 - 1.0 / k;
+        const N: usize = 20;
+        for _ in 0..N {
+            let est = 0.5 * (est_lo + est_hi);
+            if compute_k(est) > k {
+                est_hi = est;
+            } else {
+                est_lo = est;
+            }
+        }
+        0.5 * (est_lo + est_hi)
+    }
+}
+
+#[test]
+fn test_k() {
+    for k in &[0.0, 1.0, 2.0, 2.000001, 3.0, 5.0, 10.0, 20.0] {
+        let bias = compute_k_inv(*k);
+        let actual_k = compute_k(bias);
+        assert!((k - actual_k).abs() < 1e-5);
+        //println!("{}, {}, {}", k, bias, actual_k);
+    }
+}
+
+#[test]
+fn test_params_for_v_round_trip() {
+    use std::f64::consts::PI;
+    // Arms pointing straight back along the chord are not recoverable.
+    for i in 1..40 {
+        let th = -PI + PI * i as f64 / 20.0;
+        // Arms from a thousandth of the chord to three times it.
+        for j in 0..=40 {
+            let len = 3.0 * 10f64.powf(-3.0 * j as f64 / 40.0);
+            let v = len * Vec2::from_angle(th);
+            let (th1, bias) = HyperBezier::params_for_v(v);
+            assert!((MIN_BIAS..=MAX_BIAS).contains(&bias));
+            let v1 = HyperBezier::v_for_params(th1, bias);
+            assert!((v1 - v).hypot() < 1e-6 * len, "{:?} {:?}", v, v1);
+        }
+        for j in 0..=40 {
+            let bias = MIN_BIAS + 0.01 + (MAX_BIAS - MIN_BIAS - 0.02) * j as f64 / 40.0;
+            let (th1, bias1) = HyperBezier::params_for_v(HyperBezier::v_for_params(th, bias));
+            assert!((bias1 - bias).abs() < 1e-6, "{} {} {}", th, bias, bias1);
+            assert!((th1 - th).abs() < 1e-9);
+        }
+    }
+    // Out of range values give finite arms.
+    for &(th, bias) in &[(PI, 1.0), (0.0, MIN_BIAS), (0.0, -5.0), (1.0, 3.0)] {
+        let v = HyperBezier::v_for_params(th, bias);
+        assert!(v.x.is_finite() && v.y.is_finite(), "{} {}", th, bias);
+    }
+}
+
+#[test]
+fn test_new_checked() {
+    assert!(HyperBezier::new_checked(1.0, 1.0, -1.0, 0.5).is_ok());
+    assert_eq!(
+        HyperBezier::new_checked(1.0, MAX_BIAS, 1.0, 1.0).unwrap_err(),
+        ParamError::Bias(MAX_BIAS)
+    );
+    assert_eq!(
+        HyperBezier::new_checked(1.0, 1.0, 1.0, -1.5).unwrap_err(),
+        ParamError::Bias(-1.5)
+    );
+    assert_eq!(
+        HyperBezier::new_checked(1e6, 1.0, 1.0, 1.0).unwrap_err(),
+        ParamError::Curvature(1e6)
+    );
+    assert_eq!(
+        HyperBezier::new_checked(f64::NAN, 1.0, 1.0, 1.0).unwrap_err(),
+        ParamError::NotFinite
+    );
+    let hb = HyperBezier::new_clamped(1e6, 3.0, f64::NAN, -5.0);
+    assert!(HyperBezier::new_checked(hb.k0, hb.bias0, hb.k1, hb.bias1).is_ok());
+}
+
+#[test]
+fn test_integrate_orders() {
+    let hb = HyperBezier::new_clamped(1.0, 1.2, -0.5, 0.8);
+    // Orders without coefficients round up, or use the highest.
+    assert_eq!(hb.integrate(0.0, 1.0, 4), hb.integrate(0.0, 1.0, 5));
+    assert_eq!(hb.integrate(0.0, 1.0, 0), hb.integrate(0.0, 1.0, 3));
+    assert_eq!(hb.integrate(0.0, 1.0, 100), hb.integrate(0.0, 1.0, 24));
+}
+
+#[test]
+fn test_curvature_weighted() {
+    use kurbo::{Line, ParamCurve, ParamCurveNearest};
+    fn sample(hb: &HyperBezier, n: usize, parameterization: Parameterization) -> Vec<Point> {
+        let options = RenderOptions {
+            subdivisions: Some(n),
+            parameterization,
+            ..RenderOptions::default()
+        };
+        let path: BezPath = hb.render_elements_with(&options).collect();
+        path.segments()
+            .flat_map(|seg| (0..8).map(move |i| seg.eval(i as f64 / 8.0)))
+            .collect()
+    }
+    // A high tension curve, which is nearly straight except near the ends.
+    let hb = HyperBezier::new_clamped(1.0, 1.9, 1.0, 1.9);
+    let exact = sample(&hb, 64, Parameterization::FlatSpeed);
+    let error = |parameterization| {
+        sample(&hb, 4, parameterization)
+            .into_iter()
+            .map(|p| {
+                exact
+                    .windows(2)
+                    .map(|w| Line::new(w[0], w[1]).nearest(p, 1e-9).distance_sq.sqrt())
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0.0, f64::max)
+    };
+    let endpoint = error(Parameterization::EndpointSpeed);
+    let weighted = error(Parameterization::CurvatureWeighted);
+    assert!(weighted < 0.5 * endpoint, "{} vs {}", weighted, endpoint);
+}
+
+#[cfg(feature = "research")]
+#[test]
+fn test_dual_derivatives() {
+    use research::Dual;
+    let params = [1.5, 1.3, -0.5, 0.8];
+    for i in 0..4 {
+        let mut dual = [Dual::constant(0.0); 4];
+        for j in 0..4 {
+            dual[j] = if i == j {
+                Dual::var(params[j])
+            } else {
+                Dual::constant(params[j])
+            };
+        }
+        let exact = research::compute(dual);
+        let h = 1e-6;
+        let (mut lo, mut hi) = (params, params);
+        lo[i] -= h;
+        hi[i] += h;
+        let lo = research::compute(lo);
+        let hi = research::compute(hi);
+        let fd = (hi.th0 - lo.th0) / (2.0 * h);
+        assert!(
+            (exact.th0.eps - fd).abs() < 1e-6,
+            "{} vs {}",
+            exact.th0.eps,
+            fd
+        );
+        let fd = (hi.chord - lo.chord) / (2.0 * h);
+        assert!(
+            (exact.chord.eps - fd).abs() < 1e-6,
+            "{} vs {}",
+            exact.chord.eps,
+            fd
+        );
+        assert_eq!(
+            exact.th0.re,
+            HyperBezier::new_clamped(1.5, 1.3, -0.5, 0.8).compute().th0
+        );
+    }
+}
+
+#[test]
+fn test_tension_scale() {
+    let scale = TensionScale::default();
+    assert_eq!(scale.bias(0.0), MIN_MATCH_BIAS);
+    assert_eq!(scale.bias(0.5), 1.0);
+    assert_eq!(scale.bias(1.0), HyperBezier::MAX_CLAMPED_BIAS);
+    assert_eq!(scale.bias(2.0), HyperBezier::MAX_CLAMPED_BIAS);
+    for i in 0..=10 {
+        let t = i as f64 / 10.0;
+        assert!((scale.tension(scale.bias(t)) - t).abs() < 1e-12);
+    }
+    assert_eq!(
+        TensionScale::new(0.0, 0.0, 1.5).unwrap_err(),
+        ParamError::NotIncreasing
+    );
+    assert_eq!(
+        TensionScale::new(0.0, 1.0, MAX_BIAS).unwrap_err(),
+        ParamError::Bias(MAX_BIAS)
+    );
+    let scale = TensionScale::new(0.0, 0.5, 1.5).unwrap();
+    assert_eq!(scale.tension(0.0), 0.0);
+    assert_eq!(scale.tension(1.0), 0.75);
+}
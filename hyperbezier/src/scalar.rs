@@ -0,0 +1,204 @@
+//! Scalars the curve math can be evaluated over.
+//!
+//! The math is written once, generic over [`Scalar`], and used with `f64`
+//! for ordinary evaluation. With the `research` feature, it can also be
+//! evaluated with [`Dual`] numbers, giving exact derivatives.
+
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A real number type the curve math can be evaluated over.
+///
+/// Branches in the math depend only on [`Scalar::re`], so derivatives are
+/// those of the branch taken.
+pub trait Scalar:
+    Copy
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Neg<Output = Self>
+{
+    fn from_f64(x: f64) -> Self;
+    /// The real part, which is the value itself for `f64`.
+    fn re(self) -> f64;
+    fn powi(self, n: i32) -> Self;
+    fn ln(self) -> Self;
+    fn sin(self) -> Self;
+    fn cos(self) -> Self;
+    fn atan2(self, x: Self) -> Self;
+    fn hypot(self, other: Self) -> Self;
+}
+
+impl Scalar for f64 {
+    fn from_f64(x: f64) -> f64 {
+        x
+    }
+
+    fn re(self) -> f64 {
+        self
+    }
+
+    fn powi(self, n: i32) -> f64 {
+        f64::powi(self, n)
+    }
+
+    fn ln(self) -> f64 {
+        f64::ln(self)
+    }
+
+    fn sin(self) -> f64 {
+        f64::sin(self)
+    }
+
+    fn cos(self) -> f64 {
+        f64::cos(self)
+    }
+
+    fn atan2(self, x: f64) -> f64 {
+        f64::atan2(self, x)
+    }
+
+    fn hypot(self, other: f64) -> f64 {
+        f64::hypot(self, other)
+    }
+}
+
+/// A dual number, a value with its derivative along one direction.
+///
+/// Arithmetic on dual numbers carries the derivative along exactly, so
+/// evaluating a function with `eps` set to 1 on one input gives the
+/// derivative with respect to that input.
+#[cfg(feature = "research")]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dual {
+    pub re: f64,
+    pub eps: f64,
+}
+
+#[cfg(feature = "research")]
+impl Dual {
+    /// A variable, with a derivative of 1.
+    pub fn var(re: f64) -> Dual {
+        Dual { re, eps: 1.0 }
+    }
+
+    /// A constant, with a derivative of 0.
+    pub fn constant(re: f64) -> Dual {
+        Dual { re, eps: 0.0 }
+    }
+}
+
+#[cfg(feature = "research")]
+impl Add for Dual {
+    type Output = Dual;
+
+    fn add(self, other: Dual) -> Dual {
+        Dual {
+            re: self.re + other.re,
+            eps: self.eps + other.eps,
+        }
+    }
+}
+
+#[cfg(feature = "research")]
+impl Sub for Dual {
+    type Output = Dual;
+
+    fn sub(self, other: Dual) -> Dual {
+        Dual {
+            re: self.re - other.re,
+            eps: self.eps - other.eps,
+        }
+    }
+}
+
+#[cfg(feature = "research")]
+impl Mul for Dual {
+    type Output = Dual;
+
+    fn mul(self, other: Dual) -> Dual {
+        Dual {
+            re: self.re * other.re,
+            eps: self.re * other.eps + self.eps * other.re,
+        }
+    }
+}
+
+#[cfg(feature = "research")]
+impl Div for Dual {
+    type Output = Dual;
+
+    fn div(self, other: Dual) -> Dual {
+        Dual {
+            re: self.re / other.re,
+            eps: (self.eps * other.re - self.re * other.eps) / (other.re * other.re),
+        }
+    }
+}
+
+#[cfg(feature = "research")]
+impl Neg for Dual {
+    type Output = Dual;
+
+    fn neg(self) -> Dual {
+        Dual {
+            re: -self.re,
+            eps: -self.eps,
+        }
+    }
+}
+
+#[cfg(feature = "research")]
+impl Scalar for Dual {
+    fn from_f64(x: f64) -> Dual {
+        Dual::constant(x)
+    }
+
+    fn re(self) -> f64 {
+        self.re
+    }
+
+    fn powi(self, n: i32) -> Dual {
+        Dual {
+            re: self.re.powi(n),
+            eps: n as f64 * self.re.powi(n - 1) * self.eps,
+        }
+    }
+
+    fn ln(self) -> Dual {
+        Dual {
+            re: self.re.ln(),
+            eps: self.eps / self.re,
+        }
+    }
+
+    fn sin(self) -> Dual {
+        Dual {
+            re: self.re.sin(),
+            eps: self.re.cos() * self.eps,
+        }
+    }
+
+    fn cos(self) -> Dual {
+        Dual {
+            re: self.re.cos(),
+            eps: -self.re.sin() * self.eps,
+        }
+    }
+
+    fn atan2(self, x: Dual) -> Dual {
+        let r2 = self.re * self.re + x.re * x.re;
+        Dual {
+            re: self.re.atan2(x.re),
+            eps: (x.re * self.eps - self.re * x.eps) / r2,
+        }
+    }
+
+    fn hypot(self, other: Dual) -> Dual {
+        let re = self.re.hypot(other.re);
+        Dual {
+            re,
+            eps: (self.re * self.eps + other.re * other.eps) / re,
+        }
+    }
+}
@@ -0,0 +1,114 @@
+//! Evaluating hyperbeziers on the GPU.
+//!
+//! [`wgsl`] generates WGSL functions computing the tangent angle and
+//! integrating the unit tangent, matching [`HyperBezier::compute_theta`]
+//! and [`HyperBezier::integrate`], for flattening or stroking in a shader.
+//! Curves are passed to the shader as a `vec4<f32>` of parameters, packed
+//! with [`pack`].
+//!
+//! The shader works in `f32`, so results agree with the CPU to single
+//! precision.
+
+use std::fmt::Write;
+
+use crate::{gauss_legendre_coeffs, HyperBezier, MAX_A};
+
+/// Pack the parameters of a curve as `[k0, bias0, k1, bias1]`, the layout
+/// of the `vec4<f32>` the shader functions take.
+pub fn pack(hb: &HyperBezier) -> [f32; 4] {
+    [hb.k0 as f32, hb.bias0 as f32, hb.k1 as f32, hb.bias1 as f32]
+}
+
+/// Generate the WGSL functions.
+///
+/// This defines `hb_theta(p: vec4<f32>, s: f32) -> f32` and
+/// `hb_integrate(p: vec4<f32>, t0: f32, t1: f32) -> vec2<f32>`, with the
+/// integral done by Gauss-Legendre quadrature of the given `order`, which
+/// is rounded up to an available one as in [`HyperBezier::integrate`]. All
+/// other names it defines start with `hb_` or `HB_`.
+pub fn wgsl(order: usize) -> String {
+    let c = gauss_legendre_coeffs(order);
+    let order = c.len();
+    let list = |f: fn(&(f64, f64)) -> f64| {
+        c.iter()
+            .map(|x| format!("{:?}", f(x)))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "// Hyperbezier evaluation, generated by the hyperbezier crate."
+    );
+    let _ = writeln!(out);
+    let _ = writeln!(out, "const HB_MAX_A: f32 = {:?};", MAX_A);
+    let _ = writeln!(out, "const HB_ORDER: u32 = {}u;", order);
+    let _ = writeln!(
+        out,
+        "var<private> HB_GL_W: array<f32, {}> = array<f32, {}>({});",
+        order,
+        order,
+        list(|x| x.0)
+    );
+    let _ = writeln!(
+        out,
+        "var<private> HB_GL_X: array<f32, {}> = array<f32, {}>({});",
+        order,
+        order,
+        list(|x| x.1)
+    );
+    out.push_str(FUNCTIONS);
+    out
+}
+
+/// The functions, which mirror `integrate_basis`, `theta` and `integrate`.
+const FUNCTIONS: &str = r#"
+fn hb_integrate_basis(bias: f32, s: f32) -> f32 {
+    if bias <= 1.0 {
+        let iy0 = 4.0 * s * s * s - 3.0 * s * s * s * s;
+        let iy1 = s * s;
+        return iy0 + bias * (iy1 - iy0);
+    } else if bias < 1.0002 {
+        let b = (bias - 1.0) * (4.0 / 3.0);
+        return (1.0 - b) * s * s + b * s * s * s;
+    }
+    let a = min(bias - 1.0, HB_MAX_A);
+    let norm = 1.0 / (1.0 - a) + log(1.0 - a) - 1.0;
+    return (1.0 / (1.0 - a * s) + log(1.0 - a * s) - 1.0) / norm;
+}
+
+fn hb_theta(p: vec4<f32>, s: f32) -> f32 {
+    return p.z * hb_integrate_basis(p.w, s) - p.x * hb_integrate_basis(p.y, 1.0 - s);
+}
+
+fn hb_integrate(p: vec4<f32>, t0: f32, t1: f32) -> vec2<f32> {
+    let tm = 0.5 * (t1 + t0);
+    let dt = 0.5 * (t1 - t0);
+    var result = vec2<f32>(0.0, 0.0);
+    for (var i = 0u; i < HB_ORDER; i++) {
+        let th = hb_theta(p, tm + dt * HB_GL_X[i]);
+        result += HB_GL_W[i] * vec2<f32>(cos(th), sin(th));
+    }
+    return dt * result;
+}
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::{pack, wgsl};
+    use crate::HyperBezier;
+
+    #[test]
+    fn generated_wgsl() {
+        let src = wgsl(11);
+        for name in &["fn hb_theta(", "fn hb_integrate(", "HB_GL_W", "HB_MAX_A"] {
+            assert!(src.contains(name), "{}", name);
+        }
+        assert_eq!(src.matches('{').count(), src.matches('}').count());
+        // Eleven weights and abscissae, with a decimal point each.
+        let line = src.lines().find(|l| l.contains("HB_GL_X")).unwrap();
+        assert_eq!(line.matches('.').count(), 11);
+        let hb = HyperBezier::new_clamped(0.5, 1.2, -0.3, 0.8);
+        assert_eq!(pack(&hb), [0.5, 1.2, -0.3, 0.8]);
+    }
+}
@@ -0,0 +1,229 @@
+//! Approximation of segments by circular arcs.
+//!
+//! CAD, CNC and embroidery formats often prefer arcs to Bézier curves.
+//! Segments are approximated by biarcs: pairs of arcs meeting with a common
+//! tangent, so the whole approximation is tangent continuous.
+
+use std::ops::Range;
+
+use kurbo::{Arc, Line, Point, Vec2};
+
+use crate::spline::Segment;
+
+/// The most times a piece is halved.
+const MAX_DEPTH: usize = 16;
+
+/// The smallest turn, relative to the chord, for which a piece is drawn as
+/// an arc rather than a line.
+const LINE_TOLERANCE: f64 = 1e-9;
+
+/// A straight line or circular arc.
+#[derive(Clone, Copy, Debug)]
+pub enum ArcOrLine {
+    Line(Line),
+    /// A circular arc, with equal radii and no rotation.
+    Arc(Arc),
+}
+
+impl ArcOrLine {
+    /// The distance from `p` to the circle or line through this piece.
+    ///
+    /// This is the distance to the piece itself when `p` is beside it, as
+    /// when checking the approximation.
+    fn distance(&self, p: Point) -> f64 {
+        match self {
+            ArcOrLine::Line(line) => {
+                let v = line.p1 - line.p0;
+                (p - line.p0).cross(v).abs() / v.hypot()
+            }
+            ArcOrLine::Arc(arc) => ((p - arc.center).hypot() - arc.radii.x).abs(),
+        }
+    }
+}
+
+impl Segment {
+    /// Approximate the segment by circular arcs, to within `tolerance`.
+    ///
+    /// The result is a sequence of lines and arcs, each starting where the
+    /// previous one ends with the same tangent. A straight segment gives a
+    /// single line.
+    pub fn to_arcs(&self, tolerance: f64) -> Vec<ArcOrLine> {
+        let mut result = Vec::new();
+        if self.is_line() {
+            result.push(ArcOrLine::Line(Line::new(self.p0, self.p3)));
+        } else {
+            self.biarcs(0.0..1.0, tolerance, 0, &mut result);
+        }
+        result
+    }
+
+    fn biarcs(&self, range: Range<f64>, tolerance: f64, depth: usize, out: &mut Vec<ArcOrLine>) {
+        let (t0, t1) = (range.start, range.end);
+        let p0 = self.eval(t0);
+        let p1 = self.eval(t1);
+        let d0 = self.tangent(t0);
+        let d1 = self.tangent(t1);
+        let pair = biarc(p0, d0, p1, d1);
+        if depth < MAX_DEPTH {
+            let err = [0.25, 0.5, 0.75]
+                .iter()
+                .map(|&u| {
+                    let p = self.eval(t0 + u * (t1 - t0));
+                    pair.iter()
+                        .map(|piece| piece.distance(p))
+                        .fold(f64::INFINITY, f64::min)
+                })
+                .fold(0.0, f64::max);
+            if err > tolerance {
+                let tm = 0.5 * (t0 + t1);
+                self.biarcs(t0..tm, tolerance, depth + 1, out);
+                self.biarcs(tm..t1, tolerance, depth + 1, out);
+                return;
+            }
+        }
+        out.extend_from_slice(&pair);
+    }
+}
+
+/// The biarc from `p0` with unit tangent `d0` to `p1` with unit tangent
+/// `d1`.
+///
+/// The junction is chosen so that the tangent lines from each end to the
+/// tangent at the junction have equal lengths. When the tangents are
+/// parallel and don't lead towards each other, there is no such junction,
+/// and the result is a single arc or line from `p0`.
+fn biarc(p0: Point, d0: Vec2, p1: Point, d1: Vec2) -> Vec<ArcOrLine> {
+    let v = p1 - p0;
+    let t = d0 + d1;
+    let denom = 2.0 * (1.0 - d0.dot(d1));
+    let vt = v.dot(t);
+    let alpha = if denom.abs() < 1e-12 {
+        // Parallel tangents.
+        let vd = v.dot(d1);
+        if vd <= LINE_TOLERANCE * v.hypot() {
+            return vec![arc_from(p0, d0, p1)];
+        }
+        v.hypot2() / (4.0 * vd)
+    } else {
+        (-vt + (vt * vt + denom * v.hypot2()).sqrt()) / denom
+    };
+    let (q0, q1) = (p0 + alpha * d0, p1 - alpha * d1);
+    let junction = q0.midpoint(q1);
+    // The tangent at the junction is along the line between the two
+    // tangent points.
+    let dm = (q1 - q0).normalize();
+    vec![arc_from(p0, d0, junction), arc_from(junction, dm, p1)]
+}
+
+/// The arc from `a`, leaving with unit tangent `d`, to `b`.
+fn arc_from(a: Point, d: Vec2, b: Point) -> ArcOrLine {
+    let chord = b - a;
+    let len2 = chord.hypot2();
+    let cross = d.cross(chord);
+    if len2 == 0.0 || cross.abs() <= LINE_TOLERANCE * len2 {
+        return ArcOrLine::Line(Line::new(a, b));
+    }
+    let k = 2.0 * cross / len2;
+    let normal = Vec2::new(-d.y, d.x);
+    let center = a + normal / k;
+    let start_angle = (a - center).atan2();
+    let sweep_angle = 2.0 * cross.atan2(d.dot(chord));
+    ArcOrLine::Arc(Arc {
+        center,
+        radii: Vec2::new(1.0 / k.abs(), 1.0 / k.abs()),
+        start_angle,
+        sweep_angle,
+        x_rotation: 0.0,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Point, Vec2};
+
+    use super::{biarc, ArcOrLine};
+    use crate::{util, SplineSpec};
+
+    fn ends(piece: &ArcOrLine) -> (Point, Point) {
+        match piece {
+            ArcOrLine::Line(line) => (line.p0, line.p1),
+            ArcOrLine::Arc(arc) => {
+                let r = arc.radii.x;
+                let end = arc.start_angle + arc.sweep_angle;
+                (
+                    arc.center + r * Vec2::from_angle(arc.start_angle),
+                    arc.center + r * Vec2::from_angle(end),
+                )
+            }
+        }
+    }
+
+    #[test]
+    fn biarc_parallel_tangents() {
+        let d = Vec2::new(1., 0.);
+        // Side by side, and on top of each other.
+        for &p1 in &[Point::new(0., 10.), Point::ZERO] {
+            let pieces = biarc(Point::ZERO, d, p1, d);
+            assert_eq!(pieces.len(), 1);
+            let (a, b) = ends(&pieces[0]);
+            assert!(a.distance(Point::ZERO) < 1e-9 && b.distance(p1) < 1e-9);
+        }
+        // Ahead, as two straight pieces.
+        let pieces = biarc(Point::ZERO, d, Point::new(10., 0.), d);
+        assert!(pieces.iter().all(|p| matches!(p, ArcOrLine::Line(_))));
+    }
+
+    #[test]
+    fn arc_segment_is_exact() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(100., 0.));
+        spec.arc_to(Point::new(0., 100.), std::f64::consts::FRAC_PI_2, false);
+        let arcs = spec.solve().segments()[0].to_arcs(1e-6);
+        assert!(arcs.len() <= 4, "{}", arcs.len());
+        for piece in &arcs {
+            match piece {
+                ArcOrLine::Arc(arc) => {
+                    assert!((arc.radii.x - 100.).abs() < 1e-3, "{:?}", arc);
+                    assert!((arc.center - Point::ZERO).hypot() < 1e-3);
+                }
+                ArcOrLine::Line(_) => panic!(),
+            }
+        }
+    }
+
+    #[test]
+    fn biarcs_within_tolerance() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 60.), true);
+        spec.spline_to(None, None, Point::new(200., -40.), false);
+        let spline = spec.solve();
+        for seg in spline.segments() {
+            let arcs = seg.to_arcs(0.01);
+            assert!((ends(&arcs[0]).0 - seg.p0).hypot() < 1e-9);
+            assert!((ends(arcs.last().unwrap()).1 - seg.p3).hypot() < 1e-6);
+            for w in arcs.windows(2) {
+                assert!((ends(&w[0]).1 - ends(&w[1]).0).hypot() < 1e-6);
+            }
+            let pts: Vec<Point> = seg
+                .flatten_params(1e-4)
+                .into_iter()
+                .map(|(_, p)| p)
+                .collect();
+            // Points on the arcs are near the curve.
+            for piece in &arcs {
+                if let ArcOrLine::Arc(arc) = piece {
+                    let mid = arc.start_angle + 0.5 * arc.sweep_angle;
+                    let p = arc.center + arc.radii.x * Vec2::from_angle(mid);
+                    let d = pts
+                        .windows(2)
+                        .map(|w| {
+                            (p - w[0].lerp(w[1], util::nearest_on_line(p, w[0], w[1]))).hypot()
+                        })
+                        .fold(f64::INFINITY, f64::min);
+                    assert!(d < 0.02, "{}", d);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+//! Canonical forms and content hashing of specs.
+
+use crate::lock::Lock;
+use crate::spline::{Element, SplineSpec};
+
+/// The FNV-1a offset basis and prime, for 64 bits.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl SplineSpec {
+    /// Convert to a canonical form, without changing the solved shape.
+    ///
+    /// This removes elements of zero length with no explicit control
+    /// points (keeping their smoothness and metadata), turns arcs with zero
+    /// sweep into lines, and clears the smooth flag on points where it has
+    /// no effect, that is, where neither neighboring segment has an auto
+    /// control point at that point. The smooth flag of the final element of
+    /// an open path is also cleared.
+    ///
+    /// Two specs that differ only in these ways have equal canonical forms,
+    /// which is useful for deduplication and for coalescing undo steps.
+    pub fn canonicalize(&mut self) {
+        let is_closed = self.is_closed();
+        // When an element is dropped, its smoothness and metadata pass to
+        // the previous element, which ends at the same point.
+        let mut kept: Vec<Element> = Vec::with_capacity(self.elements().len());
+        let mut new_ix = Vec::with_capacity(self.elements().len());
+        for &el in self.elements() {
+            let redundant = match (kept.last(), el) {
+                (None, _) => false,
+                (_, Element::SplineTo(Some(_), _, _, _))
+                | (_, Element::SplineTo(_, Some(_), _, _)) => false,
+                (Some(last), _) => last.endpoint() == el.endpoint(),
+            };
+            if redundant {
+                let last = kept.last_mut().unwrap();
+                last.set_smooth(el.is_smooth());
+            } else {
+                kept.push(el);
+            }
+            new_ix.push(kept.len() - 1);
+        }
+        *self.elements_mut() = kept;
+        self.remap_metadata(|i| new_ix[i]);
+        let elements = self.elements_mut();
+        for el in elements.iter_mut() {
+            if let Element::ArcTo(p, sweep, is_smooth) = *el {
+                if sweep == 0.0 {
+                    *el = Element::LineTo(p, is_smooth);
+                }
+            }
+        }
+        let n = elements.len();
+        for i in 1..n {
+            let next = if i + 1 < n {
+                Some(&elements[i + 1])
+            } else if is_closed && n > 1 {
+                Some(&elements[1])
+            } else {
+                None
+            };
+            let matters = matches!(elements[i], Element::SplineTo(_, None, _, _)) && next.is_some()
+                || matches!(next, Some(Element::SplineTo(None, _, _, _)));
+            if !matters {
+                elements[i].set_smooth(false);
+            }
+        }
+    }
+
+    /// A hash of the elements, closedness, metadata, locks and windings.
+    ///
+    /// Unlike the standard library hashers, this is stable across runs,
+    /// platforms and versions, so it can be stored. Specs that are equal
+    /// have the same hash, except that zero and negative zero coordinates
+    /// hash differently.
+    pub fn content_hash(&self) -> u64 {
+        let mut hash = Fnv(FNV_OFFSET);
+        hash.write(&[self.is_closed() as u8]);
+        for el in self.elements() {
+            match *el {
+                Element::MoveTo(p) => {
+                    hash.write(&[0]);
+                    hash.write_f64(p.x);
+                    hash.write_f64(p.y);
+                }
+                Element::LineTo(p, is_smooth) => {
+                    hash.write(&[1, is_smooth as u8]);
+                    hash.write_f64(p.x);
+                    hash.write_f64(p.y);
+                }
+                Element::SplineTo(p1, p2, p3, is_smooth) => {
+                    hash.write(&[2, is_smooth as u8]);
+                    for p in [p1, p2, Some(p3)].iter() {
+                        match p {
+                            Some(p) => {
+                                hash.write(&[1]);
+                                hash.write_f64(p.x);
+                                hash.write_f64(p.y);
+                            }
+                            None => hash.write(&[0]),
+                        }
+                    }
+                }
+                Element::ArcTo(p, sweep, is_smooth) => {
+                    hash.write(&[3, is_smooth as u8]);
+                    hash.write_f64(p.x);
+                    hash.write_f64(p.y);
+                    hash.write_f64(sweep);
+                }
+            }
+        }
+        for (ix, map) in &self.metadata {
+            hash.write(&(*ix as u64).to_le_bytes());
+            for (key, value) in map {
+                hash.write_str(key);
+                hash.write_str(value);
+            }
+        }
+        for (ix, turns) in &self.windings {
+            hash.write(&(*ix as u64).to_le_bytes());
+            hash.write(&turns.to_le_bytes());
+        }
+        for (ix, lock) in &self.locks {
+            hash.write(&(*ix as u64).to_le_bytes());
+            hash.write(&[match lock {
+                Lock::X => 0,
+                Lock::Y => 1,
+            }]);
+        }
+        hash.0
+    }
+}
+
+/// A 64-bit FNV-1a hasher.
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ b as u64).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_f64(&mut self, x: f64) {
+        self.write(&x.to_bits().to_le_bytes());
+    }
+
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{Lock, SplineSpec};
+
+    #[test]
+    fn canonical_forms_match() {
+        let mut a = SplineSpec::new();
+        a.move_to(Point::new(0., 0.));
+        a.line_to(Point::new(100., 0.), true);
+        a.line_to(Point::new(100., 0.), false);
+        a.spline_to(None, None, Point::new(100., 100.), true);
+        a.arc_to(Point::new(0., 100.), 0.0, true);
+        let mut b = SplineSpec::new();
+        b.move_to(Point::new(0., 0.));
+        b.line_to(Point::new(100., 0.), false);
+        b.spline_to(None, None, Point::new(100., 100.), true);
+        b.line_to(Point::new(0., 100.), false);
+        assert!(a != b);
+        assert_ne!(a.content_hash(), b.content_hash());
+        let orig = a.solve().into_owned();
+        a.canonicalize();
+        b.canonicalize();
+        assert_eq!(a, b);
+        assert_eq!(a.content_hash(), b.content_hash());
+        // The same shape, without the zero-length segment.
+        let orig = orig.segments().iter().filter(|seg| seg.p0 != seg.p3);
+        for (seg, orig) in a.solve().segments().iter().zip(orig) {
+            assert_eq!(seg.is_line(), orig.is_line());
+            if !seg.is_line() {
+                assert!((seg.p1 - orig.p1).hypot() < 1e-6 && (seg.p2 - orig.p2).hypot() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn hash_covers_windings() {
+        let mut a = SplineSpec::new();
+        a.move_to(Point::new(0., 0.));
+        a.spline_to(None, None, Point::new(100., 0.), false);
+        let mut b = a.clone();
+        b.set_winding(1, 1);
+        assert!(a != b);
+        assert_ne!(a.content_hash(), b.content_hash());
+        b.set_winding(1, 0);
+        assert_eq!(a.content_hash(), b.content_hash());
+    }
+
+    #[test]
+    fn hash_covers_locks() {
+        let mut a = SplineSpec::new();
+        a.move_to(Point::new(0., 0.));
+        a.spline_to(None, None, Point::new(100., 50.), true);
+        a.spline_to(None, None, Point::new(200., 0.), false);
+        let mut x = a.clone();
+        x.set_lock(1, Some(Lock::X)).unwrap();
+        let mut y = a.clone();
+        y.set_lock(1, Some(Lock::Y)).unwrap();
+        assert!(a != x && x != y);
+        let hashes = [a.content_hash(), x.content_hash(), y.content_hash()];
+        assert!(hashes[0] != hashes[1] && hashes[0] != hashes[2] && hashes[1] != hashes[2]);
+        x.set_lock(1, None).unwrap();
+        assert_eq!(x.content_hash(), a.content_hash());
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(1., 2.));
+        spec.spline_to(None, Some(Point::new(3., 4.)), Point::new(5., 6.), true);
+        spec.close();
+        assert_eq!(spec.content_hash(), 0x1656_65f9_8627_7a42);
+    }
+}
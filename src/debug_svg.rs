@@ -0,0 +1,255 @@
+//! An SVG rendering of specs, for debugging.
+
+use std::fmt::Write;
+
+use kurbo::{BezPath, Point, Rect, Shape, Vec2};
+
+use crate::joints::Continuity;
+use crate::orientation::Orientation;
+use crate::sink::Tee;
+use crate::spline::{Element, SplineSpec};
+use crate::svg::SvgSink;
+
+/// The decimal places of the rendered curves.
+const PRECISION: usize = 3;
+
+/// What to draw in [`debug_svg`].
+#[derive(Clone, Copy, Debug)]
+pub struct DebugOptions {
+    /// Draw the on-curve points, blue for corners and green for smooth.
+    pub points: bool,
+    /// Draw the control handles. Explicit control points are filled, and
+    /// auto points are hollow.
+    pub handles: bool,
+    /// Draw a curvature comb, with teeth of this length per unit of
+    /// curvature, or `None` for no comb.
+    pub comb_scale: Option<f64>,
+    /// The number of comb teeth per segment.
+    pub comb_teeth: usize,
+    /// Mark points that are meant to be smooth where the solved spline has a
+    /// tangent or curvature discontinuity.
+    pub joint_badges: bool,
+    /// The margin around the drawing.
+    pub margin: f64,
+    /// The orientation of the specs' coordinates. Y-up specs are flipped
+    /// so they appear upright, and badges still report their values in
+    /// the specs' own convention.
+    pub orientation: Orientation,
+}
+
+impl Default for DebugOptions {
+    fn default() -> DebugOptions {
+        DebugOptions {
+            points: true,
+            handles: true,
+            comb_scale: None,
+            comb_teeth: 16,
+            joint_badges: true,
+            margin: 10.0,
+            orientation: Orientation::YDown,
+        }
+    }
+}
+
+/// Draw specs as a standalone SVG document.
+///
+/// The view box fits the rendered curves.
+pub fn debug_svg(specs: &[SplineSpec], options: DebugOptions) -> String {
+    let mut specs = specs.to_vec();
+    // Signs of values measured on the drawn specs, in the specs' convention.
+    let sign = match options.orientation {
+        Orientation::YUp => {
+            specs.iter_mut().for_each(SplineSpec::flip_y);
+            -1.0
+        }
+        Orientation::YDown => 1.0,
+    };
+    let mut path = BezPath::new();
+    let mut d = String::new();
+    let mut body = String::new();
+    for spec in &mut specs {
+        let elements = spec.elements().to_vec();
+        let spline = spec.solve();
+        if spline.segments().is_empty() {
+            continue;
+        }
+        // Render once, for both the drawing and its bounds.
+        let mut svg_sink = SvgSink::new(&mut d, PRECISION);
+        spline.render_to(&mut Tee(&mut path, &mut svg_sink));
+        let _ = svg_sink.finish();
+        if let Some(scale) = options.comb_scale {
+            let n = options.comb_teeth.max(1);
+            let mut tips = BezPath::new();
+            for seg in spline.segments().iter().filter(|seg| !seg.is_line()) {
+                for i in 0..=n {
+                    let t = i as f64 / n as f64;
+                    let p = seg.eval(t);
+                    // Teeth point away from the center of curvature.
+                    let normal =
+                        Vec2::from_angle(seg.tangent_angle(t) + 0.5 * std::f64::consts::PI);
+                    let tip = p - scale * seg.curvature(t) * normal;
+                    line(&mut body, p, tip, "#8cf");
+                    if i == 0 {
+                        tips.move_to(tip);
+                    } else {
+                        tips.line_to(tip);
+                    }
+                }
+            }
+            let _ = writeln!(
+                body,
+                r##"  <path d="{}" fill="none" stroke="#48c" stroke-width="0.5"/>"##,
+                tips.to_svg()
+            );
+        }
+        if options.handles {
+            for seg in spline.segments() {
+                if !seg.is_line() {
+                    line(&mut body, seg.p0, seg.p1, "grey");
+                    line(&mut body, seg.p2, seg.p3, "grey");
+                }
+            }
+            for (seg, el) in spline.segments().iter().zip(&elements[1..]) {
+                if let Element::SplineTo(p1, p2, _, _) = el {
+                    circle(&mut body, seg.p1, p1.is_some(), "grey");
+                    circle(&mut body, seg.p2, p2.is_some(), "grey");
+                }
+            }
+        }
+        if options.points {
+            for el in &elements {
+                let color = if el.is_smooth() { "green" } else { "blue" };
+                circle(&mut body, el.endpoint(), true, color);
+            }
+        }
+        if options.joint_badges {
+            for joint in spline.iter_joints() {
+                // Joint `i` is at the end of element `i`, and for a closed
+                // spline the start point is the end of the last element.
+                let el = if joint.index == 0 {
+                    elements.last().unwrap()
+                } else {
+                    &elements[joint.index]
+                };
+                let label = match joint.continuity {
+                    Continuity::Curvature => continue,
+                    _ if !el.is_smooth() => continue,
+                    Continuity::Position => format!("{:.2}°", sign * joint.angle.to_degrees()),
+                    Continuity::Tangent => {
+                        format!("Δk {:.3}", sign * (joint.next.k0 - joint.prev.k1))
+                    }
+                };
+                let p = joint.next.p0;
+                let _ = writeln!(
+                    body,
+                    r#"  <circle cx="{}" cy="{}" r="6" fill="none" stroke="red" stroke-width="1.5"/>"#,
+                    p.x, p.y
+                );
+                let _ = writeln!(
+                    body,
+                    r#"  <text x="{}" y="{}" font-size="8" fill="red">{}</text>"#,
+                    p.x + 8.0,
+                    p.y - 8.0,
+                    label
+                );
+            }
+        }
+    }
+    let bbox = if path.elements().is_empty() {
+        Rect::ZERO
+    } else {
+        path.bounding_box()
+    }
+    .inflate(options.margin, options.margin);
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}" width="{}" height="{}">"#,
+        bbox.x0,
+        bbox.y0,
+        bbox.width(),
+        bbox.height(),
+        bbox.width(),
+        bbox.height()
+    );
+    let _ = writeln!(svg, r##"  <path d="{}" fill="none" stroke="#000"/>"##, d);
+    svg.push_str(&body);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn line(out: &mut String, p0: Point, p1: Point, color: &str) {
+    let _ = writeln!(
+        out,
+        r#"  <line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="0.5"/>"#,
+        p0.x, p0.y, p1.x, p1.y, color
+    );
+}
+
+fn circle(out: &mut String, p: Point, filled: bool, color: &str) {
+    let fill = if filled { color } else { "white" };
+    let _ = writeln!(
+        out,
+        r#"  <circle cx="{}" cy="{}" r="3" fill="{}" stroke="{}" stroke-width="1"/>"#,
+        p.x, p.y, fill, color
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use super::{debug_svg, DebugOptions};
+    use crate::SplineSpec;
+
+    #[test]
+    fn markers_and_badges() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), false);
+        // Smooth, but a line meets a line at a right angle.
+        spec.line_to(Point::new(200., 100.), true);
+        spec.line_to(Point::new(100., 100.), false);
+        let options = DebugOptions {
+            comb_scale: Some(100.0),
+            comb_teeth: 4,
+            ..DebugOptions::default()
+        };
+        let svg = debug_svg(&[spec.clone()], options);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        // Every element is closed, and attributes are not separated by commas.
+        let open_close = svg.matches("</").count();
+        assert_eq!(
+            svg.matches('<').count(),
+            svg.matches("/>").count() + 2 * open_close
+        );
+        assert!(!svg.contains("\","));
+        // Five points, plus four hollow auto handles.
+        assert_eq!(svg.matches("r=\"3\"").count(), 9);
+        // Two curved segments with five teeth each.
+        assert_eq!(svg.matches("stroke=\"#8cf\"").count(), 10);
+        assert_eq!(svg.matches("<text").count(), 1);
+        assert!(svg.contains(">90.00°<"));
+        let mut flipped = spec.clone();
+        flipped.flip_y();
+        let options = DebugOptions {
+            orientation: crate::Orientation::YUp,
+            ..options
+        };
+        // The mirrored spec, as y-up, is drawn the same way, but the turn
+        // is clockwise in its own coordinates.
+        let flipped = debug_svg(&[flipped], options);
+        assert!(flipped.contains("viewBox=\"-10 -10 220 120\""));
+        assert!(flipped.contains(">-90.00°<"));
+    }
+
+    #[test]
+    fn empty() {
+        let mut single = SplineSpec::new();
+        single.move_to(Point::new(1., 2.));
+        let svg = debug_svg(&[single, SplineSpec::new()], DebugOptions::default());
+        assert!(svg.contains("viewBox"));
+    }
+}
@@ -0,0 +1,177 @@
+//! Measuring the distance between curves.
+
+use kurbo::{CubicBez, PathEl, Point};
+
+use crate::spline::{Segment, Spline};
+use crate::util;
+
+/// The distance between two curves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Deviation {
+    /// The Hausdorff distance, the largest distance from a point on either
+    /// curve to the nearest point on the other.
+    pub max: f64,
+    /// The root mean square distance from the segment to the other curve,
+    /// sampled evenly by arclength along the segment.
+    pub rms: f64,
+}
+
+impl Segment {
+    /// Measure how far this segment is from a cubic Bézier.
+    ///
+    /// Both curves are flattened to within `tolerance`, so the results are
+    /// accurate to about that distance.
+    pub fn deviation(&self, cubic: CubicBez, tolerance: f64) -> Deviation {
+        let a: Vec<Point> = self
+            .flatten_params(tolerance)
+            .into_iter()
+            .map(|(_, p)| p)
+            .collect();
+        let mut b = Vec::new();
+        kurbo::flatten(
+            [
+                PathEl::MoveTo(cubic.p0),
+                PathEl::CurveTo(cubic.p1, cubic.p2, cubic.p3),
+            ],
+            tolerance,
+            |el| match el {
+                PathEl::MoveTo(p) | PathEl::LineTo(p) => b.push(p),
+                _ => (),
+            },
+        );
+        let max_a = a
+            .iter()
+            .map(|&p| distance_to_polyline(p, &b))
+            .fold(0.0, f64::max);
+        let max_b = b
+            .iter()
+            .map(|&p| distance_to_polyline(p, &a))
+            .fold(0.0, f64::max);
+        const N: usize = 64;
+        let sum_sq: f64 = (0..=N)
+            .map(|i| distance_to_polyline(self.eval(i as f64 / N as f64), &b).powi(2))
+            .sum();
+        Deviation {
+            max: max_a.max(max_b),
+            rms: (sum_sq / (N + 1) as f64).sqrt(),
+        }
+    }
+}
+
+impl<'a> Spline<'a> {
+    /// The largest distance a point of the spline has moved from where it
+    /// was in `prev`, such as the solution for the previous frame of a drag.
+    ///
+    /// Points are matched by segment and arclength parameter, and each
+    /// segment is sampled at evenly spaced parameters. A displacement much
+    /// larger than the edit that caused it means the solver has jumped to a
+    /// different solution. Returns `None` if the splines have different
+    /// numbers of segments.
+    pub fn max_displacement_from(&self, prev: &Spline) -> Option<f64> {
+        const N: usize = 32;
+        if self.segments().len() != prev.segments().len() {
+            return None;
+        }
+        let max = self
+            .segments()
+            .iter()
+            .zip(prev.segments())
+            .flat_map(|(a, b)| {
+                (0..=N).map(move |i| {
+                    let t = i as f64 / N as f64;
+                    a.eval(t).distance(b.eval(t))
+                })
+            })
+            .fold(0.0, f64::max);
+        Some(max)
+    }
+}
+
+/// The Hausdorff distance between two splines, accurate to about
+/// `tolerance`.
+#[cfg(test)]
+pub(crate) fn spline_distance(a: &Spline, b: &Spline, tolerance: f64) -> f64 {
+    let (a, b) = (flatten(a, tolerance), flatten(b, tolerance));
+    polylines_distance(&a, &b)
+}
+
+/// Flatten each segment of a spline to a polyline.
+pub(crate) fn flatten(spline: &Spline, tolerance: f64) -> Vec<Vec<Point>> {
+    spline
+        .segments()
+        .iter()
+        .map(|seg| {
+            seg.flatten_params(tolerance)
+                .into_iter()
+                .map(|(_, p)| p)
+                .collect()
+        })
+        .collect()
+}
+
+/// The Hausdorff distance between two sets of polylines.
+pub(crate) fn polylines_distance(a: &[Vec<Point>], b: &[Vec<Point>]) -> f64 {
+    let one_way = |a: &[Vec<Point>], b: &[Vec<Point>]| {
+        a.iter()
+            .flatten()
+            .map(|&p| {
+                b.iter()
+                    .map(|pts| distance_to_polyline(p, pts))
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0.0, f64::max)
+    };
+    one_way(a, b).max(one_way(b, a))
+}
+
+fn distance_to_polyline(p: Point, pts: &[Point]) -> f64 {
+    pts.windows(2)
+        .map(|w| {
+            let t = util::nearest_on_line(p, w[0], w[1]);
+            (p - w[0].lerp(w[1], t)).hypot()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{CubicBez, Point};
+
+    use crate::SplineSpec;
+
+    #[test]
+    fn deviation_from_cubic() {
+        // A quarter circle and its usual cubic approximation, which is off
+        // by about 2.7e-4 of the radius.
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(100., 0.));
+        spec.arc_to(Point::new(0., 100.), std::f64::consts::FRAC_PI_2, false);
+        let spline = spec.solve();
+        let seg = &spline.segments()[0];
+        let k = 100.0 * 0.5522847498;
+        let cubic = CubicBez::new((100., 0.), (100., k), (k, 100.), (0., 100.));
+        let close = seg.deviation(cubic, 1e-4);
+        assert!(close.max > 0.02 && close.max < 0.03, "{:?}", close);
+        assert!(close.rms <= close.max);
+        // Against the chord, the distance is the height of the arc.
+        let (p0, p3) = (Point::new(100., 0.), Point::new(0., 100.));
+        let line = CubicBez::new(p0, p0.lerp(p3, 1.0 / 3.0), p0.lerp(p3, 2.0 / 3.0), p3);
+        let far = seg.deviation(line, 1e-4);
+        let height = 100.0 * (1.0 - 0.5f64.sqrt());
+        assert!((far.max - height).abs() < 1e-3, "{:?}", far);
+    }
+
+    #[test]
+    fn max_displacement() {
+        let mut spec = SplineSpec::circle(Point::ZERO, 100.0);
+        let prev = spec.solve().into_owned();
+        assert_eq!(prev.max_displacement_from(&prev), Some(0.0));
+        spec = SplineSpec::circle(Point::new(3., 4.), 100.0);
+        let d = spec.solve().max_displacement_from(&prev).unwrap();
+        assert!((d - 5.).abs() < 1e-6, "{}", d);
+        let mut open = SplineSpec::new();
+        open.move_to(Point::new(0., 0.));
+        open.line_to(Point::new(100., 0.), false);
+        assert_eq!(open.solve().max_displacement_from(&prev), None);
+    }
+}
@@ -0,0 +1,408 @@
+//! Editing single elements while keeping smooth points smooth.
+
+use std::fmt;
+use std::ops::Range;
+
+use kurbo::Point;
+
+use crate::spline::{Element, SplineSpec};
+use crate::util;
+
+/// One of the two control points of a spline element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlPoint {
+    /// The control point next to the start of the segment.
+    P1,
+    /// The control point next to the end of the segment.
+    P2,
+}
+
+/// The reason an edit can't be made to a spec.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EditError {
+    /// The spec has no segments, only a start point or nothing at all.
+    NoSegments,
+    /// There is no element or segment with this index.
+    Index(usize),
+    /// The start point of a path can't be removed while other elements
+    /// follow it.
+    Start,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EditError::NoSegments => write!(f, "spline has no segments"),
+            EditError::Index(i) => write!(f, "index {} is out of range", i),
+            EditError::Start => write!(f, "can't remove the start of a path"),
+        }
+    }
+}
+
+impl std::error::Error for EditError {}
+
+impl SplineSpec {
+    /// Close the path, as by [`close`](SplineSpec::close), if it has at
+    /// least one segment.
+    pub fn try_close(&mut self) -> Result<(), EditError> {
+        if self.elements().len() < 2 {
+            return Err(EditError::NoSegments);
+        }
+        self.close();
+        Ok(())
+    }
+
+    /// Remove an element, as by
+    /// [`remove_element`](SplineSpec::remove_element), if it exists and
+    /// isn't the start of a path with more elements.
+    ///
+    /// Removing the last segment of a closed path leaves it open.
+    pub fn try_remove_element(&mut self, ix: usize) -> Result<Element, EditError> {
+        let n = self.elements().len();
+        if ix >= n {
+            return Err(EditError::Index(ix));
+        }
+        if ix == 0 && n > 1 {
+            return Err(EditError::Start);
+        }
+        let el = self.remove_element(ix);
+        if self.elements().len() < 2 {
+            self.is_closed = false;
+        }
+        Ok(el)
+    }
+
+    /// Insert a point into segment `seg_ix`, as by
+    /// [`subdivide`](SplineSpec::subdivide), if the segment exists.
+    pub fn try_subdivide(&mut self, seg_ix: usize, t: f64) -> Result<(), EditError> {
+        let n_segs = self.elements().len().saturating_sub(1);
+        if n_segs == 0 {
+            return Err(EditError::NoSegments);
+        }
+        if seg_ix >= n_segs {
+            return Err(EditError::Index(seg_ix));
+        }
+        self.subdivide(seg_ix, t);
+        Ok(())
+    }
+
+    /// Mark the on-curve point at the end of element `ix` as smooth or as a
+    /// corner.
+    ///
+    /// Making a point smooth rotates explicit control points onto a shared
+    /// tangent, as by [`make_smooth`](SplineSpec::make_smooth), so a point
+    /// is never smooth with handles pointing different ways. Making it a
+    /// corner leaves the control points alone.
+    ///
+    /// For a closed path, the start point is the end of the last element.
+    /// Fails if there is no element `ix`.
+    pub fn set_smooth(&mut self, ix: usize, smooth: bool) -> Result<(), EditError> {
+        if ix >= self.elements().len() {
+            return Err(EditError::Index(ix));
+        }
+        if smooth {
+            self.make_smooth(ix);
+        } else {
+            let (ix, _) = self.joint_ixs(ix);
+            self.elements_mut()[ix].set_smooth(false);
+        }
+        Ok(())
+    }
+
+    /// Move the on-curve point at the end of element `ix` to `p`, leaving
+    /// the control points next to it in place.
+    ///
+    /// If the point is smooth, the explicit control points next to it are
+    /// then rotated back onto a shared tangent, as by
+    /// [`make_smooth`](SplineSpec::make_smooth). For a closed path, the
+    /// start point and the end of the last element move together. To carry
+    /// the control points along instead, use
+    /// [`move_points`](SplineSpec::move_points). Fails if there is no
+    /// element `ix`.
+    pub fn set_endpoint(&mut self, ix: usize, p: Point) -> Result<(), EditError> {
+        if ix >= self.elements().len() {
+            return Err(EditError::Index(ix));
+        }
+        let (joint_ix, next_ix) = self.joint_ixs(ix);
+        let is_closed = self.is_closed();
+        let elements = self.elements_mut();
+        elements[joint_ix].set_endpoint(p);
+        if is_closed && joint_ix == elements.len() - 1 {
+            // The start point is the end of the last element.
+            elements[0].set_endpoint(p);
+        }
+        if next_ix.is_some() && self.elements()[joint_ix].is_smooth() {
+            self.make_smooth(joint_ix);
+        }
+        Ok(())
+    }
+
+    /// Set a control point of element `ix`, or make it automatic with
+    /// `None`.
+    ///
+    /// Setting a control point of a line or an arc turns it into a spline
+    /// element with the other control point automatic. Making a control
+    /// point of a line or an arc automatic does nothing.
+    ///
+    /// If the on-curve point next to the control point is smooth, the
+    /// other side follows so that it stays smooth: an explicit control
+    /// point across it is rotated to line up with the new one, keeping its
+    /// distance, and a line or an arc across it instead rotates the new
+    /// control point onto its tangent.
+    ///
+    /// Fails if there is no element `ix`.
+    pub fn set_control(
+        &mut self,
+        ix: usize,
+        which: ControlPoint,
+        p: Option<Point>,
+    ) -> Result<(), EditError> {
+        if ix >= self.elements().len() {
+            return Err(EditError::Index(ix));
+        }
+        let el = &mut self.elements_mut()[ix];
+        match (*el, p) {
+            (Element::SplineTo(p1, p2, p3, smooth), _) => {
+                *el = match which {
+                    ControlPoint::P1 => Element::SplineTo(p, p2, p3, smooth),
+                    ControlPoint::P2 => Element::SplineTo(p1, p, p3, smooth),
+                };
+            }
+            (Element::LineTo(p3, smooth), Some(_)) | (Element::ArcTo(p3, _, smooth), Some(_)) => {
+                *el = match which {
+                    ControlPoint::P1 => Element::SplineTo(p, None, p3, smooth),
+                    ControlPoint::P2 => Element::SplineTo(None, p, p3, smooth),
+                };
+            }
+            _ => return Ok(()),
+        }
+        let p = match p {
+            Some(p) => p,
+            None => return Ok(()),
+        };
+        let joint = match which {
+            ControlPoint::P1 => ix - 1,
+            ControlPoint::P2 => ix,
+        };
+        let (joint_ix, next_ix) = match self.joint_ixs(joint) {
+            (joint_ix, Some(next_ix)) => (joint_ix, next_ix),
+            _ => return Ok(()),
+        };
+        let q = self.elements()[joint_ix].endpoint();
+        if !self.elements()[joint_ix].is_smooth() || p == q {
+            return Ok(());
+        }
+        let dir = (q - p).normalize();
+        let elements = self.elements_mut();
+        let other = match which {
+            ControlPoint::P1 => match &mut elements[joint_ix] {
+                Element::SplineTo(_, Some(p2), _, _) => Some(p2),
+                _ => None,
+            },
+            ControlPoint::P2 => match &mut elements[next_ix] {
+                Element::SplineTo(Some(p1), _, _, _) => Some(p1),
+                _ => None,
+            },
+        };
+        match other {
+            Some(other) => *other = util::rotate_onto(*other, q, dir),
+            // A line or an arc fixes the tangent; automatic control points
+            // follow by themselves.
+            None => self.make_smooth(joint_ix),
+        }
+        Ok(())
+    }
+
+    /// Make the control points of elements `ixs` automatic, leaving the
+    /// solver to place them, as by [`set_control`](SplineSpec::set_control).
+    ///
+    /// Pass `0..spec.elements().len()` for the whole spline. Fails, leaving
+    /// the spec as it was, if `ixs` reaches past the last element.
+    pub fn make_controls_auto(&mut self, ixs: Range<usize>) -> Result<(), EditError> {
+        self.check_range(&ixs)?;
+        for ix in ixs {
+            self.set_control(ix, ControlPoint::P1, None)?;
+            self.set_control(ix, ControlPoint::P2, None)?;
+        }
+        Ok(())
+    }
+
+    /// Replace the automatic control points of elements `ixs` with
+    /// explicit ones where the solver placed them, as by
+    /// [`set_control`](SplineSpec::set_control).
+    ///
+    /// Freezing the whole spline keeps its shape, and afterwards moving a
+    /// point no longer changes the segments that don't end at it. Auto
+    /// segments next to a partial range can shift a little, as they blend
+    /// into the new explicit control points.
+    ///
+    /// Fails, leaving the spec as it was, if `ixs` reaches past the last
+    /// element.
+    pub fn freeze_controls(&mut self, ixs: Range<usize>) -> Result<(), EditError> {
+        self.check_range(&ixs)?;
+        let segments = self.solve().segments().to_vec();
+        for ix in ixs.filter(|&ix| ix > 0) {
+            let seg = &segments[ix - 1];
+            if let Element::SplineTo(p1, p2, _, _) = self.elements()[ix] {
+                if p1.is_none() {
+                    self.set_control(ix, ControlPoint::P1, Some(seg.p1))?;
+                }
+                if p2.is_none() {
+                    self.set_control(ix, ControlPoint::P2, Some(seg.p2))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that a nonempty range of element indices is in bounds.
+    fn check_range(&self, ixs: &Range<usize>) -> Result<(), EditError> {
+        let n = self.elements().len();
+        if ixs.start < ixs.end && ixs.end > n {
+            return Err(EditError::Index(ixs.start.max(n)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{ControlPoint, EditError, Element, SplineSpec};
+
+    #[test]
+    fn set_control_keeps_smooth() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        let a = Point::new(100., 0.);
+        spec.spline_to(
+            Some(Point::new(30., 30.)),
+            Some(Point::new(70., 0.)),
+            a,
+            true,
+        );
+        spec.spline_to(
+            Some(Point::new(150., 0.)),
+            Some(Point::new(200., 30.)),
+            Point::new(200., 0.),
+            false,
+        );
+        spec.set_control(1, ControlPoint::P2, Some(Point::new(70., -30.)))
+            .unwrap();
+        // The handle across the smooth point lines up, keeping its length.
+        match spec.elements()[2] {
+            Element::SplineTo(Some(p1), ..) => {
+                let expected = a + 50. / 2f64.sqrt() * kurbo::Vec2::new(1., 1.);
+                assert!(p1.distance(expected) < 1e-9, "{:?}", p1);
+            }
+            _ => panic!(),
+        }
+        // Turning the point into a corner and back keeps the handles.
+        spec.set_smooth(1, false).unwrap();
+        assert!(!spec.elements()[1].is_smooth());
+        let before = spec.elements().to_vec();
+        spec.set_smooth(1, true).unwrap();
+        assert_eq!(spec.elements()[2], before[2]);
+    }
+
+    #[test]
+    fn freeze_and_make_auto() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(
+            None,
+            Some(Point::new(180., 60.)),
+            Point::new(200., 0.),
+            true,
+        );
+        spec.line_to(Point::new(300., 0.), false);
+        let before = spec.solve().into_owned();
+        // Only the selected elements are frozen.
+        let mut partial = spec.clone();
+        partial.freeze_controls(2..3).unwrap();
+        assert_eq!(
+            partial.elements()[1],
+            Element::SplineTo(None, None, Point::new(100., 50.), true)
+        );
+        assert!(matches!(
+            partial.elements()[2],
+            Element::SplineTo(Some(_), Some(_), ..)
+        ));
+        let n = spec.elements().len();
+        spec.freeze_controls(0..n).unwrap();
+        assert!(spec.elements().iter().all(|el| !matches!(
+            el,
+            Element::SplineTo(None, ..) | Element::SplineTo(_, None, ..)
+        )));
+        let moved = spec.solve().max_displacement_from(&before).unwrap();
+        assert!(moved < 1e-9, "{}", moved);
+        spec.make_controls_auto(0..n).unwrap();
+        assert!(spec.elements().iter().all(|el| !matches!(
+            el,
+            Element::SplineTo(Some(_), ..) | Element::SplineTo(_, Some(_), ..)
+        )));
+    }
+
+    #[test]
+    fn set_control_on_line() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), true);
+        spec.line_to(Point::new(100., 100.), false);
+        spec.set_control(2, ControlPoint::P1, Some(Point::new(130., 30.)))
+            .unwrap();
+        // The line before the smooth point fixes the tangent.
+        assert_eq!(
+            spec.elements()[2],
+            Element::SplineTo(
+                Some(Point::new(100. + 30. * 2f64.sqrt(), 0.)),
+                None,
+                Point::new(100., 100.),
+                false
+            )
+        );
+        spec.set_endpoint(2, Point::new(120., 100.)).unwrap();
+        assert_eq!(spec.elements()[2].endpoint(), Point::new(120., 100.));
+    }
+
+    #[test]
+    fn degenerate_edits() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        assert_eq!(spec.try_close(), Err(EditError::NoSegments));
+        assert_eq!(spec.try_subdivide(0, 0.5), Err(EditError::NoSegments));
+        spec.line_to(Point::new(100., 0.), false);
+        spec.line_to(Point::new(100., 100.), true);
+        spec.try_close().unwrap();
+        assert_eq!(spec.try_remove_element(0), Err(EditError::Start));
+        assert_eq!(spec.try_remove_element(3), Err(EditError::Index(3)));
+        assert_eq!(spec.try_subdivide(2, 0.5), Err(EditError::Index(2)));
+        // Setters fail on an element past the end, rather than panicking.
+        let before = spec.clone();
+        assert_eq!(spec.set_smooth(3, false), Err(EditError::Index(3)));
+        assert_eq!(spec.set_endpoint(3, Point::ZERO), Err(EditError::Index(3)));
+        assert_eq!(
+            spec.set_control(3, ControlPoint::P1, Some(Point::ZERO)),
+            Err(EditError::Index(3))
+        );
+        assert_eq!(spec.straighten(3), Err(EditError::Index(3)));
+        assert_eq!(spec.freeze_controls(1..4), Err(EditError::Index(3)));
+        assert_eq!(spec.make_controls_auto(1..4), Err(EditError::Index(3)));
+        assert_eq!(spec.elements(), before.elements());
+        // Deleting down to the start point opens the path, and joint edits
+        // on what's left do nothing.
+        spec.try_remove_element(2).unwrap();
+        spec.try_remove_element(1).unwrap();
+        assert!(!spec.is_closed());
+        spec.set_smooth(0, true).unwrap();
+        spec.slide_point(0, 10.0);
+        assert!(spec.solve().segments().is_empty());
+        // A closed path of one point, made directly, is no different.
+        spec.close();
+        spec.make_smooth(0);
+        spec.harmonize_joint(0);
+        assert_eq!(spec.try_remove_element(0), Ok(Element::MoveTo(Point::ZERO)));
+    }
+}
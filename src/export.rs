@@ -0,0 +1,253 @@
+//! Export to DXF and HPGL, for CAD programs and pen plotters.
+//!
+//! Curves are written as circular arcs and lines, using the biarc
+//! approximation of [`Segment::to_arcs`](crate::Segment::to_arcs), since
+//! both formats have arcs but no Bézier curves.
+//!
+//! Both formats use y-up coordinates. Specs in y-down coordinates can be
+//! converted first with [`SplineSpec::flip_y`](crate::SplineSpec::flip_y).
+
+use std::fmt::Write;
+
+use std::f64::consts::TAU;
+
+use kurbo::{Point, Vec2};
+
+use crate::arcs::ArcOrLine;
+use crate::spline::Spline;
+
+/// HPGL plotter units per millimeter.
+pub const HPGL_UNITS_PER_MM: f64 = 40.0;
+
+/// Options for export.
+#[derive(Clone, Copy, Debug)]
+pub struct ExportOptions {
+    /// The largest distance between the splines and the arcs written, in
+    /// spline units.
+    pub tolerance: f64,
+    /// Output units per spline unit.
+    ///
+    /// For HPGL, output units are plotter units, of which there are
+    /// [`HPGL_UNITS_PER_MM`] per millimeter.
+    pub scale: f64,
+}
+
+impl Default for ExportOptions {
+    fn default() -> ExportOptions {
+        ExportOptions {
+            tolerance: 0.1,
+            scale: 1.0,
+        }
+    }
+}
+
+/// Write splines as the entities of an R12 DXF file, made of `LINE` and
+/// `ARC` entities.
+pub fn to_dxf(splines: &[Spline], options: &ExportOptions) -> String {
+    let mut out = String::new();
+    group(&mut out, 0, "SECTION");
+    group(&mut out, 2, "ENTITIES");
+    for spline in splines {
+        for piece in pieces(spline, options) {
+            match piece {
+                ArcOrLine::Line(line) => {
+                    group(&mut out, 0, "LINE");
+                    group(&mut out, 8, "0");
+                    point(&mut out, 10, line.p0);
+                    point(&mut out, 11, line.p1);
+                }
+                ArcOrLine::Arc(arc) => {
+                    // DXF arcs run counterclockwise, so clockwise ones are
+                    // written from their end.
+                    let (start, end) = if arc.sweep_angle >= 0.0 {
+                        (arc.start_angle, arc.start_angle + arc.sweep_angle)
+                    } else {
+                        (arc.start_angle + arc.sweep_angle, arc.start_angle)
+                    };
+                    group(&mut out, 0, "ARC");
+                    group(&mut out, 8, "0");
+                    point(&mut out, 10, arc.center);
+                    group(&mut out, 40, arc.radii.x);
+                    group(&mut out, 50, start.to_degrees());
+                    group(&mut out, 51, end.to_degrees());
+                }
+            }
+        }
+    }
+    group(&mut out, 0, "ENDSEC");
+    group(&mut out, 0, "EOF");
+    out
+}
+
+/// Write splines as HPGL pen plotter commands, with pen 1.
+///
+/// The pen moves up to the start of each spline, then goes down. Lines are
+/// drawn with `PD` and arcs with `AA`, in whole plotter units. Each arc
+/// runs from where the plotter is after the rounded commands before it to
+/// the rounded end of the arc, so rounding errors don't build up along the
+/// spline. Arcs too small to round are drawn as lines.
+pub fn to_hpgl(splines: &[Spline], options: &ExportOptions) -> String {
+    let mut out = String::from("IN;SP1;");
+    let round = |p: Point| Point::new(p.x.round(), p.y.round());
+    for spline in splines {
+        let start = match spline.segments().first() {
+            Some(seg) => round((seg.p0.to_vec2() * options.scale).to_point()),
+            None => continue,
+        };
+        let _ = write!(out, "PU{},{};PD;", start.x, start.y);
+        // Where the plotter is.
+        let mut current = start;
+        for piece in pieces(spline, options) {
+            match piece {
+                ArcOrLine::Line(line) => {
+                    current = round(line.p1);
+                    let _ = write!(out, "PD{},{};", current.x, current.y);
+                }
+                ArcOrLine::Arc(arc) => {
+                    let angle = arc.start_angle + arc.sweep_angle;
+                    let target = round(arc.center + arc.radii.x * Vec2::from_angle(angle));
+                    // The circle through the current point and the rounded
+                    // end with the same sweep, so errors from earlier
+                    // commands aren't carried along.
+                    let chord = target - current;
+                    let normal = Vec2::new(-chord.y, chord.x);
+                    let center =
+                        current.midpoint(target) + normal * (0.5 / (0.5 * arc.sweep_angle).tan());
+                    let center = round(center);
+                    let (from, to) = (current - center, target - center);
+                    if from.hypot2() == 0.0 || to.hypot2() == 0.0 || !center.is_finite() {
+                        current = target;
+                        let _ = write!(out, "PD{},{};", current.x, current.y);
+                        continue;
+                    }
+                    let turn = to.atan2() - from.atan2() - arc.sweep_angle;
+                    let sweep = arc.sweep_angle + turn - TAU * (turn / TAU).round();
+                    let _ = write!(
+                        out,
+                        "AA{},{},{:.3};",
+                        center.x,
+                        center.y,
+                        sweep.to_degrees()
+                    );
+                    let end = center + from.hypot() * Vec2::from_angle(from.atan2() + sweep);
+                    current = round(end);
+                }
+            }
+        }
+    }
+    out.push_str("PU;SP0;\n");
+    out
+}
+
+/// The arcs and lines of a spline, scaled to output units.
+fn pieces<'a>(
+    spline: &'a Spline,
+    options: &'a ExportOptions,
+) -> impl Iterator<Item = ArcOrLine> + 'a {
+    let scale = options.scale;
+    let scale_point = move |p: Point| (p.to_vec2() * scale).to_point();
+    spline
+        .segments()
+        .iter()
+        .flat_map(move |seg| seg.to_arcs(options.tolerance))
+        .map(move |piece| match piece {
+            ArcOrLine::Line(mut line) => {
+                line.p0 = scale_point(line.p0);
+                line.p1 = scale_point(line.p1);
+                ArcOrLine::Line(line)
+            }
+            ArcOrLine::Arc(mut arc) => {
+                arc.center = scale_point(arc.center);
+                arc.radii *= scale;
+                ArcOrLine::Arc(arc)
+            }
+        })
+}
+
+/// Write a DXF group, a code and a value on separate lines.
+fn group(out: &mut String, code: u32, value: impl std::fmt::Display) {
+    let _ = writeln!(out, "{:>3}\n{}", code, value);
+}
+
+/// Write a point as the x and y groups starting at `code`.
+fn point(out: &mut String, code: u32, p: Point) {
+    group(out, code, p.x);
+    group(out, code + 10, p.y);
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Rect;
+
+    use super::*;
+    use crate::SplineSpec;
+
+    #[test]
+    fn rounded_rect() {
+        let mut spec = SplineSpec::rounded_rect(Rect::new(0., 0., 100., 50.), 10.);
+        let splines = [spec.solve()];
+        let options = ExportOptions {
+            tolerance: 0.01,
+            scale: HPGL_UNITS_PER_MM,
+        };
+        let dxf = to_dxf(&splines, &options);
+        assert!(dxf.starts_with("  0\nSECTION\n  2\nENTITIES\n"));
+        assert!(dxf.ends_with("  0\nENDSEC\n  0\nEOF\n"));
+        assert_eq!(dxf.matches("\nLINE\n").count(), 4);
+        assert!(dxf.matches("\nARC\n").count() >= 4);
+        // Radii are scaled.
+        assert!(dxf.contains(" 40\n400\n") || dxf.contains(" 40\n399.99"));
+        let hpgl = to_hpgl(&splines, &options);
+        assert!(hpgl.starts_with("IN;SP1;PU"));
+        assert!(hpgl.ends_with("PU;SP0;\n"));
+        // The pen goes down before drawing anything, then four lines.
+        assert!(hpgl.find("PD;").unwrap() < hpgl.find("AA").unwrap());
+        assert_eq!(hpgl.matches("PD").count(), 5);
+        // The arcs turn a full circle between them.
+        let sweep: f64 = hpgl
+            .split(';')
+            .filter(|cmd| cmd.starts_with("AA"))
+            .map(|cmd| cmd.rsplit(',').next().unwrap().parse::<f64>().unwrap())
+            .sum();
+        assert!((sweep - 360.0).abs() < 0.5, "{}", sweep);
+    }
+
+    #[test]
+    fn hpgl_returns_to_start() {
+        // Many small arcs, each with a center off the plotter grid.
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0.3, 0.3));
+        spec.spline_to(None, None, Point::new(20.3, 10.6), true);
+        spec.spline_to(None, None, Point::new(0.7, 30.2), true);
+        spec.spline_to(None, None, Point::new(-10.4, 12.5), true);
+        spec.spline_to(None, None, Point::new(0.3, 0.3), true);
+        spec.close();
+        let splines = [spec.solve()];
+        let options = ExportOptions {
+            tolerance: 1e-4,
+            scale: 1.0,
+        };
+        let hpgl = to_hpgl(&splines, &options);
+        // Follow the plotter around the circle.
+        let mut p = Point::ZERO;
+        for cmd in hpgl.split(';') {
+            let nums: Vec<f64> = cmd
+                .get(2..)
+                .unwrap_or("")
+                .split(',')
+                .filter_map(|n| n.parse().ok())
+                .collect();
+            match (&cmd.get(..2), nums.as_slice()) {
+                (Some("PU"), &[x, y]) | (Some("PD"), &[x, y]) => p = Point::new(x, y),
+                (Some("AA"), &[x, y, sweep]) => {
+                    let c = Point::new(x, y);
+                    let from = p - c;
+                    p = c + from.hypot() * Vec2::from_angle(from.atan2() + sweep.to_radians());
+                }
+                _ => (),
+            }
+        }
+        assert!(hpgl.matches("AA").count() > 50);
+        assert!(p.distance(Point::ZERO) < 0.5, "{:?}", p);
+    }
+}
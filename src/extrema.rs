@@ -0,0 +1,147 @@
+//! Points where the tangent is horizontal or vertical.
+
+use std::f64::consts::FRAC_PI_2;
+
+use crate::spline::{Segment, SplineSpec};
+
+/// The number of intervals the tangent angle is sampled over when looking
+/// for extrema.
+const N_SAMPLES: usize = 32;
+
+/// How close, in radians, the tangent at an end of a segment must be to an
+/// axis for the end to count as the extremum.
+const ANGLE_TOLERANCE: f64 = 1e-3;
+
+impl Segment {
+    /// The arclength parameters, in increasing order, of the points inside
+    /// the segment where the tangent is horizontal or vertical.
+    ///
+    /// Extrema at or very near the ends of the segment are not included.
+    pub fn extrema(&self) -> Vec<f64> {
+        self.extrema_with_angles()
+            .into_iter()
+            .map(|(t, _)| t)
+            .collect()
+    }
+
+    /// The extrema, each with its axis-aligned tangent angle.
+    fn extrema_with_angles(&self) -> Vec<(f64, f64)> {
+        let mut result = Vec::new();
+        if self.is_line() {
+            return result;
+        }
+        // The tangent angle is this offset plus the hyperbezier's theta.
+        let offset = self.tangent_angle(0.0) - self.hb.compute_theta(0.0);
+        let th = |t| offset + self.hb.compute_theta(t);
+        let (th_start, th_end) = (th(0.0), th(1.0));
+        let mut t0 = 0.0;
+        let mut th0 = th_start;
+        for i in 1..=N_SAMPLES {
+            let t1 = i as f64 / N_SAMPLES as f64;
+            let th1 = th(t1);
+            let (lo, hi) = (th0.min(th1), th0.max(th1));
+            let mut k = (lo / FRAC_PI_2).ceil();
+            while k * FRAC_PI_2 <= hi {
+                let target = k * FRAC_PI_2;
+                k += 1.0;
+                if (target - th_start).abs() < ANGLE_TOLERANCE
+                    || (target - th_end).abs() < ANGLE_TOLERANCE
+                {
+                    continue;
+                }
+                // Bisect, keeping `a` on the same side of the target as t0.
+                let (mut a, mut b) = (t0, t1);
+                let sign = (th0 - target).signum();
+                for _ in 0..50 {
+                    let m = 0.5 * (a + b);
+                    if (th(m) - target).signum() == sign {
+                        a = m;
+                    } else {
+                        b = m;
+                    }
+                }
+                result.push((0.5 * (a + b), target));
+            }
+            t0 = t1;
+            th0 = th1;
+        }
+        result.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        result
+    }
+}
+
+impl SplineSpec {
+    /// Insert smooth on-curve points at the extrema of every segment,
+    /// preserving the shape, and return the number of points added.
+    ///
+    /// This is the usual preparation of outlines for fonts. Curves are split
+    /// as by [`subdivide`](SplineSpec::subdivide), with the control points
+    /// at each new point aligned exactly with the axis.
+    pub fn add_extrema_points(&mut self) -> usize {
+        let segs = self.solve().segments().to_vec();
+        let mut count = 0;
+        // Work backwards so that earlier segment indices stay valid.
+        for (seg_ix, seg) in segs.iter().enumerate().rev() {
+            let cuts = seg.extrema_with_angles();
+            if !cuts.is_empty() {
+                count += cuts.len();
+                self.split_segment(seg, seg_ix, &cuts);
+            }
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{util, Element, SplineSpec};
+
+    #[test]
+    fn add_extrema_points() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 100.), true);
+        spec.spline_to(None, None, Point::new(200., 20.), true);
+        spec.spline_to(None, None, Point::new(180., -50.), false);
+        let orig = spec.solve().into_owned();
+        let added = spec.add_extrema_points();
+        assert!(added > 0);
+        assert_eq!(spec.elements().len(), 4 + added);
+        let spline = spec.solve().into_owned();
+        assert!(spline.segments().iter().all(|seg| seg.extrema().is_empty()));
+        // The control points either side of each new point are level or
+        // plumb with it.
+        for pair in spec.elements()[1..].windows(2) {
+            if let (Element::SplineTo(_, Some(p2), p, _), Element::SplineTo(Some(p1), ..)) =
+                (pair[0], pair[1])
+            {
+                if orig.segments().iter().any(|seg| seg.p3 == p) {
+                    continue;
+                }
+                let (v2, v1) = (p - p2, p1 - p);
+                assert!(
+                    v2.x.abs() < 1e-9 && v1.x.abs() < 1e-9
+                        || v2.y.abs() < 1e-9 && v1.y.abs() < 1e-9
+                );
+            }
+        }
+        let pts: Vec<Point> = orig
+            .segments()
+            .iter()
+            .flat_map(|seg| seg.flatten_params(1e-3))
+            .map(|(_, p)| p)
+            .collect();
+        for seg in spline.segments() {
+            for i in 0..=8 {
+                let p = seg.eval(i as f64 / 8.0);
+                let d = pts
+                    .windows(2)
+                    .map(|w| (p - w[0].lerp(w[1], util::nearest_on_line(p, w[0], w[1]))).hypot())
+                    .fold(f64::INFINITY, f64::min);
+                assert!(d < 0.1, "{}", d);
+            }
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Fitting splines through sampled points, such as freehand strokes.
+
+use kurbo::Point;
+
+use crate::spline::SplineSpec;
+
+/// Options for fitting a spline through points.
+#[derive(Clone, Copy, Debug)]
+pub struct FitOptions {
+    /// The largest distance between the points and the polygon the spline
+    /// is fitted through.
+    pub tolerance: f64,
+    /// The smallest turn, in radians, at a vertex of the polygon for it to
+    /// become a corner.
+    pub corner_angle: f64,
+}
+
+impl Default for FitOptions {
+    fn default() -> FitOptions {
+        FitOptions {
+            tolerance: 1.0,
+            corner_angle: 1.2,
+        }
+    }
+}
+
+impl SplineSpec {
+    /// Fit a spec through a sequence of points, such as a freehand stroke.
+    ///
+    /// The points are simplified to a polygon with the Ramer-Douglas-Peucker
+    /// algorithm, and the vertices where the polygon turns sharply become
+    /// corners. The other vertices become smooth auto points, and a run
+    /// between two corners is a straight line. The ends of an open path
+    /// count as corners.
+    ///
+    /// Repeated points are ignored. The result has no segments if there are
+    /// fewer than two distinct points.
+    pub fn fit_points(pts: &[Point], is_closed: bool, options: &FitOptions) -> SplineSpec {
+        let mut pts = pts.to_vec();
+        pts.dedup();
+        if is_closed && pts.len() > 1 && pts.first() == pts.last() {
+            pts.pop();
+        }
+        let mut spec = SplineSpec::new();
+        if pts.len() < 2 {
+            if let Some(&p) = pts.first() {
+                spec.move_to(p);
+            }
+            return spec;
+        }
+        let n_pts = pts.len();
+        let mut keep = vec![0];
+        if is_closed {
+            // Split the closed polygon at the point farthest from the first,
+            // and simplify each half.
+            let far = (1..n_pts)
+                .max_by(|&i, &j| {
+                    let (di, dj) = ((pts[i] - pts[0]).hypot2(), (pts[j] - pts[0]).hypot2());
+                    di.partial_cmp(&dj).unwrap()
+                })
+                .unwrap();
+            douglas_peucker(&pts, 0, far, options.tolerance, &mut keep);
+            keep.push(far);
+            douglas_peucker(&pts, far, n_pts, options.tolerance, &mut keep);
+        } else {
+            douglas_peucker(&pts, 0, n_pts - 1, options.tolerance, &mut keep);
+            keep.push(n_pts - 1);
+        }
+        let poly: Vec<Point> = keep.iter().map(|&i| pts[i]).collect();
+        let n = poly.len();
+        let is_corner: Vec<bool> = (0..n)
+            .map(|i| {
+                if !is_closed && (i == 0 || i == n - 1) {
+                    return true;
+                }
+                let v0 = poly[i] - poly[(i + n - 1) % n];
+                let v1 = poly[(i + 1) % n] - poly[i];
+                v0.cross(v1).atan2(v0.dot(v1)).abs() >= options.corner_angle
+            })
+            .collect();
+        // Start a closed path at a corner, if there is one.
+        let start = if is_closed {
+            is_corner.iter().position(|&c| c).unwrap_or(0)
+        } else {
+            0
+        };
+        let n_elements = if is_closed { n } else { n - 1 };
+        spec.move_to(poly[start]);
+        for k in 1..=n_elements {
+            let (i0, i1) = ((start + k - 1) % n, (start + k) % n);
+            if is_corner[i0] && is_corner[i1] {
+                spec.line_to(poly[i1], false);
+            } else {
+                spec.spline_to(None, None, poly[i1], !is_corner[i1]);
+            }
+        }
+        if is_closed {
+            spec.close();
+        }
+        spec
+    }
+}
+
+/// Simplify the polyline between `i0` and `i1` by the Ramer-Douglas-Peucker
+/// algorithm, where index `pts.len()` wraps around to 0. Kept indices
+/// strictly between them are pushed in order.
+fn douglas_peucker(pts: &[Point], i0: usize, i1: usize, tolerance: f64, keep: &mut Vec<usize>) {
+    let (p0, p1) = (pts[i0], pts[i1 % pts.len()]);
+    let chord = p1 - p0;
+    let dist = |p: Point| {
+        let v = p - p0;
+        if chord.hypot2() == 0.0 {
+            v.hypot()
+        } else {
+            chord.cross(v).abs() / chord.hypot()
+        }
+    };
+    let worst = (i0 + 1..i1).max_by(|&i, &j| dist(pts[i]).partial_cmp(&dist(pts[j])).unwrap());
+    if let Some(i) = worst {
+        if dist(pts[i]) > tolerance {
+            douglas_peucker(pts, i0, i, tolerance, keep);
+            keep.push(i);
+            douglas_peucker(pts, i, i1, tolerance, keep);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use super::FitOptions;
+    use crate::{util, Element, SplineSpec};
+
+    #[test]
+    fn fit_stroke() {
+        // A half circle, then a sharp turn into a straight run.
+        let mut pts: Vec<Point> = (0..=60)
+            .map(|i| {
+                let th = (i as f64 / 60.0) * std::f64::consts::PI;
+                Point::new(100. - 100. * th.cos(), 100. * th.sin())
+            })
+            .collect();
+        pts.extend((1..=20).map(|i| Point::new(200. + 5. * i as f64, 0.)));
+        let options = FitOptions::default();
+        let mut spec = SplineSpec::fit_points(&pts, false, &options);
+        let els = spec.elements();
+        let corner = els
+            .iter()
+            .position(|el| el.endpoint().distance(Point::new(200., 0.)) < 1e-9)
+            .unwrap();
+        assert!(!els[corner].is_smooth());
+        assert!(els[1..corner]
+            .iter()
+            .all(|el| matches!(el, Element::SplineTo(None, None, _, true))));
+        assert_eq!(
+            els.last(),
+            Some(&Element::LineTo(Point::new(300., 0.), false))
+        );
+        let spline = spec.solve();
+        let curve: Vec<Point> = spline
+            .segments()
+            .iter()
+            .flat_map(|seg| seg.flatten_params(0.01))
+            .map(|(_, p)| p)
+            .collect();
+        for &p in &pts {
+            let d = curve
+                .windows(2)
+                .map(|w| (p - w[0].lerp(w[1], util::nearest_on_line(p, w[0], w[1]))).hypot())
+                .fold(f64::INFINITY, f64::min);
+            assert!(d < 2.0 * options.tolerance, "{} {:?}", d, p);
+        }
+    }
+
+    #[test]
+    fn fit_line() {
+        let pts = [0.0, 0.0, 1.0, 2.0, 3.0, 3.0].map(|x| Point::new(10. * x, 5. * x));
+        let spec = SplineSpec::fit_points(&pts, false, &FitOptions::default());
+        assert_eq!(
+            spec.elements(),
+            &[
+                Element::MoveTo(Point::ZERO),
+                Element::LineTo(Point::new(30., 15.), false)
+            ]
+        );
+    }
+}
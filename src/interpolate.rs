@@ -0,0 +1,401 @@
+//! Interpolation between compatible splines.
+//!
+//! Two splines are compatible when they have the same structure: the same
+//! sequence of element types, with the same auto and explicit control points
+//! and the same smoothness at each point, and the same windings. This is the usual requirement for
+//! interpolating between masters of a variable font.
+
+use std::fmt;
+
+use kurbo::Point;
+
+use crate::hyperbezier::ThetaParams;
+use crate::spline::{Element, Segment, Spline, SplineSpec};
+
+/// The reason two specs are not compatible.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mismatch {
+    /// One spec is closed and the other is open.
+    Closed,
+    /// The specs have different numbers of elements.
+    Length(usize, usize),
+    /// The elements at this index differ in type, in which control points
+    /// are given, or in smoothness.
+    Element(usize),
+    /// The segments of the elements at this index wind by different
+    /// numbers of turns.
+    Winding(usize),
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mismatch::Closed => write!(f, "one spline is closed and the other is open"),
+            Mismatch::Length(a, b) => write!(f, "element counts differ ({} and {})", a, b),
+            Mismatch::Element(i) => write!(f, "element {} differs", i),
+            Mismatch::Winding(i) => write!(f, "windings of element {} differ", i),
+        }
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+impl SplineSpec {
+    /// Whether this spec has the same structure as `other`.
+    ///
+    /// Only compatible specs can be interpolated.
+    pub fn is_compatible(&self, other: &SplineSpec) -> bool {
+        check_compatible(self, other).is_ok()
+    }
+}
+
+/// Check that two specs have the same structure, reporting the first
+/// difference.
+pub fn check_compatible(a: &SplineSpec, b: &SplineSpec) -> Result<(), Mismatch> {
+    if a.is_closed() != b.is_closed() {
+        return Err(Mismatch::Closed);
+    }
+    let (n_a, n_b) = (a.elements().len(), b.elements().len());
+    if n_a != n_b {
+        return Err(Mismatch::Length(n_a, n_b));
+    }
+    if let Some(i) = a
+        .elements()
+        .iter()
+        .zip(b.elements())
+        .position(|(a, b)| !elements_compatible(a, b))
+    {
+        return Err(Mismatch::Element(i));
+    }
+    match (0..n_a).find(|&i| a.winding(i) != b.winding(i)) {
+        Some(i) => Err(Mismatch::Winding(i)),
+        None => Ok(()),
+    }
+}
+
+/// Modify two specs so that they are compatible, without changing their
+/// shapes more than necessary.
+///
+/// When the element counts differ, points are inserted into the spec with
+/// fewer elements, with [`SplineSpec::subdivide`], at the relative arclength
+/// positions of the unmatched points of the other, or at the middle of its
+/// longest segment where those fall on its existing points. Then any
+/// elements that still differ are replaced in both specs by spline segments
+/// with explicit control points taken from the solution. Where the
+/// smoothness of a point differs, the following element is also made
+/// explicit, and the smoothness from `a` is used.
+///
+/// This is best-effort: points are matched by arclength alone, so the
+/// correspondence may not be the one a designer would choose. It fails if
+/// one spec is closed and the other open, if one is empty, or if the
+/// windings of matching segments differ.
+pub fn make_compatible(a: &mut SplineSpec, b: &mut SplineSpec) -> Result<(), Mismatch> {
+    if a.is_closed() != b.is_closed() {
+        return Err(Mismatch::Closed);
+    }
+    let (n_a, n_b) = (a.elements().len(), b.elements().len());
+    if n_a.min(n_b) < 2 && n_a != n_b {
+        return Err(Mismatch::Length(n_a, n_b));
+    }
+    if n_a < n_b {
+        insert_matching(a, b);
+    } else if n_b < n_a {
+        insert_matching(b, a);
+    }
+    let n = a.elements().len();
+    let mut explicit = vec![false; n];
+    for i in 1..n {
+        let (el_a, el_b) = (a.elements()[i], b.elements()[i]);
+        if !elements_compatible(&el_a, &el_b) {
+            explicit[i] = true;
+            if el_a.is_smooth() != el_b.is_smooth() {
+                if i + 1 < n {
+                    explicit[i + 1] = true;
+                } else if a.is_closed() {
+                    explicit[1] = true;
+                }
+            }
+        }
+    }
+    make_explicit(a, &explicit, None);
+    let smooth: Vec<bool> = a.elements().iter().map(Element::is_smooth).collect();
+    make_explicit(b, &explicit, Some(&smooth));
+    check_compatible(a, b)
+}
+
+/// Subdivide `few` until it has as many elements as `many`.
+fn insert_matching(few: &mut SplineSpec, many: &mut SplineSpec) {
+    let targets = joints(&many.solve());
+    while few.elements().len() < many.elements().len() {
+        let spline = few.solve();
+        let have = joints(&spline);
+        let dist = |u: f64| {
+            have.iter()
+                .fold(u.min(1.0 - u), |d, &v| d.min((u - v).abs()))
+        };
+        let u = targets
+            .iter()
+            .copied()
+            .max_by(|&u0, &u1| dist(u0).total_cmp(&dist(u1)))
+            .unwrap();
+        // If every unmatched point falls on an existing one, split the
+        // longest segment instead, rather than leave one of no length.
+        let (seg_ix, t) = spline
+            .locate_interior(u * spline.arclen())
+            .unwrap_or_else(|| {
+                let segs = spline.segments();
+                let longest = (0..segs.len())
+                    .max_by(|&i, &j| segs[i].arclen().total_cmp(&segs[j].arclen()))
+                    .unwrap();
+                (longest, 0.5)
+            });
+        few.subdivide(seg_ix, t);
+    }
+}
+
+/// The relative arclength positions of the interior on-curve points.
+fn joints(spline: &Spline) -> Vec<f64> {
+    let total = spline.arclen();
+    let mut s = 0.0;
+    let segs = spline.segments();
+    segs[..segs.len() - 1]
+        .iter()
+        .map(|seg| {
+            s += seg.arclen();
+            s / total
+        })
+        .collect()
+}
+
+/// Replace the flagged elements with explicit spline segments, optionally
+/// setting the smoothness of those elements.
+fn make_explicit(spec: &mut SplineSpec, explicit: &[bool], smooth: Option<&[bool]>) {
+    let segs = spec.solve().segments().to_vec();
+    for (i, el) in spec.elements_mut().iter_mut().enumerate().skip(1) {
+        if explicit[i] {
+            let seg = &segs[i - 1];
+            let is_smooth = smooth.map(|s| s[i]).unwrap_or_else(|| el.is_smooth());
+            let (p1, p2) = if seg.is_line() {
+                (
+                    seg.p0.lerp(seg.p3, 1.0 / 3.0),
+                    seg.p0.lerp(seg.p3, 2.0 / 3.0),
+                )
+            } else {
+                (seg.p1, seg.p2)
+            };
+            *el = Element::SplineTo(Some(p1), Some(p2), seg.p3, is_smooth);
+        }
+    }
+}
+
+/// Interpolate between two compatible specs.
+///
+/// All points, including explicit control points, are interpolated linearly,
+/// as are the sweep angles of arcs. A `t` of 0 gives `a` and 1 gives `b`.
+/// The windings, metadata and locks are those of `a`.
+///
+/// Returns `None` if the specs are not compatible.
+pub fn interpolate_specs(a: &SplineSpec, b: &SplineSpec, t: f64) -> Option<SplineSpec> {
+    if !a.is_compatible(b) {
+        return None;
+    }
+    let mut spec = SplineSpec::new();
+    for (el_a, el_b) in a.elements().iter().zip(b.elements()) {
+        match (*el_a, *el_b) {
+            (Element::MoveTo(p_a), Element::MoveTo(p_b)) => spec.move_to(p_a.lerp(p_b, t)),
+            (Element::LineTo(p_a, is_smooth), Element::LineTo(p_b, _)) => {
+                spec.line_to(p_a.lerp(p_b, t), is_smooth)
+            }
+            (
+                Element::SplineTo(p1_a, p2_a, p3_a, is_smooth),
+                Element::SplineTo(p1_b, p2_b, p3_b, _),
+            ) => spec.spline_to(
+                lerp_opt(p1_a, p1_b, t),
+                lerp_opt(p2_a, p2_b, t),
+                p3_a.lerp(p3_b, t),
+                is_smooth,
+            ),
+            (Element::ArcTo(p_a, sweep_a, is_smooth), Element::ArcTo(p_b, sweep_b, _)) => spec
+                .arc_to(
+                    p_a.lerp(p_b, t),
+                    sweep_a + t * (sweep_b - sweep_a),
+                    is_smooth,
+                ),
+            _ => unreachable!(),
+        }
+    }
+    if a.is_closed() {
+        spec.close();
+    }
+    spec.windings = a.windings.clone();
+    spec.metadata = a.metadata.clone();
+    spec.locks = a.locks.clone();
+    Some(spec)
+}
+
+impl<'a> Spline<'a> {
+    /// Interpolate between two solved splines with the same number of
+    /// segments.
+    ///
+    /// Rather than interpolating control points, this interpolates the end
+    /// points, tangent angles and bias of each segment, which preserves the
+    /// character of the curves better when the two splines differ a lot.
+    /// The result is not solved again, so continuity at smooth points is
+    /// only approximate when the two inputs have different curvature there.
+    ///
+    /// Returns `None` if the splines have different numbers of segments or
+    /// differ in whether they are closed.
+    pub fn interpolate(&self, other: &Spline, t: f64) -> Option<Spline<'static>> {
+        if self.segments().len() != other.segments().len() || self.is_closed() != other.is_closed()
+        {
+            return None;
+        }
+        let segments = self
+            .segments()
+            .iter()
+            .zip(other.segments())
+            .map(|(a, b)| a.interpolate(b, t))
+            .collect();
+        Some(Spline::from_segments(segments, self.is_closed()))
+    }
+}
+
+impl Segment {
+    /// Interpolate the end points, tangent angles and bias of two segments.
+    fn interpolate(&self, other: &Segment, t: f64) -> Segment {
+        let p0 = self.p0.lerp(other.p0, t);
+        let p3 = self.p3.lerp(other.p3, t);
+        if self.is_line() && other.is_line() {
+            return Segment::line(p0, p3);
+        }
+        let lerp = |x0: f64, x1: f64| x0 + t * (x1 - x0);
+        let r_a = self.hb.compute();
+        let r_b = other.hb.compute();
+        let params = ThetaParams {
+            th0: lerp(r_a.th0, r_b.th0),
+            bias0: lerp(self.hb.bias0, other.hb.bias0),
+            th1: lerp(r_a.th1, r_b.th1),
+            bias1: lerp(self.hb.bias1, other.hb.bias1),
+        };
+        Segment::solve_theta(p0, p3, &params)
+    }
+}
+
+fn elements_compatible(a: &Element, b: &Element) -> bool {
+    match (a, b) {
+        (Element::MoveTo(_), Element::MoveTo(_)) => true,
+        (Element::LineTo(_, s_a), Element::LineTo(_, s_b)) => s_a == s_b,
+        (Element::SplineTo(p1_a, p2_a, _, s_a), Element::SplineTo(p1_b, p2_b, _, s_b)) => {
+            p1_a.is_some() == p1_b.is_some() && p2_a.is_some() == p2_b.is_some() && s_a == s_b
+        }
+        (Element::ArcTo(_, _, s_a), Element::ArcTo(_, _, s_b)) => s_a == s_b,
+        _ => false,
+    }
+}
+
+fn lerp_opt(a: Option<Point>, b: Option<Point>, t: f64) -> Option<Point> {
+    a.zip(b).map(|(a, b)| a.lerp(b, t))
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use super::{check_compatible, interpolate_specs, make_compatible, Mismatch};
+    use crate::SplineSpec;
+
+    fn blob(scale: f64) -> SplineSpec {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100. * scale, 0.), true);
+        spec.spline_to(None, None, Point::new(100. * scale, 100.), true);
+        spec.line_to(Point::new(0., 0.), false);
+        spec.close();
+        spec
+    }
+
+    #[test]
+    fn interpolate_points() {
+        let a = blob(1.0);
+        let b = blob(2.0);
+        let mid = interpolate_specs(&a, &b, 0.5).unwrap();
+        assert!(mid.is_compatible(&a));
+        assert_eq!(mid.elements()[2].endpoint(), Point::new(150., 100.));
+    }
+
+    #[test]
+    fn interpolate_keeps_windings() {
+        let mut a = blob(1.0);
+        let mut b = blob(2.0);
+        a.set_winding(1, 1);
+        assert_eq!(check_compatible(&a, &b), Err(Mismatch::Winding(1)));
+        b.set_winding(1, 1);
+        assert_eq!(interpolate_specs(&a, &b, 0.0).unwrap(), a);
+        assert_eq!(interpolate_specs(&a, &b, 1.0).unwrap(), b);
+    }
+
+    #[test]
+    fn incompatible() {
+        let a = blob(1.0);
+        let mut b = blob(1.0);
+        b.elements_mut()[3] = crate::Element::LineTo(Point::new(0., 0.), true);
+        assert_eq!(check_compatible(&a, &b), Err(Mismatch::Element(3)));
+        assert!(interpolate_specs(&a, &b, 0.5).is_none());
+    }
+
+    #[test]
+    fn make_circle_compatible_with_blob() {
+        let mut a = SplineSpec::circle(Point::new(50., 50.), 50.);
+        let mut b = blob(1.0);
+        assert_eq!(check_compatible(&a, &b), Err(Mismatch::Length(5, 4)));
+        let circle = a.solve().into_owned();
+        make_compatible(&mut a, &mut b).unwrap();
+        assert_eq!(b.elements().len(), 5);
+        assert!(interpolate_specs(&a, &b, 0.5).is_some());
+        // The circle is still a circle.
+        for seg in a.solve().segments() {
+            for i in 0..=4 {
+                let r = (seg.eval(i as f64 * 0.25) - Point::new(50., 50.)).hypot();
+                assert!((r - 50.0).abs() < 0.01);
+            }
+        }
+        assert!((a.solve().arclen() - circle.arclen()).abs() < 0.01);
+    }
+
+    #[test]
+    fn matching_skips_existing_points() {
+        // The extra point of `b` is at the same place as the middle point
+        // of `a`, so matching it exactly would leave a segment of no length.
+        let mut a = SplineSpec::new();
+        a.move_to(Point::new(0., 0.));
+        a.line_to(Point::new(100., 0.), false);
+        a.line_to(Point::new(200., 0.), false);
+        let mut b = SplineSpec::new();
+        b.move_to(Point::new(0., 0.));
+        b.line_to(Point::new(100., 0.), false);
+        b.line_to(Point::new(100., 0.), false);
+        b.line_to(Point::new(200., 0.), false);
+        make_compatible(&mut a, &mut b).unwrap();
+        assert_eq!(a.elements().len(), 4);
+        for seg in a.solve().segments() {
+            assert!(seg.arclen() > 1.0, "{:?}", seg);
+        }
+    }
+
+    #[test]
+    fn interpolate_solved() {
+        let mut a = blob(1.0);
+        let mut b = blob(2.0);
+        let spline_a = a.solve().into_owned();
+        let spline_b = b.solve();
+        let same = spline_a.interpolate(&spline_b, 0.0).unwrap();
+        // The tangent angles are solved to within 1e-3.
+        for (s0, s1) in same.segments().iter().zip(spline_a.segments()) {
+            assert!((s0.p1 - s1.p1).hypot() < 0.01);
+            assert!((s0.k0 - s1.k0).abs() <= 1e-2 * s1.k0.abs());
+        }
+        let mid = spline_a.interpolate(&spline_b, 0.5).unwrap();
+        assert_eq!(mid.segments()[1].p3, Point::new(150., 100.));
+        assert!(mid.segments()[2].is_line());
+    }
+}
@@ -0,0 +1,149 @@
+//! Intersections between spline segments.
+//!
+//! Parameters are arclength parameters within a segment, from 0 to 1. For a
+//! whole [`Spline`], a parameter of `i + t` refers to parameter `t` of
+//! segment `i`.
+
+use kurbo::Point;
+
+use crate::spline::{Segment, Spline};
+
+impl Segment {
+    /// Find the points where this segment crosses another.
+    ///
+    /// Returns pairs of parameters, the first on `self` and the second on
+    /// `other`. The curves are flattened to within `tolerance`, so the
+    /// results are accurate to about that distance.
+    pub fn intersect(&self, other: &Segment, tolerance: f64) -> Vec<(f64, f64)> {
+        let a = self.flatten_params(tolerance);
+        let b = other.flatten_params(tolerance);
+        polyline_crossings(&a, &b, |_, _| false)
+    }
+}
+
+impl<'a> Spline<'a> {
+    /// Find the points where the spline crosses itself.
+    ///
+    /// Returns pairs of parameters `(u0, u1)` with `u0 < u1`, where a
+    /// parameter of `i + t` refers to parameter `t` of segment `i`. The
+    /// meeting point of adjacent segments is not reported.
+    ///
+    /// The curves are flattened to within `tolerance`, so the results are
+    /// accurate to about that distance.
+    pub fn self_intersections(&self, tolerance: f64) -> Vec<(f64, f64)> {
+        let segs = self.segments();
+        let n = segs.len();
+        let flat: Vec<_> = segs.iter().map(|s| s.flatten_params(tolerance)).collect();
+        let mut result = Vec::new();
+        for i in 0..n {
+            // Pieces of the same segment which share a vertex.
+            let adjacent = |ia: usize, ib: usize| ia + 1 >= ib;
+            for (t0, t1) in polyline_crossings(&flat[i], &flat[i], adjacent) {
+                if t0 < t1 {
+                    result.push((i as f64 + t0, i as f64 + t1));
+                }
+            }
+            for j in i + 1..n {
+                let last_i = flat[i].len() - 2;
+                let last_j = flat[j].len() - 2;
+                // The pieces which meet at the joint between segments.
+                let adjacent = |ia: usize, ib: usize| {
+                    (j == i + 1 && ia == last_i && ib == 0)
+                        || (self.is_closed() && i == 0 && j == n - 1 && ia == 0 && ib == last_j)
+                };
+                for (t0, t1) in polyline_crossings(&flat[i], &flat[j], adjacent) {
+                    result.push((i as f64 + t0, j as f64 + t1));
+                }
+            }
+        }
+        result.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        result
+    }
+}
+
+/// Find crossings between two parametrized polylines.
+///
+/// Pairs of pieces (by index) for which `skip` returns true are ignored.
+/// Pieces are treated as half-open, so a crossing exactly at a vertex is
+/// only reported once.
+fn polyline_crossings(
+    a: &[(f64, Point)],
+    b: &[(f64, Point)],
+    skip: impl Fn(usize, usize) -> bool,
+) -> Vec<(f64, f64)> {
+    let mut result = Vec::new();
+    for (ia, wa) in a.windows(2).enumerate() {
+        let (ta0, pa0) = wa[0];
+        let (ta1, pa1) = wa[1];
+        for (ib, wb) in b.windows(2).enumerate() {
+            if skip(ia, ib) {
+                continue;
+            }
+            let (tb0, pb0) = wb[0];
+            let (tb1, pb1) = wb[1];
+            if pa0.x.max(pa1.x) < pb0.x.min(pb1.x)
+                || pb0.x.max(pb1.x) < pa0.x.min(pa1.x)
+                || pa0.y.max(pa1.y) < pb0.y.min(pb1.y)
+                || pb0.y.max(pb1.y) < pa0.y.min(pa1.y)
+            {
+                continue;
+            }
+            let da = pa1 - pa0;
+            let db = pb1 - pb0;
+            let denom = da.cross(db);
+            if denom == 0.0 {
+                continue;
+            }
+            let v = pb0 - pa0;
+            let ua = v.cross(db) / denom;
+            let ub = v.cross(da) / denom;
+            if (0.0..1.0).contains(&ua) && (0.0..1.0).contains(&ub) {
+                result.push((ta0 + ua * (ta1 - ta0), tb0 + ub * (tb1 - tb0)));
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::SplineSpec;
+
+    #[test]
+    fn crossing_lines() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 100.), false);
+        spec.line_to(Point::new(100., 0.), false);
+        spec.line_to(Point::new(0., 100.), false);
+        let crossings = spec.solve().self_intersections(0.1);
+        assert_eq!(crossings.len(), 1);
+        let (u0, u1) = crossings[0];
+        assert!((u0 - 0.5).abs() < 1e-9 && (u1 - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn figure_eight() {
+        let pts = [
+            (120., 0.),
+            (80., -40.),
+            (-80., 40.),
+            (-120., 0.),
+            (-80., -40.),
+        ];
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(80., 40.));
+        for &(x, y) in &pts {
+            spec.spline_to(None, None, Point::new(x, y), true);
+        }
+        spec.spline_to(None, None, Point::new(80., 40.), true);
+        spec.close();
+        let spline = spec.solve();
+        let crossings = spline.self_intersections(0.01);
+        assert_eq!(crossings.len(), 1);
+        let (u0, u1) = crossings[0];
+        assert!((u0 - 2.5).abs() < 1e-3 && (u1 - 5.5).abs() < 1e-3);
+    }
+}
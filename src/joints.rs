@@ -0,0 +1,121 @@
+//! Iterating over the joints and segments of a solved spline.
+
+use crate::spline::{Segment, Spline};
+use crate::util;
+
+/// The largest turn, in radians, for a joint to count as smooth.
+const ANGLE_TOLERANCE: f64 = 1e-6;
+
+/// The largest relative jump in curvature for a joint to count as
+/// curvature continuous.
+const CURVATURE_TOLERANCE: f64 = 1e-2;
+
+/// The continuity of a spline at a joint between segments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Continuity {
+    /// The tangent direction changes: a corner.
+    Position,
+    /// The tangent is continuous but the curvature is not.
+    Tangent,
+    /// The tangent and curvature are both continuous.
+    Curvature,
+}
+
+/// The meeting point of two segments of a spline.
+#[derive(Clone, Copy, Debug)]
+pub struct Joint<'a> {
+    /// The index of the on-curve point, as in [`PickResult::OnCurve`].
+    ///
+    /// [`PickResult::OnCurve`]: crate::PickResult::OnCurve
+    pub index: usize,
+    /// The segment ending at the joint.
+    pub prev: &'a Segment,
+    /// The segment starting at the joint.
+    pub next: &'a Segment,
+    /// The change in tangent angle at the joint, in radians.
+    pub angle: f64,
+    pub continuity: Continuity,
+}
+
+impl<'a> Spline<'a> {
+    /// Iterate over the joints between segments.
+    ///
+    /// For a closed spline, this includes the joint at the start point,
+    /// which comes first.
+    pub fn iter_joints(&self) -> impl Iterator<Item = Joint<'_>> + '_ {
+        let segs = self.segments();
+        let n = segs.len();
+        let start = if self.is_closed() { 0 } else { 1 };
+        (start..n).map(move |i| {
+            let prev = &segs[(i + n - 1) % n];
+            let next = &segs[i];
+            let angle = util::mod_tau(next.tangent_angle(0.0) - prev.tangent_angle(1.0));
+            let k_scale = prev.k1.abs().max(next.k0.abs());
+            let continuity = if angle.abs() > ANGLE_TOLERANCE {
+                Continuity::Position
+            } else if (prev.k1 - next.k0).abs() > CURVATURE_TOLERANCE * k_scale + 1e-12 {
+                Continuity::Tangent
+            } else {
+                Continuity::Curvature
+            };
+            Joint {
+                index: i,
+                prev,
+                next,
+                angle,
+                continuity,
+            }
+        })
+    }
+
+    /// Iterate over the straight segments, with their indices.
+    pub fn iter_lines(&self) -> impl Iterator<Item = (usize, &Segment)> + '_ {
+        self.segments()
+            .iter()
+            .enumerate()
+            .filter(|(_, seg)| seg.is_line())
+    }
+
+    /// Iterate over the curved segments, with their indices.
+    pub fn iter_curves(&self) -> impl Iterator<Item = (usize, &Segment)> + '_ {
+        self.segments()
+            .iter()
+            .enumerate()
+            .filter(|(_, seg)| !seg.is_line())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Point, Rect};
+
+    use super::Continuity;
+    use crate::SplineSpec;
+
+    #[test]
+    fn rounded_rect_joints() {
+        let mut spec = SplineSpec::rounded_rect(Rect::new(0., 0., 100., 50.), 10.);
+        let spline = spec.solve();
+        assert_eq!(spline.iter_lines().count(), 4);
+        assert_eq!(spline.iter_curves().count(), 4);
+        // Lines meet arcs with matching tangents but not curvature.
+        let joints: Vec<_> = spline.iter_joints().collect();
+        assert_eq!(joints.len(), 8);
+        assert!(joints.iter().all(|j| j.continuity == Continuity::Tangent));
+    }
+
+    #[test]
+    fn open_joints() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), false);
+        spec.line_to(Point::new(200., 100.), false);
+        let spline = spec.solve();
+        let joints: Vec<_> = spline.iter_joints().collect();
+        assert_eq!(joints.len(), 2);
+        assert_eq!(joints[0].index, 1);
+        assert_eq!(joints[0].continuity, Continuity::Curvature);
+        assert_eq!(joints[1].continuity, Continuity::Position);
+    }
+}
@@ -0,0 +1,149 @@
+//! Placing items, such as glyphs, along a spline.
+
+use kurbo::{Affine, Vec2};
+
+use crate::spline::Spline;
+
+/// How close to an on-curve point, relative to the length of the spline, a
+/// position is taken to be at it.
+const JOINT_TOLERANCE: f64 = 1e-9;
+
+/// An item placed along a spline.
+#[derive(Clone, Debug)]
+pub struct Placement<T> {
+    pub item: T,
+    /// The transform from the item's coordinates to the spline's.
+    ///
+    /// In item coordinates, the origin is the start of the item's advance
+    /// and the advance runs along the positive x axis.
+    pub transform: Affine,
+}
+
+impl<'a> Spline<'a> {
+    /// The total arclength of the spline.
+    pub fn arclen(&self) -> f64 {
+        self.segments().iter().map(|seg| seg.arclen()).sum()
+    }
+
+    /// Find the segment and arclength parameter at arclength `s` from the
+    /// start.
+    ///
+    /// Values outside the spline are clamped to its ends. The spline must
+    /// have at least one segment.
+    pub(crate) fn locate(&self, s: f64) -> (usize, f64) {
+        let segs = self.segments();
+        let mut s = s.max(0.0);
+        let mut seg_ix = 0;
+        while seg_ix + 1 < segs.len() && s > segs[seg_ix].arclen() {
+            s -= segs[seg_ix].arclen();
+            seg_ix += 1;
+        }
+        (seg_ix, (s / segs[seg_ix].arclen()).min(1.0))
+    }
+
+    /// Find the segment and arclength parameter at arclength `s` from the
+    /// start, if that is strictly inside a segment.
+    ///
+    /// Positions outside the spline, or within a tiny fraction of its
+    /// length of one of its on-curve points, give `None`, as inserting a
+    /// point there would leave a segment of no length.
+    pub(crate) fn locate_interior(&self, s: f64) -> Option<(usize, f64)> {
+        let total = self.arclen();
+        let eps = JOINT_TOLERANCE * total;
+        if !(s > eps && s < total - eps) {
+            return None;
+        }
+        let (seg_ix, t) = self.locate(s);
+        let len = self.segments()[seg_ix].arclen();
+        if t * len <= eps || (1.0 - t) * len <= eps {
+            return None;
+        }
+        Some((seg_ix, t))
+    }
+
+    /// Place a sequence of items end to end along the spline.
+    ///
+    /// Each item is given with its advance width, and the first item starts
+    /// at arclength `offset`. Each item is centered on the curve at the
+    /// middle of its advance and rotated to follow the tangent there. Items
+    /// whose middle falls past the end of the spline are dropped.
+    pub fn place_along<T>(
+        &self,
+        offset: f64,
+        items: impl IntoIterator<Item = (f64, T)>,
+    ) -> Vec<Placement<T>> {
+        let segs = self.segments();
+        let mut result = Vec::new();
+        let mut seg_ix = 0;
+        // Arclength at the start of `seg_ix`.
+        let mut seg_start = 0.0;
+        let mut pos = offset;
+        for (advance, item) in items {
+            let mid = pos + 0.5 * advance;
+            pos += advance;
+            if mid < 0.0 {
+                continue;
+            }
+            while seg_ix < segs.len() && seg_start + segs[seg_ix].arclen() < mid {
+                seg_start += segs[seg_ix].arclen();
+                seg_ix += 1;
+            }
+            let seg = match segs.get(seg_ix) {
+                Some(seg) => seg,
+                None => break,
+            };
+            let t = (mid - seg_start) / seg.arclen();
+            let p = seg.eval(t);
+            let th = seg.tangent_angle(t);
+            let transform = Affine::translate(p.to_vec2())
+                * Affine::rotate(th)
+                * Affine::translate(Vec2::new(-0.5 * advance, 0.0));
+            result.push(Placement { item, transform });
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::SplineSpec;
+
+    #[test]
+    fn place_along_line() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), false);
+        spec.line_to(Point::new(100., 100.), false);
+        let spline = spec.solve();
+        assert!((spline.arclen() - 200.0).abs() < 1e-9);
+        let items = (0..7).map(|i| (30.0, i));
+        let placed = spline.place_along(10.0, items);
+        // The last item's middle is at 205, past the end.
+        assert_eq!(placed.len(), 6);
+        assert!((placed[1].transform * Point::ZERO - Point::new(40., 0.)).hypot() < 1e-9);
+        // The fourth item is centered just past the corner, so follows the
+        // second side.
+        let origin = placed[3].transform * Point::ZERO;
+        assert!((origin - Point::new(100., 0.)).hypot() < 1e-9);
+        let dir = placed[3].transform * Point::new(1.0, 0.0) - origin;
+        assert!((dir - kurbo::Vec2::new(0.0, 1.0)).hypot() < 1e-9);
+    }
+
+    #[test]
+    fn place_along_circle() {
+        let mut spec = SplineSpec::circle(Point::ZERO, 100.0);
+        let spline = spec.solve();
+        let circumference = 200.0 * std::f64::consts::PI;
+        assert!((spline.arclen() - circumference).abs() < 1e-6);
+        let items = (0..8).map(|i| (0.125 * circumference, i));
+        for placed in spline.place_along(0.0, items) {
+            let center = placed.transform * Point::new(0.0625 * circumference, 0.0);
+            assert!((center.to_vec2().hypot() - 100.0).abs() < 1e-6);
+            // The baseline is tangent to the circle.
+            let dir = placed.transform * Point::new(1.0, 0.0) - placed.transform * Point::ZERO;
+            assert!(dir.dot(center.to_vec2()).abs() < 1e-6);
+        }
+    }
+}
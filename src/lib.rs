@@ -10,11 +10,70 @@
 //! [Spiro]: https://github.com/raphlinus/spiro
 //! [research spline]: https://github.com/raphlinus/spline-research
 
+mod arcs;
+mod canonical;
+mod debug_svg;
+mod distance;
+mod edit;
+#[cfg(feature = "export")]
+pub mod export;
+mod extrema;
+mod fit;
 mod hyperbezier;
+mod interpolate;
+mod intersect;
+mod joints;
+mod layout;
+mod lock;
+mod mapping;
+mod metadata;
+mod moves;
+#[cfg(feature = "ops")]
+pub mod ops;
+mod orientation;
+mod pick;
+mod quads;
+pub mod quality;
+mod refine;
+mod samples;
+mod seam;
+mod shapes;
 mod simple_spline;
+mod simplify;
+mod sink;
+mod slide;
+mod smooth;
+mod spiro;
 mod spline;
+mod stem;
+mod straighten;
+mod svg;
+#[cfg(feature = "trace")]
+pub mod trace;
 mod util;
+mod winding;
 
-pub use crate::spline::{Element, Segment, Spline, SplineSpec};
-pub use hyperbezier::{HyperBezier, ThetaParams};
-pub use simple_spline::SimpleSpline;
+pub use crate::spline::{Element, Segment, SolveOptions, SolveStats, Spline, SplineSpec};
+pub use arcs::ArcOrLine;
+pub use debug_svg::{debug_svg, DebugOptions};
+pub use distance::Deviation;
+pub use edit::{ControlPoint, EditError};
+pub use fit::FitOptions;
+#[cfg(feature = "research")]
+pub use hyperbezier::research;
+pub use hyperbezier::{
+    HyperBezier, ParamError, Parameterization, RenderOptions, TensionScale, ThetaParams, MAX_BIAS,
+    MAX_K, MIN_BIAS, MIN_MATCH_BIAS, STABLE_TOLERANCE,
+};
+pub use interpolate::{check_compatible, interpolate_specs, make_compatible, Mismatch};
+pub use joints::{Continuity, Joint};
+pub use layout::Placement;
+pub use lock::Lock;
+pub use mapping::PieceSource;
+pub use orientation::Orientation;
+pub use pick::PickResult;
+pub use samples::Sample;
+pub use simple_spline::{BiasLaw, EulerLimit, SimpleSpline};
+pub use sink::{CallbackSink, PathSink};
+pub use spiro::{SpiroExport, SpiroPoint, SpiroType};
+pub use stem::Stem;
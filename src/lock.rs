@@ -0,0 +1,201 @@
+//! Locking one coordinate of on-curve points.
+//!
+//! A point with a locked coordinate is partly interpolating: the solver
+//! keeps the locked coordinate, but slides the point along the other axis
+//! to make the spline as fair as it can. This is useful for keeping a
+//! point on an alignment zone, such as a baseline or overshoot, while
+//! letting the spline choose where along it the point goes.
+
+use kurbo::Vec2;
+#[cfg(feature = "serde")]
+use serde_::{Deserialize, Serialize};
+
+use crate::edit::EditError;
+use crate::quality;
+use crate::spline::SplineSpec;
+
+/// The number of passes over the locked points.
+const N_PASSES: usize = 3;
+
+/// The most Newton steps taken for each point in each pass.
+const N_STEPS: usize = 8;
+
+/// The step used to estimate derivatives, relative to the neighboring
+/// chords.
+const DERIV_STEP: f64 = 1e-3;
+
+/// The largest step, relative to the neighboring chords.
+const MAX_STEP: f64 = 0.25;
+
+/// The coordinate of an on-curve point that is locked.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_")
+)]
+pub enum Lock {
+    /// The x coordinate is fixed, and the point slides vertically.
+    X,
+    /// The y coordinate is fixed, and the point slides horizontally.
+    Y,
+}
+
+impl Lock {
+    /// The direction the point slides in.
+    fn direction(self) -> Vec2 {
+        match self {
+            Lock::X => Vec2::new(0.0, 1.0),
+            Lock::Y => Vec2::new(1.0, 0.0),
+        }
+    }
+}
+
+impl SplineSpec {
+    /// Lock a coordinate of the on-curve point at the end of element `ix`,
+    /// or unlock it with `None`.
+    ///
+    /// In the solved spline, the point is moved along the other axis to
+    /// minimize the variation of curvature of the spline, carrying any
+    /// explicit control points next to it along. The elements of the spec
+    /// are left as they are, and each solve slides the point from there.
+    ///
+    /// This is costly: each locked point is slid by Newton steps with
+    /// derivatives from differencing, and every step solves the whole
+    /// spline several times over. Each locked point costs tens of solves
+    /// of the spline without locks, and at most about 170.
+    pub fn set_lock(&mut self, ix: usize, lock: Option<Lock>) -> Result<(), EditError> {
+        if ix >= self.elements().len() {
+            return Err(EditError::Index(ix));
+        }
+        match lock {
+            Some(lock) => self.locks.insert(ix, lock),
+            None => self.locks.remove(&ix),
+        };
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// The locked coordinate of the point at the end of element `ix`, if
+    /// any.
+    pub fn lock(&self, ix: usize) -> Option<Lock> {
+        self.locks.get(&ix).copied()
+    }
+
+    /// Slide the locked points to minimize the variation of curvature,
+    /// leaving the segments solved, and return the solver residual.
+    ///
+    /// Each point in turn takes Newton steps along its free axis, with
+    /// derivatives estimated by differencing, and a few passes are made so
+    /// that points affecting each other settle. The elements are restored
+    /// afterwards, so only the segments have the points where they slid.
+    pub(crate) fn slide_locked_points(&mut self) -> f64 {
+        let elements = self.elements().to_vec();
+        let locks: Vec<(usize, Lock)> = self.locks.iter().map(|(&ix, &l)| (ix, l)).collect();
+        for _ in 0..N_PASSES {
+            for &(ix, lock) in &locks {
+                let dir = lock.direction();
+                let scale = self.neighbor_scale(ix);
+                if scale == 0.0 {
+                    continue;
+                }
+                let h = DERIV_STEP * scale;
+                let mut e0 = self.energy_after(ix, Vec2::ZERO);
+                for _ in 0..N_STEPS {
+                    let e_plus = self.energy_after(ix, h * dir);
+                    let e_minus = self.energy_after(ix, -h * dir);
+                    let grad = (e_plus - e_minus) / (2.0 * h);
+                    let curv = (e_plus - 2.0 * e0 + e_minus) / (h * h);
+                    let mut step = if curv > 0.0 {
+                        -grad / curv
+                    } else {
+                        -grad.signum() * MAX_STEP * scale
+                    };
+                    step = step.max(-MAX_STEP * scale).min(MAX_STEP * scale);
+                    // Backtrack until the step helps.
+                    let mut accepted = false;
+                    for _ in 0..5 {
+                        let e = self.energy_after(ix, step * dir);
+                        if e < e0 {
+                            self.move_point(ix, step * dir);
+                            e0 = e;
+                            accepted = true;
+                            break;
+                        }
+                        step *= 0.5;
+                    }
+                    if !accepted || step.abs() < DERIV_STEP * h {
+                        break;
+                    }
+                }
+            }
+        }
+        let residual = self.solve_segments();
+        *self.elements_mut() = elements;
+        residual
+    }
+
+    /// The energy of the solved spline, with the point at the end of
+    /// element `ix` moved by `delta`.
+    fn energy_after(&mut self, ix: usize, delta: Vec2) -> f64 {
+        self.move_point(ix, delta);
+        self.solve_segments();
+        let e = quality::energy(self.raw_segments());
+        self.move_point(ix, -delta);
+        e
+    }
+
+    /// The mean length of the chords next to the point at the end of
+    /// element `ix`.
+    fn neighbor_scale(&self, ix: usize) -> f64 {
+        let elements = self.elements();
+        let n = elements.len();
+        let p = elements[ix].endpoint();
+        let mut neighbors = Vec::new();
+        if ix > 0 {
+            neighbors.push(elements[ix - 1].endpoint());
+        } else if self.is_closed() && n > 1 {
+            neighbors.push(elements[n - 2].endpoint());
+        }
+        if ix + 1 < n {
+            neighbors.push(elements[ix + 1].endpoint());
+        } else if self.is_closed() && n > 1 {
+            neighbors.push(elements[1].endpoint());
+        }
+        if neighbors.is_empty() {
+            return 0.0;
+        }
+        neighbors.iter().map(|&q| (q - p).hypot()).sum::<f64>() / neighbors.len() as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use super::Lock;
+    use crate::{EditError, SplineSpec};
+
+    #[test]
+    fn locked_point_slides_to_circle() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(100., 0.));
+        spec.spline_to(None, None, Point::new(0., 100.), true);
+        spec.spline_to(None, None, Point::new(-100., 0.), true);
+        spec.spline_to(None, None, Point::new(0., -130.), true);
+        spec.spline_to(None, None, Point::new(100., 0.), true);
+        spec.close();
+        spec.set_lock(3, Some(Lock::X)).unwrap();
+        assert_eq!(spec.set_lock(5, Some(Lock::X)), Err(EditError::Index(5)));
+        let before = spec.elements().to_vec();
+        let p = spec.solve().segments()[2].p3;
+        assert_eq!(p.x, 0.0);
+        assert!((p.y + 100.).abs() < 0.1, "{:?}", p);
+        assert!(spec.solve_stats().unwrap().residual < 1e-3);
+        // Solving leaves the elements as they were.
+        assert_eq!(spec.elements(), &before[..]);
+        // Index 2 is now where 3 was.
+        spec.remove_element(1);
+        assert_eq!(spec.lock(2), Some(Lock::X));
+    }
+}
@@ -0,0 +1,123 @@
+//! Mapping rendered output back to the elements of a spec.
+//!
+//! Exporters can use this to annotate each output curve with the element
+//! it came from, so autohinters and reviewers can trace output points back
+//! to the structure of the spline.
+
+use std::ops::Range;
+
+use kurbo::{CubicBez, QuadBez};
+
+use crate::spline::SplineSpec;
+
+/// Where in a spec a piece of rendered output came from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PieceSource {
+    /// The index of the element whose segment the piece renders.
+    pub element: usize,
+    /// The arclength parameters of the segment the piece covers, from 0
+    /// to 1.
+    ///
+    /// The piece starts at a point of the spec if the range starts at 0,
+    /// and ends at one if it ends at 1. Its other ends are added in
+    /// rendering.
+    pub range: Range<f64>,
+    /// Whether the element's first control point is automatic, placed by
+    /// the solver.
+    pub auto_p1: bool,
+    /// Whether the element's second control point is automatic.
+    pub auto_p2: bool,
+}
+
+impl SplineSpec {
+    /// Render the spec to cubic Béziers within `tolerance`, each with its
+    /// source.
+    ///
+    /// The cubics are those of [`Segment::render_mapped`](crate::Segment::render_mapped)
+    /// for each segment in turn.
+    pub fn render_mapped(&mut self, tolerance: f64) -> Vec<(CubicBez, PieceSource)> {
+        let spline = self.solve().into_owned();
+        let mut result = Vec::new();
+        for (i, seg) in spline.segments().iter().enumerate() {
+            for (cubic, range) in seg.render_mapped(tolerance) {
+                result.push((cubic, self.piece_source(i + 1, range)));
+            }
+        }
+        result
+    }
+
+    /// Render the spec to quadratic Béziers within `tolerance`, each with
+    /// its source.
+    ///
+    /// The quadratics are those of [`Segment::to_quads`](crate::Segment::to_quads)
+    /// for each segment in turn, which cover equal lengths of it.
+    pub fn render_quads_mapped(&mut self, tolerance: f64) -> Vec<(QuadBez, PieceSource)> {
+        let spline = self.solve().into_owned();
+        let mut result = Vec::new();
+        for (i, seg) in spline.segments().iter().enumerate() {
+            let quads = seg.to_quads(tolerance);
+            let n = quads.len() as f64;
+            for (j, quad) in quads.into_iter().enumerate() {
+                let range = j as f64 / n..(j + 1) as f64 / n;
+                result.push((quad, self.piece_source(i + 1, range)));
+            }
+        }
+        result
+    }
+
+    fn piece_source(&self, element: usize, range: Range<f64>) -> PieceSource {
+        let el = &self.elements()[element];
+        PieceSource {
+            element,
+            range,
+            auto_p1: el.is_auto_p1(),
+            auto_p2: el.is_auto_p2(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::SplineSpec;
+
+    #[test]
+    fn render_mapped() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 60.), true);
+        spec.spline_to(
+            Some(Point::new(150., 60.)),
+            None,
+            Point::new(200., 0.),
+            false,
+        );
+        spec.line_to(Point::new(300., 0.), false);
+        let cubics = spec.render_mapped(0.1);
+        let quads = spec.render_quads_mapped(0.1);
+        for sources in &[
+            cubics.iter().map(|(_, s)| s.clone()).collect::<Vec<_>>(),
+            quads.iter().map(|(_, s)| s.clone()).collect(),
+        ] {
+            // Each element is covered in order, from 0 to 1.
+            for w in sources.windows(2) {
+                if w[0].element == w[1].element {
+                    assert_eq!(w[0].range.end, w[1].range.start);
+                } else {
+                    assert_eq!((w[0].range.end, w[1].range.start), (1.0, 0.0));
+                    assert_eq!(w[1].element, w[0].element + 1);
+                }
+            }
+            assert_eq!(sources[0].element, 1);
+            assert!(sources[0].auto_p1 && sources[0].auto_p2);
+            let second = sources.iter().find(|s| s.element == 2).unwrap();
+            assert!(!second.auto_p1 && second.auto_p2);
+            let last = sources.last().unwrap();
+            assert_eq!((last.element, last.range.clone()), (3, 0.0..1.0));
+            assert!(!last.auto_p1 && !last.auto_p2);
+        }
+        assert_eq!(cubics[0].0.p0, Point::new(0., 0.));
+        assert_eq!(quads.last().unwrap().0.p2, Point::new(300., 0.));
+    }
+}
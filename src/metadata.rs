@@ -0,0 +1,121 @@
+//! User metadata attached to elements of a spec.
+//!
+//! Font tools can use this to store anchor names, hinting tags or component
+//! references alongside the geometry. Metadata is a set of string key-value
+//! pairs per element, and describes the on-curve point the element ends at.
+
+use std::collections::BTreeMap;
+
+use crate::spline::{Element, SplineSpec};
+
+/// Metadata for each element with any, by element index.
+pub(crate) type Metadata = BTreeMap<usize, BTreeMap<String, String>>;
+
+impl SplineSpec {
+    /// The metadata of an element, if it has any.
+    pub fn metadata(&self, ix: usize) -> Option<&BTreeMap<String, String>> {
+        self.metadata.get(&ix)
+    }
+
+    /// Set a metadata value on an element, returning the previous value.
+    pub fn set_metadata(
+        &mut self,
+        ix: usize,
+        key: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Option<String> {
+        debug_assert!(ix < self.elements().len());
+        self.metadata
+            .entry(ix)
+            .or_default()
+            .insert(key.into(), value.into())
+    }
+
+    /// Remove a metadata value from an element, returning it.
+    pub fn remove_metadata(&mut self, ix: usize, key: &str) -> Option<String> {
+        let map = self.metadata.get_mut(&ix)?;
+        let value = map.remove(key);
+        if map.is_empty() {
+            self.metadata.remove(&ix);
+        }
+        value
+    }
+
+    /// Insert an element, moving the metadata of later elements with them.
+    pub fn insert_element(&mut self, ix: usize, el: Element) {
+        self.elements_mut().insert(ix, el);
+        self.remap_metadata(|i| if i >= ix { i + 1 } else { i });
+    }
+
+    /// Remove an element and its metadata, moving the metadata of later
+    /// elements with them.
+    pub fn remove_element(&mut self, ix: usize) -> Element {
+        let el = self.elements_mut().remove(ix);
+        self.metadata.remove(&ix);
+        self.locks.remove(&ix);
+        self.windings.remove(&ix);
+        self.remap_metadata(|i| if i > ix { i - 1 } else { i });
+        el
+    }
+
+    /// Move metadata to new element indices.
+    ///
+    /// When several elements map to the same index, their metadata is
+    /// merged, with values from lower original indices taking precedence.
+    /// Locked coordinates and windings move in the same way.
+    pub(crate) fn remap_metadata(&mut self, f: impl Fn(usize) -> usize) {
+        let old = std::mem::take(&mut self.metadata);
+        for (i, map) in old {
+            let entry = self.metadata.entry(f(i)).or_default();
+            for (key, value) in map {
+                entry.entry(key).or_insert(value);
+            }
+        }
+        let old = std::mem::take(&mut self.locks);
+        for (i, lock) in old {
+            self.locks.entry(f(i)).or_insert(lock);
+        }
+        let old = std::mem::take(&mut self.windings);
+        for (i, turns) in old {
+            self.windings.entry(f(i)).or_insert(turns);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::SplineSpec;
+
+    #[test]
+    fn metadata_follows_edits() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), true);
+        spec.set_metadata(2, "anchor", "top");
+        spec.subdivide(0, 0.5);
+        assert_eq!(spec.metadata(3).unwrap()["anchor"], "top");
+        assert!(spec.metadata(1).is_none());
+        spec.remove_element(1);
+        assert_eq!(spec.metadata(2).unwrap()["anchor"], "top");
+        assert_eq!(spec.remove_metadata(2, "anchor").as_deref(), Some("top"));
+        assert!(spec.metadata(2).is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn metadata_round_trips() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), false);
+        spec.set_metadata(1, "hint", "stem");
+        let json = serde_json::to_string(&spec).unwrap();
+        let spec2: SplineSpec = serde_json::from_str(&json).unwrap();
+        assert_eq!(spec, spec2);
+        // Specs without metadata serialize as before.
+        spec.remove_metadata(1, "hint");
+        assert!(!serde_json::to_string(&spec).unwrap().contains("metadata"));
+    }
+}
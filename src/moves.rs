@@ -0,0 +1,89 @@
+//! Moving on-curve points.
+
+use kurbo::{Point, Vec2};
+
+use crate::spline::{Element, SplineSpec};
+
+impl SplineSpec {
+    /// Move many on-curve points at once, as for a drag of a selection.
+    ///
+    /// Each entry gives the index of the element whose end point moves,
+    /// and its new position. Explicit control points next to a moved point
+    /// are carried along with it. For a closed path, the start point and
+    /// the end of the last element move together. If a point appears more
+    /// than once, the last entry wins.
+    ///
+    /// The spec is only solved again once, on the next
+    /// [`solve`](SplineSpec::solve).
+    pub fn move_points(&mut self, moves: &[(usize, Point)]) {
+        for &(ix, p) in moves {
+            let delta = p - self.elements()[ix].endpoint();
+            self.move_point(ix, delta);
+        }
+    }
+
+    /// Move the on-curve point at the end of element `ix`, with any
+    /// explicit control points next to it.
+    ///
+    /// For a closed path, the start point and the end of the last element
+    /// move together.
+    pub(crate) fn move_point(&mut self, ix: usize, delta: Vec2) {
+        let is_closed = self.is_closed();
+        let elements = self.elements_mut();
+        let n = elements.len();
+        let mut ixs = vec![ix];
+        if is_closed && n > 1 && (ix == 0 || ix == n - 1) {
+            ixs = vec![0, n - 1];
+        }
+        for ix in ixs {
+            let shift = |p: &mut Point| *p += delta;
+            match &mut elements[ix] {
+                Element::MoveTo(p) | Element::LineTo(p, _) | Element::ArcTo(p, _, _) => shift(p),
+                Element::SplineTo(_, p2, p3, _) => {
+                    p2.iter_mut().for_each(shift);
+                    shift(p3);
+                }
+            }
+            if let Some(Element::SplineTo(p1, ..)) = elements.get_mut(ix + 1) {
+                p1.iter_mut().for_each(shift);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{Element, SplineSpec};
+
+    #[test]
+    fn move_points() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(
+            Some(Point::new(0., 50.)),
+            Some(Point::new(50., 80.)),
+            Point::new(100., 80.),
+            true,
+        );
+        spec.spline_to(None, None, Point::new(200., 60.), true);
+        spec.line_to(Point::new(0., 0.), false);
+        spec.close();
+        spec.solve();
+        spec.move_points(&[(3, Point::new(10., 10.)), (1, Point::new(100., 90.))]);
+        assert_eq!(spec.elements()[0], Element::MoveTo(Point::new(10., 10.)));
+        assert_eq!(
+            spec.elements()[1],
+            Element::SplineTo(
+                Some(Point::new(10., 60.)),
+                Some(Point::new(50., 90.)),
+                Point::new(100., 90.),
+                true
+            )
+        );
+        let spline = spec.solve();
+        assert_eq!(spline.segments()[0].p0, Point::new(10., 10.));
+        assert_eq!(spline.segments()[2].p3, Point::new(10., 10.));
+    }
+}
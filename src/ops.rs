@@ -0,0 +1,463 @@
+//! Boolean operations on closed splines.
+//!
+//! The operations work on rendered outlines: each spline is rendered and
+//! flattened to a polygon within the given tolerance, the polygons are
+//! combined, and the result is returned as a path of line segments. The
+//! fill rule is nonzero for both inputs and the output.
+//!
+//! The result can optionally be fit back into spline specifications with
+//! [`refit`].
+
+use std::collections::HashMap;
+
+use kurbo::simplify::{self, SimplifyOptions};
+use kurbo::{BezPath, PathEl, Point, QuadBez, Vec2};
+
+use crate::spline::{Spline, SplineSpec};
+
+/// The tolerance curves in the input of [`refit`] are flattened to,
+/// relative to the tolerance of the fit.
+const REFIT_FLATTEN_TOLERANCE: f64 = 0.1;
+
+/// The shortest line fit by [`refit`], relative to the tolerance. Shorter
+/// lines are merged into the next.
+const REFIT_MIN_LINE: f64 = 1e-3;
+
+/// A boolean operation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoolOp {
+    /// Points inside either shape.
+    Union,
+    /// Points inside both shapes.
+    Intersection,
+    /// Points inside the first shape but not the second.
+    Difference,
+    /// Points inside exactly one of the shapes.
+    Xor,
+}
+
+impl BoolOp {
+    fn apply(self, in_a: bool, in_b: bool) -> bool {
+        match self {
+            BoolOp::Union => in_a || in_b,
+            BoolOp::Intersection => in_a && in_b,
+            BoolOp::Difference => in_a && !in_b,
+            BoolOp::Xor => in_a != in_b,
+        }
+    }
+}
+
+/// The union of two shapes, each made of one or more closed splines.
+pub fn union(a: &[Spline], b: &[Spline], tolerance: f64) -> BezPath {
+    boolean(a, b, BoolOp::Union, tolerance)
+}
+
+/// The intersection of two shapes.
+pub fn intersection(a: &[Spline], b: &[Spline], tolerance: f64) -> BezPath {
+    boolean(a, b, BoolOp::Intersection, tolerance)
+}
+
+/// The first shape with the second removed.
+pub fn difference(a: &[Spline], b: &[Spline], tolerance: f64) -> BezPath {
+    boolean(a, b, BoolOp::Difference, tolerance)
+}
+
+/// Apply a boolean operation to two shapes.
+///
+/// Each shape is a set of closed splines, combined with the nonzero fill
+/// rule. Removing the overlaps of a single shape is a union with an empty
+/// second shape.
+///
+/// The output is a path made of line segments, with the inside of each
+/// subpath to its left (counterclockwise in a y-up coordinate system).
+pub fn boolean(a: &[Spline], b: &[Spline], op: BoolOp, tolerance: f64) -> BezPath {
+    let polys_a = flatten_all(a, tolerance);
+    let polys_b = flatten_all(b, tolerance);
+    let mut edges = Vec::new();
+    for poly in polys_a.iter().chain(&polys_b) {
+        for (i, &p0) in poly.iter().enumerate() {
+            let p1 = poly[(i + 1) % poly.len()];
+            if p0 != p1 {
+                edges.push(Edge { p0, p1 });
+            }
+        }
+    }
+    let splits = split_points(&edges);
+    // A small offset for probing either side of an edge.
+    let eps = 1e-4 * tolerance;
+    let inside = |p: Point| op.apply(winding(&polys_a, p) != 0, winding(&polys_b, p) != 0);
+    let mut kept: Vec<(Point, Point)> = Vec::new();
+    for (edge, mut ts) in edges.iter().zip(splits) {
+        ts.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+        let mut pts = vec![edge.p0];
+        pts.extend(ts.iter().map(|&(_, p)| p));
+        pts.push(edge.p1);
+        pts.dedup();
+        for w in pts.windows(2) {
+            let (p0, p1) = (w[0], w[1]);
+            let d = p1 - p0;
+            let mid = p0.midpoint(p1);
+            let normal = Vec2::new(-d.y, d.x) * (eps / d.hypot());
+            let left = inside(mid + normal);
+            let right = inside(mid - normal);
+            if left && !right {
+                kept.push((p0, p1));
+            } else if right && !left {
+                kept.push((p1, p0));
+            }
+        }
+    }
+    // Coincident edges from both shapes produce duplicates.
+    kept.sort_by(|x, y| key(x.0).cmp(&key(y.0)).then(key(x.1).cmp(&key(y.1))));
+    kept.dedup();
+    chain(&kept)
+}
+
+/// Fit the output of a boolean operation back into spline specifications.
+///
+/// Corners are detected where the direction changes by more than
+/// `corner_angle` (in radians) between adjacent line segments; smooth runs
+/// are fit with explicit control points to within `tolerance`. Any curves
+/// in the path are flattened first.
+pub fn refit(path: &BezPath, tolerance: f64, corner_angle: f64) -> Vec<SplineSpec> {
+    let options = SimplifyOptions::default().angle_thresh(corner_angle.tan());
+    // The simplifier only fits curves, so present the lines as cubics.
+    let mut cubics = BezPath::new();
+    let mut start = Point::ZERO;
+    let mut last = Point::ZERO;
+    path.flatten(REFIT_FLATTEN_TOLERANCE * tolerance, |el| match el {
+        PathEl::MoveTo(p) => {
+            cubics.move_to(p);
+            start = p;
+            last = p;
+        }
+        // The simplifier recurses without end on curves of almost no
+        // length, which flattening can leave where rendered cubics meet.
+        PathEl::LineTo(p) if p.distance(last) > REFIT_MIN_LINE * tolerance => {
+            line_as_cubic(&mut cubics, last, p);
+            last = p;
+        }
+        PathEl::ClosePath => {
+            if last.distance(start) > REFIT_MIN_LINE * tolerance {
+                line_as_cubic(&mut cubics, last, start);
+            }
+            cubics.close_path();
+            last = start;
+        }
+        // Flattening leaves only lines.
+        _ => (),
+    });
+    let simplified = simplify::simplify_bezpath(cubics.iter(), tolerance, &options);
+    let mut specs = Vec::new();
+    let mut spec = SplineSpec::new();
+    let mut start_tan = None;
+    let mut last_tan: Option<Vec2> = None;
+    for el in simplified.iter() {
+        let (start_v, end_v) = match el {
+            PathEl::MoveTo(p) => {
+                if spec.elements().len() > 1 {
+                    specs.push(std::mem::take(&mut spec));
+                } else {
+                    spec = SplineSpec::new();
+                }
+                spec.move_to(p);
+                start_tan = None;
+                last_tan = None;
+                continue;
+            }
+            PathEl::ClosePath if spec.elements().len() < 2 => continue,
+            PathEl::ClosePath => {
+                let start = spec.elements()[0].endpoint();
+                if let Some(el) = spec.elements_mut().last_mut() {
+                    // The fit may not land exactly on the start point.
+                    el.set_endpoint(start);
+                }
+                if let (Some(v0), Some(v1)) = (last_tan, start_tan) {
+                    set_last_smooth(&mut spec, is_smooth(v0, v1, corner_angle));
+                }
+                spec.close();
+                specs.push(std::mem::take(&mut spec));
+                continue;
+            }
+            PathEl::LineTo(p) => {
+                let v = p - last_end(&spec);
+                spec.line_to(p, false);
+                (v, v)
+            }
+            PathEl::QuadTo(p1, p2) => {
+                let p0 = last_end(&spec);
+                let c = QuadBez::new(p0, p1, p2).raise();
+                spec.spline_to(Some(c.p1), Some(c.p2), c.p3, false);
+                (p1 - p0, p2 - p1)
+            }
+            PathEl::CurveTo(p1, p2, p3) => {
+                let p0 = last_end(&spec);
+                spec.spline_to(Some(p1), Some(p2), p3, false);
+                (p1 - p0, p3 - p2)
+            }
+        };
+        match last_tan {
+            Some(tan) => {
+                let n = spec.elements().len();
+                let smooth = is_smooth(tan, start_v, corner_angle);
+                spec.elements_mut()[n - 2].set_smooth(smooth);
+            }
+            None => start_tan = Some(start_v),
+        }
+        last_tan = Some(end_v);
+    }
+    if spec.elements().len() > 1 {
+        specs.push(spec);
+    }
+    specs
+}
+
+fn line_as_cubic(path: &mut BezPath, p0: Point, p1: Point) {
+    path.curve_to(p0.lerp(p1, 1.0 / 3.0), p0.lerp(p1, 2.0 / 3.0), p1);
+}
+
+struct Edge {
+    p0: Point,
+    p1: Point,
+}
+
+fn flatten_all(splines: &[Spline], tolerance: f64) -> Vec<Vec<Point>> {
+    let mut polys = Vec::new();
+    for spline in splines {
+        let mut poly: Vec<Point> = Vec::new();
+        // Snap to a fine grid, so points that should coincide do, both
+        // where rendered segments meet and where the two shapes have
+        // vertices at the same place.
+        let grid = 1e-6 * tolerance;
+        let snap = |p: Point| Point::new((p.x / grid).round() * grid, (p.y / grid).round() * grid);
+        spline.render().flatten(tolerance, |el| match el {
+            PathEl::MoveTo(p) | PathEl::LineTo(p) => {
+                let p = snap(p);
+                if poly.last() != Some(&p) {
+                    poly.push(p);
+                }
+            }
+            _ => (),
+        });
+        if poly.len() > 1 && poly[0] == poly[poly.len() - 1] {
+            poly.pop();
+        }
+        if poly.len() > 2 {
+            polys.push(poly);
+        }
+    }
+    polys
+}
+
+/// Find the points at which each edge needs to be split.
+///
+/// The result is, for each edge, a list of (parameter, point) pairs. Where
+/// two edges meet, the same point value is recorded for both, so the split
+/// edges can be chained by exact comparison.
+fn split_points(edges: &[Edge]) -> Vec<Vec<(f64, Point)>> {
+    const EPS: f64 = 1e-9;
+    let mut splits = vec![Vec::new(); edges.len()];
+    for i in 0..edges.len() {
+        for j in i + 1..edges.len() {
+            let (a, b) = (&edges[i], &edges[j]);
+            let da = a.p1 - a.p0;
+            let db = b.p1 - b.p0;
+            let denom = da.cross(db);
+            let scale = da.hypot() * db.hypot();
+            if denom.abs() <= EPS * scale {
+                // Parallel; split at the endpoints of collinear overlaps.
+                if (b.p0 - a.p0).cross(da).abs() <= EPS * da.hypot2() {
+                    add_interior(&mut splits[i], a, b.p0);
+                    add_interior(&mut splits[i], a, b.p1);
+                    add_interior(&mut splits[j], b, a.p0);
+                    add_interior(&mut splits[j], b, a.p1);
+                }
+                continue;
+            }
+            let v = b.p0 - a.p0;
+            let ta = v.cross(db) / denom;
+            let tb = v.cross(da) / denom;
+            if !(-EPS..=1.0 + EPS).contains(&ta) || !(-EPS..=1.0 + EPS).contains(&tb) {
+                continue;
+            }
+            let p = if ta <= EPS {
+                a.p0
+            } else if ta >= 1.0 - EPS {
+                a.p1
+            } else if tb <= EPS {
+                b.p0
+            } else if tb >= 1.0 - EPS {
+                b.p1
+            } else {
+                a.p0 + ta * da
+            };
+            add_interior(&mut splits[i], a, p);
+            add_interior(&mut splits[j], b, p);
+        }
+    }
+    splits
+}
+
+/// Record a split point if it lies strictly inside the edge.
+fn add_interior(splits: &mut Vec<(f64, Point)>, edge: &Edge, p: Point) {
+    if p == edge.p0 || p == edge.p1 {
+        return;
+    }
+    let d = edge.p1 - edge.p0;
+    let t = (p - edge.p0).dot(d) / d.hypot2();
+    if t > 0.0 && t < 1.0 {
+        splits.push((t, p));
+    }
+}
+
+/// The nonzero winding number of a set of polygons around a point.
+fn winding(polys: &[Vec<Point>], p: Point) -> i32 {
+    let mut w = 0;
+    for poly in polys {
+        for (i, &p0) in poly.iter().enumerate() {
+            let p1 = poly[(i + 1) % poly.len()];
+            if p0.y <= p.y {
+                if p1.y > p.y && (p1 - p0).cross(p - p0) > 0.0 {
+                    w += 1;
+                }
+            } else if p1.y <= p.y && (p1 - p0).cross(p - p0) < 0.0 {
+                w -= 1;
+            }
+        }
+    }
+    w
+}
+
+fn key(p: Point) -> (u64, u64) {
+    (p.x.to_bits(), p.y.to_bits())
+}
+
+/// Chain directed edges into closed subpaths.
+///
+/// A chain that can't be closed, which only numerical trouble can cause,
+/// is dropped rather than closed across the gap.
+fn chain(edges: &[(Point, Point)]) -> BezPath {
+    let mut outgoing: HashMap<(u64, u64), Vec<usize>> = HashMap::new();
+    for (i, e) in edges.iter().enumerate() {
+        outgoing.entry(key(e.0)).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut path = BezPath::new();
+    for start in 0..edges.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let origin = edges[start].0;
+        let mut pts = vec![origin];
+        let mut p = edges[start].1;
+        let mut closed = true;
+        while p != origin {
+            let next = outgoing
+                .get(&key(p))
+                .and_then(|ixs| ixs.iter().copied().find(|&ix| !used[ix]));
+            match next {
+                Some(ix) => {
+                    used[ix] = true;
+                    pts.push(p);
+                    p = edges[ix].1;
+                }
+                None => {
+                    closed = false;
+                    break;
+                }
+            }
+        }
+        if closed {
+            path.move_to(origin);
+            for &p in &pts[1..] {
+                path.line_to(p);
+            }
+            path.close_path();
+        }
+    }
+    path
+}
+
+fn is_smooth(v0: Vec2, v1: Vec2, corner_angle: f64) -> bool {
+    v0.cross(v1).atan2(v0.dot(v1)).abs() < corner_angle
+}
+
+fn last_end(spec: &SplineSpec) -> Point {
+    spec.elements().last().unwrap().endpoint()
+}
+
+fn set_last_smooth(spec: &mut SplineSpec, smooth: bool) {
+    if let Some(el) = spec.elements_mut().last_mut() {
+        el.set_smooth(smooth);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use kurbo::{Rect, Shape};
+
+    use crate::spline::Element;
+
+    fn solved(spec: SplineSpec) -> Spline<'static> {
+        let mut spec = spec;
+        spec.solve().into_owned()
+    }
+
+    #[test]
+    fn overlapping_squares() {
+        let a = [solved(SplineSpec::rect(Rect::new(0., 0., 100., 100.)))];
+        let b = [solved(SplineSpec::rect(Rect::new(50., 50., 150., 150.)))];
+        assert!((union(&a, &b, 0.1).area() - 17500.0).abs() < 1e-6);
+        assert!((intersection(&a, &b, 0.1).area() - 2500.0).abs() < 1e-6);
+        assert!((difference(&a, &b, 0.1).area() - 7500.0).abs() < 1e-6);
+        assert!((boolean(&a, &b, BoolOp::Xor, 0.1).area() - 15000.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn shared_edge_is_removed() {
+        let a = [solved(SplineSpec::rect(Rect::new(0., 0., 100., 100.)))];
+        let b = [solved(SplineSpec::rect(Rect::new(100., 0., 200., 100.)))];
+        let path = union(&a, &b, 0.1);
+        assert!((path.area() - 20000.0).abs() < 1e-6);
+        // The long sides are each made of two edges.
+        assert_eq!(path.segments().count(), 6);
+    }
+
+    #[test]
+    fn circles_refit() {
+        let a = [solved(SplineSpec::circle(Point::new(0., 0.), 100.))];
+        let b = [solved(SplineSpec::circle(Point::new(100., 0.), 100.))];
+        let path = union(&a, &b, 0.01);
+        // Area of two unit circles overlapping by a lens, scaled.
+        let lens = 2.0 * std::f64::consts::PI / 3.0 - 0.75f64.sqrt();
+        let expected = (2.0 * std::f64::consts::PI - lens) * 1e4;
+        assert!((path.area() - expected).abs() < 1e-3 * expected);
+        let mut specs = refit(&path, 0.1, 0.2);
+        assert_eq!(specs.len(), 1);
+        let refit_area = specs[0].solve().render().area();
+        assert!((refit_area - expected).abs() < 1e-2 * expected);
+        let corners = specs[0]
+            .elements()
+            .iter()
+            .filter(|el| matches!(el, Element::SplineTo(_, _, _, false)))
+            .count();
+        assert_eq!(corners, 2);
+        // Curves are flattened rather than rejected.
+        let circle = a[0].render();
+        let specs = refit(&circle, 0.1, 0.2);
+        assert_eq!(specs.len(), 1);
+        let area = specs[0].clone().solve().render().area();
+        assert!((area - circle.area()).abs() < 1e-2 * circle.area());
+    }
+
+    #[test]
+    fn open_chain_is_dropped() {
+        let (a, b, c) = (Point::new(0., 0.), Point::new(1., 0.), Point::new(0., 1.));
+        let closed = chain(&[(a, b), (b, c), (c, a)]);
+        assert_eq!(closed.elements().len(), 4);
+        let open = chain(&[(a, b), (b, c)]);
+        assert!(open.elements().is_empty());
+    }
+}
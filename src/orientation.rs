@@ -0,0 +1,77 @@
+//! The direction of the y axis.
+//!
+//! The math in this crate doesn't depend on the direction of the y axis,
+//! but signs do: positive curvature, arc sweep and turning angle are
+//! counterclockwise as seen in a y-up coordinate system, and so clockwise
+//! as seen in a y-down one. Font coordinates are usually y-up, while screen
+//! and SVG coordinates are y-down.
+
+use kurbo::Point;
+
+use crate::spline::{Element, SplineSpec};
+
+/// The direction of the y axis of a coordinate system.
+///
+/// This tells [`debug_svg`](crate::debug_svg) which way up to draw specs.
+/// Elsewhere, specs can be converted with [`SplineSpec::flip_y`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    /// Y increases upwards, as in font coordinates.
+    YUp,
+    /// Y increases downwards, as in screen and SVG coordinates.
+    YDown,
+}
+
+impl SplineSpec {
+    /// Mirror the spec in the x axis, negating y coordinates.
+    ///
+    /// This converts between y-up and y-down coordinates, keeping the
+    /// shape as seen. Arc sweeps are negated so arcs bend the same way.
+    pub fn flip_y(&mut self) {
+        let flip = |p: &mut Point| p.y = -p.y;
+        for el in self.elements_mut() {
+            match el {
+                Element::MoveTo(p) | Element::LineTo(p, _) => flip(p),
+                Element::SplineTo(p1, p2, p3, _) => {
+                    for p in p1.iter_mut().chain(p2.iter_mut()) {
+                        flip(p);
+                    }
+                    flip(p3);
+                }
+                Element::ArcTo(p, sweep, _) => {
+                    flip(p);
+                    *sweep = -*sweep;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::SplineSpec;
+
+    #[test]
+    fn flip_negates_curvature() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.arc_to(Point::new(100., 0.), 1.0, false);
+        spec.spline_to(
+            Some(Point::new(150., 50.)),
+            None,
+            Point::new(200., 0.),
+            false,
+        );
+        let orig = spec.solve().into_owned();
+        let mut flipped = spec.clone();
+        flipped.flip_y();
+        for (a, b) in orig.segments().iter().zip(flipped.solve().segments()) {
+            assert!((a.k0 + b.k0).abs() < 1e-9 && (a.k1 + b.k1).abs() < 1e-9);
+            assert!((a.p1.y + b.p1.y).abs() < 1e-9);
+        }
+        flipped.flip_y();
+        assert_eq!(flipped, spec);
+    }
+}
@@ -0,0 +1,119 @@
+//! Hit testing of solved splines.
+
+use kurbo::Point;
+
+use crate::spline::Spline;
+use crate::util;
+
+/// The part of a spline hit by [`Spline::pick`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PickResult {
+    /// An on-curve point.
+    ///
+    /// Point 0 is the start of the spline, and point `i` is the end of
+    /// segment `i - 1`. For a closed spline, the end of the last segment is
+    /// point 0.
+    OnCurve(usize),
+    /// The first control point (`p1`) of the given segment.
+    ControlP1(usize),
+    /// The second control point (`p2`) of the given segment.
+    ControlP2(usize),
+    /// The interior of the given segment, at an arclength parameter from 0
+    /// to 1.
+    Segment(usize, f64),
+}
+
+impl<'a> Spline<'a> {
+    /// Find the part of the spline within `radius` of `point`.
+    ///
+    /// On-curve points take priority over control points, which take
+    /// priority over the curve itself; within each kind, the nearest wins.
+    /// Control points of line segments are not reported, as they coincide
+    /// with the end points. A spline with no segments has nothing to pick.
+    pub fn pick(&self, point: Point, radius: f64) -> Option<PickResult> {
+        let segs = self.segments();
+        if segs.is_empty() {
+            return None;
+        }
+        let nearest = |candidates: &mut dyn Iterator<Item = (PickResult, Point)>| {
+            candidates
+                .map(|(result, p)| (result, (p - point).hypot()))
+                .filter(|&(_, dist)| dist <= radius)
+                .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+                .map(|(result, _)| result)
+        };
+        let n_points = if self.is_closed() {
+            segs.len()
+        } else {
+            segs.len() + 1
+        };
+        let mut on_curve = (0..n_points).map(|i| match segs.get(i) {
+            Some(seg) => (PickResult::OnCurve(i), seg.p0),
+            None => (PickResult::OnCurve(i), segs[i - 1].p3),
+        });
+        if let Some(result) = nearest(&mut on_curve) {
+            return Some(result);
+        }
+        let mut controls = segs
+            .iter()
+            .enumerate()
+            .filter(|(_, seg)| !seg.is_line())
+            .flat_map(|(i, seg)| {
+                let p1 = (PickResult::ControlP1(i), seg.p1);
+                let p2 = (PickResult::ControlP2(i), seg.p2);
+                std::iter::once(p1).chain(std::iter::once(p2))
+            });
+        if let Some(result) = nearest(&mut controls) {
+            return Some(result);
+        }
+        // Flatten finely enough that the error is small relative to the radius.
+        let mut curve = segs.iter().enumerate().flat_map(|(i, seg)| {
+            let pts = seg.flatten_params(0.1 * radius);
+            let hits: Vec<_> = pts
+                .windows(2)
+                .map(|w| {
+                    let (t0, p0) = w[0];
+                    let (t1, p1) = w[1];
+                    let u = util::nearest_on_line(point, p0, p1);
+                    (PickResult::Segment(i, t0 + u * (t1 - t0)), p0.lerp(p1, u))
+                })
+                .collect();
+            hits
+        });
+        nearest(&mut curve)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use super::PickResult;
+    use crate::SplineSpec;
+
+    #[test]
+    fn pick_priority() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(Some(Point::new(0., 50.)), None, Point::new(100., 50.), true);
+        spec.line_to(Point::new(100., 0.), false);
+        let spline = spec.solve();
+        assert_eq!(
+            spline.pick(Point::new(2., 1.), 5.),
+            Some(PickResult::OnCurve(0))
+        );
+        assert_eq!(
+            spline.pick(Point::new(100., -2.), 5.),
+            Some(PickResult::OnCurve(2))
+        );
+        assert_eq!(
+            spline.pick(Point::new(1., 48.), 5.),
+            Some(PickResult::ControlP1(0))
+        );
+        match spline.pick(Point::new(102., 25.), 5.) {
+            Some(PickResult::Segment(1, t)) => assert!((t - 0.5).abs() < 1e-9),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(spline.pick(Point::new(50., -20.), 5.), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Approximation of splines by quadratic Béziers.
+//!
+//! TrueType outlines are made of quadratic Béziers, stored as a contour of
+//! on-curve and off-curve points. Between two off-curve points, an on-curve
+//! point at their midpoint is implied rather than stored. Segments are
+//! approximated by runs of quadratics joined at implied points, so the only
+//! on-curve points stored are the points of the spline.
+
+use kurbo::{BezPath, ParamCurve, ParamCurveNearest, Point, QuadBez, Vec2};
+
+use crate::spline::{Segment, Spline};
+use crate::util;
+
+/// The most quadratics for one segment.
+const MAX_QUADS: usize = 64;
+
+/// The tolerance of the polyline checked against, relative to the
+/// tolerance of the approximation.
+const FLATTEN_TOLERANCE: f64 = 0.1;
+
+/// The accuracy of nearest point queries when checking the approximation,
+/// relative to the tolerance.
+const NEAREST_ACCURACY: f64 = 1e-3;
+
+impl Segment {
+    /// Approximate the segment by quadratic Béziers, to within `tolerance`.
+    ///
+    /// The quadratics run from the start of the segment to its end, each
+    /// starting where the previous one ends with the same tangent. Where
+    /// two meet is the midpoint of their control points, so it need not be
+    /// stored in a TrueType contour. The quadratics are over equal lengths
+    /// of the segment, and as few are used as meet the tolerance, up to 64.
+    /// A straight segment gives a single quadratic with its control point
+    /// at the middle.
+    pub fn to_quads(&self, tolerance: f64) -> Vec<QuadBez> {
+        if self.is_line() {
+            return vec![QuadBez::new(self.p0, self.p0.midpoint(self.p3), self.p3)];
+        }
+        let flat: Vec<Point> = self
+            .flatten_params(FLATTEN_TOLERANCE * tolerance)
+            .into_iter()
+            .map(|(_, p)| p)
+            .collect();
+        let mut quads = Vec::new();
+        for n in 1..=MAX_QUADS {
+            let ts: Vec<f64> = (0..=n).map(|i| i as f64 / n as f64).collect();
+            quads = self.quads_at(&ts);
+            let within = (0..n).all(|i| {
+                // The implied points move a little along the tangent from
+                // the curve, so part of a piece of the curve may be covered
+                // by the next quadratic instead.
+                let near = &quads[i.saturating_sub(1)..(i + 2).min(n)];
+                self.quads_error(near, ts[i], ts[i + 1], tolerance) <= tolerance
+                    && quad_error(&quads[i], &flat) <= tolerance
+            });
+            if within {
+                break;
+            }
+        }
+        quads
+    }
+
+    /// The approximation by quadratics between the arclength parameters
+    /// `ts`, from 0 to 1.
+    fn quads_at(&self, ts: &[f64]) -> Vec<QuadBez> {
+        let n = ts.len() - 1;
+        let ends: Vec<(Point, Vec2)> = ts
+            .iter()
+            .enumerate()
+            .map(|(i, &t)| {
+                let p = match i {
+                    0 => self.p0,
+                    _ if i == n => self.p3,
+                    _ => self.eval(t),
+                };
+                (p, self.tangent(t))
+            })
+            .collect();
+        let controls: Vec<Point> = ends
+            .windows(2)
+            .map(|w| tangent_intersection(w[0], w[1]))
+            .collect();
+        (0..n)
+            .map(|i| {
+                let p0 = match i {
+                    0 => self.p0,
+                    _ => controls[i - 1].midpoint(controls[i]),
+                };
+                let p2 = match i + 1 {
+                    j if j == n => self.p3,
+                    j => controls[i].midpoint(controls[j]),
+                };
+                QuadBez::new(p0, controls[i], p2)
+            })
+            .collect()
+    }
+
+    /// The largest distance from points of the segment between `t0` and
+    /// `t1` to the nearest of `quads`.
+    fn quads_error(&self, quads: &[QuadBez], t0: f64, t1: f64, tolerance: f64) -> f64 {
+        let accuracy = NEAREST_ACCURACY * tolerance;
+        (0..=4)
+            .map(|j| {
+                let p = self.eval(t0 + 0.25 * j as f64 * (t1 - t0));
+                quads
+                    .iter()
+                    .map(|quad| quad.nearest(p, accuracy).distance_sq)
+                    .fold(f64::INFINITY, f64::min)
+                    .sqrt()
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+impl<'a> Spline<'a> {
+    /// Render the spline as quadratic Béziers, to within `tolerance`.
+    ///
+    /// The path has only `QuadTo` and `LineTo` elements, with the same
+    /// points as the contour from
+    /// [`truetype_points`](Spline::truetype_points).
+    pub fn render_quads(&self, tolerance: f64) -> BezPath {
+        let mut path = BezPath::new();
+        if let Some(seg) = self.segments().first() {
+            path.move_to(seg.p0);
+        }
+        for seg in self.segments() {
+            if seg.is_line() {
+                path.line_to(seg.p3);
+            } else {
+                for quad in seg.to_quads(tolerance) {
+                    path.quad_to(quad.p1, quad.p2);
+                }
+            }
+        }
+        if self.is_closed() {
+            path.close_path();
+        }
+        path
+    }
+
+    /// The points of a TrueType contour approximating the spline to within
+    /// `tolerance`, each with whether it is on the curve.
+    ///
+    /// The points of the spline are on-curve points, and between them are
+    /// the control points of the quadratics from
+    /// [`Segment::to_quads`], with the on-curve points where those meet
+    /// left implied. Lines have no off-curve points. The contour starts at
+    /// the start of the spline, and for a closed spline doesn't repeat it
+    /// at the end.
+    pub fn truetype_points(&self, tolerance: f64) -> Vec<(Point, bool)> {
+        let mut points = Vec::new();
+        if let Some(seg) = self.segments().first() {
+            points.push((seg.p0, true));
+        }
+        for seg in self.segments() {
+            if !seg.is_line() {
+                let quads = seg.to_quads(tolerance);
+                points.extend(quads.iter().map(|quad| (quad.p1, false)));
+            }
+            points.push((seg.p3, true));
+        }
+        if self.is_closed() && points.len() > 1 {
+            points.pop();
+        }
+        points
+    }
+}
+
+/// The largest distance from points of `quad` to the polyline `flat`.
+fn quad_error(quad: &QuadBez, flat: &[Point]) -> f64 {
+    [0.25, 0.5, 0.75]
+        .iter()
+        .map(|&u| {
+            let p = quad.eval(u);
+            flat.windows(2)
+                .map(|w| p.distance(w[0].lerp(w[1], util::nearest_on_line(p, w[0], w[1]))))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+/// The point where the tangent lines from two ends meet, or the midpoint
+/// of the ends if the tangents don't meet ahead of the first and behind
+/// the second.
+fn tangent_intersection(start: (Point, Vec2), end: (Point, Vec2)) -> Point {
+    let ((p0, d0), (p1, d1)) = (start, end);
+    let v = p1 - p0;
+    let cross = d0.cross(d1);
+    if cross != 0.0 {
+        let a = v.cross(d1) / cross;
+        let b = d0.cross(v) / cross;
+        if a > 0.0 && b > 0.0 {
+            return p0 + a * d0;
+        }
+    }
+    p0.midpoint(p1)
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::SplineSpec;
+
+    #[test]
+    fn quads_within_tolerance() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 60.), true);
+        spec.spline_to(None, None, Point::new(200., -40.), true);
+        spec.line_to(Point::new(0., 0.), false);
+        spec.close();
+        let spline = spec.solve();
+        for seg in &spline.segments()[..2] {
+            let quads = seg.to_quads(0.1);
+            assert!(quads.len() < 16, "{}", quads.len());
+            assert_eq!(quads[0].p0, seg.p0);
+            assert_eq!(quads.last().unwrap().p2, seg.p3);
+            // Quadratics meet at the midpoints of their control points.
+            for w in quads.windows(2) {
+                assert_eq!(w[0].p2, w[1].p0);
+                assert_eq!(w[0].p2, w[0].p1.midpoint(w[1].p1));
+            }
+            // The quadratics stay near the curve.
+            let flat: Vec<Point> = seg
+                .flatten_params(1e-4)
+                .into_iter()
+                .map(|(_, p)| p)
+                .collect();
+            for quad in &quads {
+                let d = super::quad_error(quad, &flat);
+                assert!(d < 0.11, "{}", d);
+            }
+        }
+        // The gentle first segment needs a single quadratic, and the loop
+        // into the line several.
+        assert_eq!(spline.segments()[0].to_quads(0.1).len(), 1);
+        assert!(spline.segments()[1].to_quads(0.1).len() > 4);
+        let points = spline.truetype_points(0.1);
+        assert_eq!(points[0], (Point::new(0., 0.), true));
+        assert_eq!(points.iter().filter(|(_, on)| *on).count(), 3);
+        // The closing line is implied by the closed contour.
+        assert_eq!(points.last(), Some(&(Point::new(200., -40.), true)));
+    }
+}
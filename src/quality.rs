@@ -0,0 +1,204 @@
+//! Measures of curve quality.
+//!
+//! These quantify defects such as a dip in curvature partway along a
+//! segment, so that tests of the curve family and the solver can check for
+//! them rather than relying on plots. Curvature is sampled at equal steps
+//! of arclength.
+
+use crate::spline::{Segment, Spline};
+
+/// The number of steps each segment is sampled at.
+const N_SAMPLES: usize = 64;
+
+/// Changes in curvature smaller than this, relative to the length of the
+/// curve, are treated as noise.
+const CURVATURE_EPSILON: f64 = 1e-6;
+
+/// Measures of the quality of a curve.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Quality {
+    /// The integral of the squared derivative of curvature with respect to
+    /// arclength, times the cube of the arclength.
+    ///
+    /// This is zero for lines and circular arcs, and doesn't change when
+    /// the curve is scaled.
+    pub curvature_variation: f64,
+    /// The number of times the curvature changes sign.
+    ///
+    /// For a spline, this includes sign changes at the joints.
+    pub inflections: usize,
+    /// The number of local minima and maxima of curvature inside segments.
+    ///
+    /// Curvature is ideally monotonic along a segment, so each of these is
+    /// a violation, such as a curvature dip.
+    pub curvature_extrema: usize,
+}
+
+/// Measure the quality of a segment.
+pub fn segment(seg: &Segment) -> Quality {
+    let stats = SegmentStats::new(seg);
+    Quality {
+        curvature_variation: stats.variation * stats.arclen.powi(3),
+        inflections: stats.inflections,
+        curvature_extrema: stats.extrema,
+    }
+}
+
+/// Measure the quality of a spline, as a whole.
+pub fn spline(spline: &Spline) -> Quality {
+    let mut variation = 0.0;
+    let mut arclen = 0.0;
+    let mut inflections = 0;
+    let mut extrema = 0;
+    let mut first_sign = 0.0;
+    let mut last_sign = 0.0;
+    for seg in spline.segments() {
+        let stats = SegmentStats::new(seg);
+        if first_sign == 0.0 {
+            first_sign = stats.first_sign;
+        }
+        variation += stats.variation;
+        arclen += stats.arclen;
+        extrema += stats.extrema;
+        inflections += stats.inflections;
+        if stats.first_sign * last_sign < 0.0 {
+            inflections += 1;
+        }
+        if stats.last_sign != 0.0 {
+            last_sign = stats.last_sign;
+        }
+    }
+    if spline.is_closed() && first_sign * last_sign < 0.0 {
+        inflections += 1;
+    }
+    Quality {
+        curvature_variation: variation * arclen.powi(3),
+        inflections,
+        curvature_extrema: extrema,
+    }
+}
+
+/// The integral of the squared derivative of curvature with respect to
+/// arclength over all the segments, without scaling.
+pub(crate) fn energy(segs: &[Segment]) -> f64 {
+    segs.iter()
+        .map(|seg| SegmentStats::new(seg).variation)
+        .sum()
+}
+
+/// Curvature statistics of a segment.
+struct SegmentStats {
+    arclen: f64,
+    /// The integral of the squared derivative of curvature.
+    variation: f64,
+    inflections: usize,
+    extrema: usize,
+    /// The signs of the first and last nonzero curvature, or 0 if there is
+    /// none.
+    first_sign: f64,
+    last_sign: f64,
+}
+
+impl SegmentStats {
+    fn new(seg: &Segment) -> SegmentStats {
+        let arclen = seg.arclen();
+        let mut stats = SegmentStats {
+            arclen,
+            variation: 0.0,
+            inflections: 0,
+            extrema: 0,
+            first_sign: 0.0,
+            last_sign: 0.0,
+        };
+        if seg.is_line() || arclen == 0.0 {
+            return stats;
+        }
+        let ds = arclen / N_SAMPLES as f64;
+        let eps = CURVATURE_EPSILON / arclen;
+        let ks: Vec<f64> = (0..=N_SAMPLES)
+            .map(|i| seg.curvature(i as f64 / N_SAMPLES as f64))
+            .collect();
+        let mut last_dk_sign = 0.0;
+        for (i, w) in ks.windows(2).enumerate() {
+            let dk = w[1] - w[0];
+            stats.variation += dk * dk / ds;
+            if dk.abs() > eps {
+                let sign = dk.signum();
+                if sign * last_dk_sign < 0.0 && i > 0 {
+                    stats.extrema += 1;
+                }
+                last_dk_sign = sign;
+            }
+        }
+        for &k in &ks {
+            if k.abs() > eps {
+                let sign = k.signum();
+                if stats.first_sign == 0.0 {
+                    stats.first_sign = sign;
+                } else if sign != stats.last_sign {
+                    stats.inflections += 1;
+                }
+                stats.last_sign = sign;
+            }
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use super::{segment, spline};
+    use crate::SplineSpec;
+
+    #[test]
+    fn arc_is_perfect() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.arc_to(Point::new(100., 0.), 1.0, false);
+        let q = segment(&spec.solve().segments()[0]);
+        assert!(q.curvature_variation < 1e-6, "{:?}", q);
+        assert_eq!(q.inflections, 0);
+        assert_eq!(q.curvature_extrema, 0);
+    }
+
+    #[test]
+    fn curvature_dip() {
+        // Short handles concentrate curvature at the ends, with a dip in
+        // the middle.
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        let (p1, p2) = (Point::new(0., 10.), Point::new(100., 10.));
+        spec.spline_to(Some(p1), Some(p2), Point::new(100., 0.), false);
+        let q = segment(&spec.solve().segments()[0]);
+        assert_eq!(q.inflections, 0);
+        assert_eq!(q.curvature_extrema, 1);
+        // An s-bend has an inflection, and curvature falls steadily.
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        let (p1, p2) = (Point::new(0., 30.), Point::new(100., -30.));
+        spec.spline_to(Some(p1), Some(p2), Point::new(100., 0.), false);
+        let q = segment(&spec.solve().segments()[0]);
+        assert_eq!(q.inflections, 1);
+        assert_eq!(q.curvature_extrema, 0);
+    }
+
+    #[test]
+    fn spline_scale_invariant() {
+        let make = |scale: f64| {
+            let mut spec = SplineSpec::new();
+            spec.move_to(Point::new(0., 0.));
+            spec.spline_to(None, None, Point::new(100. * scale, 50. * scale), true);
+            spec.spline_to(None, None, Point::new(200. * scale, -50. * scale), true);
+            spec.spline_to(None, None, Point::new(300. * scale, 0.), false);
+            spline(&spec.solve())
+        };
+        let (a, b) = (make(1.0), make(3.0));
+        assert_eq!(a.inflections, 1);
+        assert_eq!(a.inflections, b.inflections);
+        assert_eq!(a.curvature_extrema, b.curvature_extrema);
+        let ratio = a.curvature_variation / b.curvature_variation;
+        assert!((ratio - 1.0).abs() < 1e-6, "{}", ratio);
+    }
+}
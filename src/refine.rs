@@ -0,0 +1,190 @@
+//! Adaptive approximation of segments by cubic Béziers.
+
+use std::ops::Range;
+
+use kurbo::{CubicBez, ParamCurve, Vec2};
+
+use crate::spline::Segment;
+
+/// The most times a piece is halved.
+const MAX_DEPTH: usize = 16;
+
+impl Segment {
+    /// Approximate part of the segment by cubic Béziers, to within
+    /// `tolerance`.
+    ///
+    /// The `range` is in arclength parameters, from 0 to 1. Pieces are
+    /// halved only where they are not yet within tolerance, so the cubics
+    /// are dense where the curvature is high or changes quickly, such as
+    /// near a cusp, and sparse where the curve is flat.
+    ///
+    /// Each cubic matches the curve's end points and tangents, with handle
+    /// lengths that would be exact for a circular arc of the same turn.
+    pub fn refine(&self, range: Range<f64>, tolerance: f64) -> Vec<CubicBez> {
+        self.refine_iter(range, tolerance)
+            .map(|(cubic, _)| cubic)
+            .collect()
+    }
+
+    /// Iterate over cubic Béziers rendering the segment to within
+    /// `tolerance`, each with the range of arclength parameters it covers.
+    ///
+    /// This is for mapping a location on a rendered cubic back to the
+    /// segment. The ends of each cubic are the points at the ends of its
+    /// range, and the ranges are contiguous, covering 0 to 1. In between,
+    /// the cubic's own parameter follows arclength closely but not exactly.
+    /// Straight segments yield a single cubic with its control points at
+    /// the thirds.
+    pub fn render_mapped(
+        &self,
+        tolerance: f64,
+    ) -> impl Iterator<Item = (CubicBez, Range<f64>)> + '_ {
+        let (line, curves) = if self.is_line() {
+            let v = self.p3 - self.p0;
+            let cubic = CubicBez::new(self.p0, self.p0 + v / 3.0, self.p3 - v / 3.0, self.p3);
+            (Some((cubic, 0.0..1.0)), None)
+        } else {
+            (None, Some(self.refine_iter(0.0..1.0, tolerance)))
+        };
+        line.into_iter().chain(curves.into_iter().flatten())
+    }
+
+    /// Lazily yield the cubics of [`Segment::refine`], with their ranges,
+    /// without allocating.
+    pub(crate) fn refine_iter(
+        &self,
+        range: Range<f64>,
+        tolerance: f64,
+    ) -> impl Iterator<Item = (CubicBez, Range<f64>)> + '_ {
+        // The ends of pending pieces, with their depths. Each piece starts
+        // where the previous one ends, and the latest pushed comes first.
+        let mut stack = [(0.0, 0); MAX_DEPTH + 1];
+        stack[0] = (range.end, 0);
+        let mut len = 1;
+        let mut t0 = range.start;
+        std::iter::from_fn(move || {
+            while len > 0 {
+                let (t1, depth) = stack[len - 1];
+                let tm = 0.5 * (t0 + t1);
+                let cubic = self.piece(t0, t1);
+                let err = self.piece_error(&cubic, t0, t1);
+                if err <= tolerance || depth >= MAX_DEPTH {
+                    len -= 1;
+                    let range = t0..t1;
+                    t0 = t1;
+                    return Some((cubic, range));
+                }
+                // Replace the piece with its two halves.
+                stack[len - 1] = (t1, depth + 1);
+                stack[len] = (tm, depth + 1);
+                len += 1;
+            }
+            None
+        })
+    }
+
+    /// Estimate the distance of a cubic from the segment between `t0` and
+    /// `t1`.
+    ///
+    /// This is measured normal to the segment at the quarter points and the
+    /// middle. The middle alone is not enough, as the cubics are exact
+    /// there for circular arcs.
+    fn piece_error(&self, cubic: &CubicBez, t0: f64, t1: f64) -> f64 {
+        [0.25, 0.5, 0.75]
+            .iter()
+            .map(|&u| {
+                let t = t0 + u * (t1 - t0);
+                let normal = Vec2::from_angle(self.tangent_angle(t) + 0.5 * std::f64::consts::PI);
+                (cubic.eval(u) - self.eval(t)).dot(normal).abs()
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// A cubic Bézier matching the segment's end points and tangents
+    /// between arclength parameters `t0` and `t1`.
+    ///
+    /// At the ends of the segment, the cubic ends exactly at its end points.
+    fn piece(&self, t0: f64, t1: f64) -> CubicBez {
+        let p0 = if t0 == 0.0 { self.p0 } else { self.eval(t0) };
+        let p3 = if t1 == 1.0 { self.p3 } else { self.eval(t1) };
+        let turn = self.hb.compute_theta(t1) - self.hb.compute_theta(t0);
+        let len = (t1 - t0) * self.arclen();
+        let handle = if turn.abs() < 1e-9 {
+            len / 3.0
+        } else {
+            len * (4.0 / 3.0) * (0.25 * turn).tan() / turn
+        };
+        let v0 = handle * Vec2::from_angle(self.tangent_angle(t0));
+        let v1 = handle * Vec2::from_angle(self.tangent_angle(t1));
+        CubicBez::new(p0, p0 + v0, p3 - v1, p3)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{ParamCurve, Point};
+
+    use crate::{util, SplineSpec};
+
+    #[test]
+    fn refine_near_cusp() {
+        // Handles close to the end points give high tension, with the
+        // curvature concentrated near the ends.
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        let (p1, p2) = (Point::new(10., 10.), Point::new(90., 10.));
+        spec.spline_to(Some(p1), Some(p2), Point::new(100., 0.), false);
+        let spline = spec.solve();
+        let seg = &spline.segments()[0];
+        let cubics = seg.refine(0.0..1.0, 1e-3);
+        assert!(cubics.len() > 2);
+        let first = cubics[0].p3 - cubics[0].p0;
+        let middle = &cubics[cubics.len() / 2];
+        assert!(first.hypot() < 0.5 * (middle.p3 - middle.p0).hypot());
+        assert_eq!(cubics[0].p0, seg.p0);
+        for w in cubics.windows(2) {
+            assert_eq!(w[0].p3, w[1].p0);
+        }
+        assert!((cubics.last().unwrap().p3 - seg.p3).hypot() < 1e-9);
+        // Check accuracy away from the middles, where it was estimated.
+        let pts: Vec<Point> = seg
+            .flatten_params(1e-6)
+            .into_iter()
+            .map(|(_, p)| p)
+            .collect();
+        for c in &cubics {
+            for &u in &[0.25, 0.75] {
+                let p = c.eval(u);
+                let d = pts
+                    .windows(2)
+                    .map(|w| (p - w[0].lerp(w[1], util::nearest_on_line(p, w[0], w[1]))).hypot())
+                    .fold(f64::INFINITY, f64::min);
+                assert!(d < 2e-3, "{}", d);
+            }
+        }
+    }
+
+    #[test]
+    fn render_mapped_ranges() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(50., 0.), true);
+        spec.spline_to(None, None, Point::new(100., 50.), false);
+        let spline = spec.solve();
+        for seg in spline.segments() {
+            let mapped: Vec<_> = seg.render_mapped(1e-3).collect();
+            assert_eq!(mapped[0].1.start, 0.0);
+            assert_eq!(mapped.last().unwrap().1.end, 1.0);
+            for w in mapped.windows(2) {
+                assert_eq!(w[0].1.end, w[1].1.start);
+            }
+            for (cubic, range) in &mapped {
+                assert!((cubic.p0 - seg.eval(range.start)).hypot() < 1e-9);
+                assert!((cubic.p3 - seg.eval(range.end)).hypot() < 1e-9);
+            }
+        }
+        let line = &spline.segments()[0];
+        let (cubic, _) = line.render_mapped(1e-3).next().unwrap();
+        assert!((cubic.eval(0.3) - line.eval(0.3)).hypot() < 1e-9);
+    }
+}
@@ -0,0 +1,51 @@
+//! Samples of solved splines, for comparison with other implementations.
+
+use kurbo::Point;
+#[cfg(feature = "serde")]
+use serde_::{Deserialize, Serialize};
+
+use crate::spline::Spline;
+
+/// A point on a solved spline, with its tangent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(crate = "serde_")
+)]
+pub struct Sample {
+    /// The index of the segment.
+    pub segment: usize,
+    /// The arclength parameter within the segment, from 0 to 1.
+    pub t: f64,
+    /// The point on the segment at `t`.
+    pub point: Point,
+    /// The tangent angle, in radians.
+    pub tangent: f64,
+}
+
+impl<'a> Spline<'a> {
+    /// Sample each segment at `per_segment` equal steps of arclength,
+    /// including both ends.
+    ///
+    /// This is the format of the fixtures comparing the solver with the
+    /// [research spline], so that they can be generated by either.
+    ///
+    /// [research spline]: https://github.com/raphlinus/spline-research
+    pub fn samples(&self, per_segment: usize) -> Vec<Sample> {
+        let per_segment = per_segment.max(1);
+        let mut samples = Vec::new();
+        for (segment, seg) in self.segments().iter().enumerate() {
+            for j in 0..=per_segment {
+                let t = j as f64 / per_segment as f64;
+                samples.push(Sample {
+                    segment,
+                    t,
+                    point: seg.eval(t),
+                    tangent: seg.tangent_angle(t),
+                });
+            }
+        }
+        samples
+    }
+}
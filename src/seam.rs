@@ -0,0 +1,62 @@
+//! Moving the start point of closed paths.
+
+use crate::spline::{Element, SplineSpec};
+
+impl SplineSpec {
+    /// Make the on-curve point at the end of element `ix` the start of a
+    /// closed path, without changing its shape.
+    ///
+    /// The start point, or seam, decides the order of points in exports
+    /// and which points correspond when interpolating. The elements are
+    /// rotated, carrying their metadata and locks with them. This does
+    /// nothing for an open path, or for the current start point.
+    pub fn set_start(&mut self, ix: usize) {
+        let n = self.elements().len();
+        if !self.is_closed() || ix == 0 || ix >= n - 1 {
+            return;
+        }
+        let start = self.elements()[ix].endpoint();
+        let elements = self.elements_mut();
+        elements[1..].rotate_left(ix);
+        elements[0] = Element::MoveTo(start);
+        // The old move-to and last element both describe the old start
+        // point, which now ends element n - 1 - ix.
+        self.remap_metadata(|i| match i {
+            0 => n - 1 - ix,
+            i if i > ix => i - ix,
+            i => i + n - 1 - ix,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{Element, SplineSpec};
+
+    #[test]
+    fn set_start() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), false);
+        spec.spline_to(None, None, Point::new(120., 60.), true);
+        spec.spline_to(None, None, Point::new(50., 100.), true);
+        spec.spline_to(None, None, Point::new(0., 0.), false);
+        spec.close();
+        spec.set_metadata(2, "name", "right");
+        spec.set_metadata(4, "name", "origin");
+        let before = spec.solve().into_owned();
+        spec.set_start(2);
+        assert_eq!(spec.elements()[0], Element::MoveTo(Point::new(120., 60.)));
+        assert_eq!(spec.elements()[1].endpoint(), Point::new(50., 100.));
+        assert_eq!(spec.elements()[4].endpoint(), Point::new(120., 60.));
+        assert_eq!(spec.metadata(4).unwrap()["name"], "right");
+        assert_eq!(spec.metadata(2).unwrap()["name"], "origin");
+        // The same segments, starting two later.
+        let after = spec.solve();
+        for (i, seg) in after.segments().iter().enumerate() {
+            assert!(seg.approx_eq(&before.segments()[(i + 2) % 4], 1e-6));
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Constructors for common closed shapes.
+
+use std::f64::consts::{FRAC_PI_2, PI};
+
+use kurbo::{Affine, ParamCurve, Point, Rect, Vec2};
+
+use crate::hyperbezier::HyperBezier;
+use crate::spline::SplineSpec;
+use crate::util;
+
+/// The most segments per quadrant we will use to meet a tolerance.
+const MAX_SUBDIVISIONS: usize = 64;
+
+impl SplineSpec {
+    /// A circle, made of four exact quarter arcs.
+    ///
+    /// The path starts at the rightmost point and runs counterclockwise
+    /// in a y-up coordinate system.
+    pub fn circle(center: Point, radius: f64) -> SplineSpec {
+        let mut spec = SplineSpec::new();
+        spec.move_to(center + Vec2::new(radius, 0.0));
+        for i in 1..=4 {
+            let p = center + radius * Vec2::from_angle(i as f64 * FRAC_PI_2);
+            spec.arc_to(p, FRAC_PI_2, true);
+        }
+        spec.close();
+        spec
+    }
+
+    /// A rectangle.
+    ///
+    /// The path starts at the corner with the smallest coordinates and runs
+    /// counterclockwise in a y-up coordinate system.
+    pub fn rect(rect: Rect) -> SplineSpec {
+        let rect = rect.abs();
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(rect.x0, rect.y0));
+        spec.line_to(Point::new(rect.x1, rect.y0), false);
+        spec.line_to(Point::new(rect.x1, rect.y1), false);
+        spec.line_to(Point::new(rect.x0, rect.y1), false);
+        spec.line_to(Point::new(rect.x0, rect.y0), false);
+        spec.close();
+        spec
+    }
+
+    /// A rectangle with circular corners.
+    ///
+    /// The radius is clamped to half the length of the shorter side. Each
+    /// corner is an exact quarter arc, tangent to the adjoining sides. The
+    /// path starts at the beginning of the bottom side (in a y-up coordinate
+    /// system) and runs counterclockwise.
+    pub fn rounded_rect(rect: Rect, radius: f64) -> SplineSpec {
+        let rect = rect.abs();
+        let r = radius.max(0.0).min(0.5 * rect.width().min(rect.height()));
+        if r == 0.0 {
+            return SplineSpec::rect(rect);
+        }
+        let (x0, y0, x1, y1) = (rect.x0, rect.y0, rect.x1, rect.y1);
+        // The start and end of each side, in order.
+        let sides = [
+            (Point::new(x0 + r, y0), Point::new(x1 - r, y0)),
+            (Point::new(x1, y0 + r), Point::new(x1, y1 - r)),
+            (Point::new(x1 - r, y1), Point::new(x0 + r, y1)),
+            (Point::new(x0, y1 - r), Point::new(x0, y0 + r)),
+        ];
+        let mut spec = SplineSpec::new();
+        spec.move_to(sides[0].0);
+        for (i, &(start, end)) in sides.iter().enumerate() {
+            if start != end {
+                spec.line_to(end, true);
+            }
+            spec.arc_to(sides[(i + 1) % 4].0, FRAC_PI_2, true);
+        }
+        spec.close();
+        spec
+    }
+
+    /// A regular polygon with `n_sides` sides, inscribed in a circle.
+    ///
+    /// The first vertex is the rightmost point of the circle, and the path
+    /// runs counterclockwise in a y-up coordinate system.
+    pub fn regular_polygon(center: Point, radius: f64, n_sides: usize) -> SplineSpec {
+        debug_assert!(n_sides >= 3);
+        let mut spec = SplineSpec::new();
+        spec.move_to(center + Vec2::new(radius, 0.0));
+        for i in 1..n_sides {
+            let th = i as f64 * (2.0 * PI / n_sides as f64);
+            spec.line_to(center + radius * Vec2::from_angle(th), false);
+        }
+        spec.line_to(center + Vec2::new(radius, 0.0), false);
+        spec.close();
+        spec
+    }
+
+    /// An axis-aligned ellipse.
+    ///
+    /// See [`SplineSpec::superellipse`] for the meaning of `tolerance`.
+    pub fn ellipse(center: Point, radii: Vec2, tolerance: f64) -> SplineSpec {
+        SplineSpec::superellipse(center, radii, 2.0, tolerance)
+    }
+
+    /// An axis-aligned superellipse, `|x/a|^n + |y/b|^n = 1`.
+    ///
+    /// An exponent of 2 is an ellipse; larger values are more square. The
+    /// exponent should be greater than 1, as smaller values have corners.
+    ///
+    /// The result uses explicit control points, with tangents taken from the
+    /// exact curve. Each quadrant is subdivided until the rendered spline is
+    /// within `tolerance` of the exact curve, up to a limit of 64 segments
+    /// per quadrant. The path starts at the rightmost point and runs
+    /// counterclockwise in a y-up coordinate system.
+    pub fn superellipse(center: Point, radii: Vec2, exponent: f64, tolerance: f64) -> SplineSpec {
+        debug_assert!(exponent > 1.0);
+        let curve = Superellipse {
+            center,
+            radii,
+            exponent,
+        };
+        let mut n = 1;
+        loop {
+            let mut spec = curve.to_spec(n);
+            if n >= MAX_SUBDIVISIONS || curve.max_deviation(&mut spec) <= tolerance {
+                return spec;
+            }
+            n *= 2;
+        }
+    }
+}
+
+struct Superellipse {
+    center: Point,
+    radii: Vec2,
+    exponent: f64,
+}
+
+impl Superellipse {
+    /// The point at the given angle parameter.
+    fn eval(&self, t: f64) -> Point {
+        let e = 2.0 / self.exponent;
+        let (s, c) = t.sin_cos();
+        let x = self.radii.x * c.signum() * c.abs().powf(e);
+        let y = self.radii.y * s.signum() * s.abs().powf(e);
+        self.center + Vec2::new(x, y)
+    }
+
+    /// The tangent angle at a point, from the gradient of the implicit form.
+    fn tangent_angle(&self, p: Point) -> f64 {
+        let n = self.exponent;
+        let v = p - self.center;
+        let gx = (v.x / self.radii.x).abs().powf(n - 1.0) * v.x.signum() / self.radii.x;
+        let gy = (v.y / self.radii.y).abs().powf(n - 1.0) * v.y.signum() / self.radii.y;
+        Vec2::new(-gy, gx).atan2()
+    }
+
+    /// The implicit function, which is 1 on the curve.
+    fn implicit(&self, p: Point) -> f64 {
+        let v = p - self.center;
+        (v.x / self.radii.x).abs().powf(self.exponent)
+            + (v.y / self.radii.y).abs().powf(self.exponent)
+    }
+
+    /// Build a spec with `n` segments per quadrant.
+    fn to_spec(&self, n: usize) -> SplineSpec {
+        let mut spec = SplineSpec::new();
+        let mut p0 = self.eval(0.0);
+        let mut th0 = self.tangent_angle(p0);
+        spec.move_to(p0);
+        let n_total = 4 * n;
+        for i in 1..=n_total {
+            // Land exactly on the start point when closing.
+            let p3 = if i == n_total {
+                self.eval(0.0)
+            } else {
+                self.eval(i as f64 * (2.0 * PI / n_total as f64))
+            };
+            let th3 = self.tangent_angle(p3);
+            let (p1, p2) = euler_handles(p0, th0, p3, th3);
+            spec.spline_to(Some(p1), Some(p2), p3, true);
+            p0 = p3;
+            th0 = th3;
+        }
+        spec.close();
+        spec
+    }
+
+    /// The maximum radial distance from the solved spec to the exact curve.
+    fn max_deviation(&self, spec: &mut SplineSpec) -> f64 {
+        let path = spec.solve().render();
+        let mut max_dev: f64 = 0.0;
+        for seg in path.segments() {
+            for i in 1..8 {
+                let p = seg.eval(i as f64 * 0.125);
+                let r = (p - self.center).hypot();
+                let scale = self.implicit(p).powf(-1.0 / self.exponent);
+                max_dev = max_dev.max(r * (1.0 - scale).abs());
+            }
+        }
+        max_dev
+    }
+}
+
+/// Control points for a segment with the given world-space end tangents.
+///
+/// The handle lengths are those of an Euler spiral (bias of 1), which is
+/// exactly a circular arc when the tangents are symmetric.
+fn euler_handles(p0: Point, th0: f64, p3: Point, th3: f64) -> (Point, Point) {
+    let v = p3 - p0;
+    let chord_th = v.atan2();
+    let a = Affine::new([v.x, v.y, -v.y, v.x, p0.x, p0.y]);
+    let th0 = util::mod_tau(th0 - chord_th);
+    let th1 = util::mod_tau(chord_th - th3);
+    let p1 = a * HyperBezier::v_for_params(th0, 1.0).to_point();
+    let p2 = a * (Point::new(1.0, 0.0) - HyperBezier::v_for_params(-th1, 1.0));
+    (p1, p2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ellipse_within_tolerance() {
+        let curve = Superellipse {
+            center: Point::new(300.0, 200.0),
+            radii: Vec2::new(200.0, 100.0),
+            exponent: 2.0,
+        };
+        let mut spec = SplineSpec::ellipse(curve.center, curve.radii, 0.1);
+        assert!(curve.max_deviation(&mut spec) <= 0.1);
+        assert!(spec.elements().len() < 4 * MAX_SUBDIVISIONS);
+    }
+
+    #[test]
+    fn superellipse_within_tolerance() {
+        for &exponent in &[1.5, 3.0, 5.0] {
+            let curve = Superellipse {
+                center: Point::ZERO,
+                radii: Vec2::new(100.0, 100.0),
+                exponent,
+            };
+            let mut spec = SplineSpec::superellipse(curve.center, curve.radii, exponent, 0.25);
+            assert!(
+                curve.max_deviation(&mut spec) <= 0.25,
+                "exponent {}",
+                exponent
+            );
+        }
+    }
+
+    #[test]
+    fn rect_corners() {
+        // A rect given from its opposite corner is normalized.
+        let mut spec = SplineSpec::rect(Rect::new(110.0, 70.0, 10.0, 20.0));
+        assert!(spec.is_closed());
+        let corners: Vec<Point> = spec.elements().iter().map(|el| el.endpoint()).collect();
+        assert_eq!(
+            corners,
+            [
+                Point::new(10.0, 20.0),
+                Point::new(110.0, 20.0),
+                Point::new(110.0, 70.0),
+                Point::new(10.0, 70.0),
+                Point::new(10.0, 20.0),
+            ]
+        );
+        let spline = spec.solve();
+        assert_eq!(spline.segments().len(), 4);
+        assert!(spline.segments().iter().all(|seg| seg.is_line()));
+    }
+
+    #[test]
+    fn regular_polygon_vertices() {
+        let center = Point::new(50.0, -20.0);
+        for n in 3..8 {
+            let mut spec = SplineSpec::regular_polygon(center, 30.0, n);
+            assert!(spec.is_closed());
+            let els = spec.elements();
+            assert_eq!(els.len(), n + 1);
+            assert_eq!(els[0].endpoint(), center + Vec2::new(30.0, 0.0));
+            assert_eq!(els[n].endpoint(), els[0].endpoint());
+            for (i, el) in els.iter().enumerate().take(n) {
+                let th = i as f64 * (2.0 * PI / n as f64);
+                let expected = center + 30.0 * Vec2::from_angle(th);
+                assert!(el.endpoint().distance(expected) < 1e-9, "{} {}", n, i);
+            }
+            let spline = spec.solve();
+            assert_eq!(spline.segments().len(), n);
+            let side = 60.0 * (PI / n as f64).sin();
+            for seg in spline.segments() {
+                assert!((seg.p0.distance(seg.p3) - side).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn rounded_rect_fills_rect() {
+        use kurbo::Shape;
+        let rect = Rect::new(10.0, 20.0, 110.0, 70.0);
+        for &radius in &[0.0, 10.0, 25.0, 100.0] {
+            let mut spec = SplineSpec::rounded_rect(rect, radius);
+            let bbox = spec.solve().render().bounding_box();
+            assert!((bbox.x0 - rect.x0).abs() < 1e-6);
+            assert!((bbox.y0 - rect.y0).abs() < 1e-6);
+            assert!((bbox.x1 - rect.x1).abs() < 1e-6);
+            assert!((bbox.y1 - rect.y1).abs() < 1e-6);
+        }
+        // With the maximum radius, the sides of length 50 disappear.
+        let spec = SplineSpec::rounded_rect(rect, 25.0);
+        assert_eq!(spec.elements().len(), 7);
+    }
+
+    #[test]
+    fn circular_ellipse_is_one_segment_per_quadrant() {
+        let spec = SplineSpec::ellipse(Point::ZERO, Vec2::new(50.0, 50.0), 0.05);
+        assert_eq!(spec.elements().len(), 5);
+    }
+}
@@ -1,15 +1,65 @@
 //! A simple interpolating spline.
 
 use std::f64::consts::PI;
+use std::fmt::Debug;
+use std::sync::Arc;
 
 use kurbo::{Affine, BezPath, Point, Vec2};
 
 use crate::hyperbezier::{HyperBezier, HyperBezierResult, ThetaParams};
 use crate::util;
 
+/// The largest limit used by [`EulerLimit`], leaving room for the bias to
+/// rise before a right angle.
+const MAX_EULER_LIMIT: f64 = 0.45 * PI;
+
 pub struct SimpleSpline {
     pts: Vec<Point>,
     ths: Vec<f64>,
+    bias_law: Arc<dyn BiasLaw>,
+}
+
+/// The bias given to an auto control point, from the tangent angle at its
+/// end of the segment.
+///
+/// Lower bias flattens the curvature near the end. The law shapes how
+/// curves behave as they approach a half circle, and can be swapped out to
+/// evaluate alternatives.
+pub trait BiasLaw: Debug + Send + Sync {
+    /// The bias for an end with tangent angle `th`, relative to the chord.
+    fn bias_for_theta(&self, th: f64) -> f64;
+}
+
+/// The default bias law.
+///
+/// Segments are Euler spirals, with a bias of 1, for tangent angles up to
+/// the limit. Beyond it, the bias rises quadratically to 2 at a right
+/// angle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EulerLimit {
+    /// The largest tangent angle, in radians, for an Euler spiral.
+    ///
+    /// This is clamped to the range 0 to 0.45π.
+    pub limit: f64,
+}
+
+impl Default for EulerLimit {
+    fn default() -> EulerLimit {
+        EulerLimit { limit: 0.3 * PI }
+    }
+}
+
+impl BiasLaw for EulerLimit {
+    fn bias_for_theta(&self, th: f64) -> f64 {
+        let th = th.abs();
+        let limit = self.limit.clamp(0.0, MAX_EULER_LIMIT);
+        if th < limit {
+            1.0
+        } else {
+            let len = 1.0 - (th - limit) / (0.5 * PI - limit);
+            2.0 - len.powi(2)
+        }
+    }
 }
 
 struct Seg {
@@ -21,7 +71,16 @@ struct Seg {
 impl SimpleSpline {
     pub fn new(pts: Vec<Point>) -> SimpleSpline {
         let ths = Self::initial_ths(&pts);
-        SimpleSpline { pts, ths }
+        SimpleSpline {
+            pts,
+            ths,
+            bias_law: Arc::new(EulerLimit::default()),
+        }
+    }
+
+    /// Set the law giving the bias at each end of a segment.
+    pub fn set_bias_law(&mut self, bias_law: Arc<dyn BiasLaw>) {
+        self.bias_law = bias_law;
     }
 
     fn initial_ths(pts: &[Point]) -> Vec<f64> {
@@ -81,19 +140,19 @@ impl SimpleSpline {
         // TODO: probably invert these signs in solve_for_theta
         let params = ThetaParams {
             th0: -seg.th0,
-            bias0: bias_for_theta(seg.th0),
+            bias0: self.bias_law.bias_for_theta(seg.th0),
             th1: -seg.th1,
-            bias1: bias_for_theta(seg.th1),
+            bias1: self.bias_law.bias_for_theta(seg.th1),
         };
         (HyperBezier::solve_for_theta(&params), seg)
     }
 
-    fn compute_curvature(th0: f64, th1: f64) -> HyperBezierResult {
+    fn compute_curvature(&self, th0: f64, th1: f64) -> HyperBezierResult {
         let params = ThetaParams {
             th0: -th0,
-            bias0: bias_for_theta(th0),
+            bias0: self.bias_law.bias_for_theta(th0),
             th1: -th1,
-            bias1: bias_for_theta(th1),
+            bias1: self.bias_law.bias_for_theta(th1),
         };
         let hb = HyperBezier::solve_for_theta(&params);
         hb.compute()
@@ -121,7 +180,7 @@ impl SimpleSpline {
         let (hb, mut seg0) = self.get_hyperbezier(0);
         let mut r0 = hb.compute();
         let mut ch0 = seg0.d.hypot();
-        for i in 0..n - 2 {
+        for (i, x_i) in x.iter_mut().enumerate() {
             let (hb, seg1) = self.get_hyperbezier(i + 1);
             let r1 = hb.compute();
             let ch1 = seg1.d.hypot();
@@ -129,37 +188,25 @@ impl SimpleSpline {
             abs_err += err.abs();
 
             const EPSILON: f64 = 1e-3;
-            let ak0p = Self::compute_curvature(seg0.th0, seg0.th1 + EPSILON);
-            let ak1p = Self::compute_curvature(seg1.th0 - EPSILON, seg1.th1);
+            let ak0p = self.compute_curvature(seg0.th0, seg0.th1 + EPSILON);
+            let ak1p = self.compute_curvature(seg1.th0 - EPSILON, seg1.th1);
             let errp = compute_err(ch0, ak0p, ch1, ak1p);
             let derr = (errp - err) * (1.0 / EPSILON);
 
-            x[i] = err / derr;
+            *x_i = err / derr;
 
             r0 = r1;
             ch0 = ch1;
             seg0 = seg1;
         }
         let scale = (0.25 * (iter_ix as f64 + 1.0)).tanh();
-        for i in 0..n - 2 {
-            self.ths[i + 1] += scale * x[i];
+        for (th, x_i) in self.ths[1..n - 1].iter_mut().zip(&x) {
+            *th += scale * x_i;
         }
         abs_err
     }
 }
 
-pub(crate) fn bias_for_theta(th: f64) -> f64 {
-    // Tangent angles up to this limit will be Euler spirals.
-    const EULER_LIMIT: f64 = 0.3 * PI;
-    let th = th.abs();
-    if th < EULER_LIMIT {
-        1.0
-    } else {
-        let len = 1.0 - (th - EULER_LIMIT) / (0.5 * PI - EULER_LIMIT);
-        2.0 - len.powi(2)
-    }
-}
-
 /// The tangent of an endpoint given the other tangent.
 pub(crate) fn endpoint_tangent(th: f64) -> f64 {
     0.5 * (2.0 * th).sin()
@@ -175,3 +222,52 @@ fn compute_err(ch0: f64, ak0: HyperBezierResult, ch1: f64, ak1: HyperBezierResul
     let a1 = (ak1k0.sin() * ch0).atan2(ak1k0.cos() * ch1);
     a0 - a1
 }
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+    use std::sync::Arc;
+
+    use kurbo::Point;
+
+    use super::{BiasLaw, EulerLimit};
+    use crate::{SolveOptions, SplineSpec};
+
+    #[test]
+    fn euler_limit() {
+        let law = EulerLimit::default();
+        assert_eq!(law.bias_for_theta(0.2 * PI), 1.0);
+        assert_eq!(law.bias_for_theta(-0.3 * PI), 1.0);
+        assert!((law.bias_for_theta(0.5 * PI) - 2.0).abs() < 1e-12);
+        // Limits at or past a right angle are clamped.
+        for &limit in &[0.5 * PI, PI] {
+            let law = EulerLimit { limit };
+            assert_eq!(law.bias_for_theta(0.4 * PI), 1.0);
+            assert!((law.bias_for_theta(0.5 * PI) - 2.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn custom_bias_law() {
+        #[derive(Debug)]
+        struct Flat;
+        impl BiasLaw for Flat {
+            fn bias_for_theta(&self, _th: f64) -> f64 {
+                0.5
+            }
+        }
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), false);
+        let euler = spec.solve().into_owned();
+        assert_eq!(euler.segments()[0].hb.bias0, 1.0);
+        spec.set_solve_options(SolveOptions {
+            bias_law: Arc::new(Flat),
+            ..SolveOptions::default()
+        });
+        let flat = spec.solve();
+        assert_eq!(flat.segments()[0].hb.bias0, 0.5);
+        assert!(!euler.approx_eq(&flat, 1e-3));
+    }
+}
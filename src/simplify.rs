@@ -0,0 +1,163 @@
+//! Removing redundant on-curve points.
+
+use std::f64::consts::PI;
+
+use kurbo::Point;
+
+use crate::distance;
+use crate::spline::{self, Element, Spline, SplineSpec};
+
+impl SplineSpec {
+    /// Remove on-curve points whose removal changes the solved shape by no
+    /// more than `tolerance`, and return the number removed.
+    ///
+    /// This is useful after importing traced data or resampling densely.
+    /// Points are removed greedily, cheapest first. The cost of a removal
+    /// is estimated by solving the merged segment between its neighbors
+    /// alone, and measuring it against the part of the original shape it
+    /// replaces, so that errors don't accumulate and each removal takes
+    /// one full solve. That solve can still move the auto segments further
+    /// away a little.
+    ///
+    /// A point is only a candidate when the elements either side of it are
+    /// of the same kind: two lines, two splines or two arcs. The elements
+    /// are merged into one, keeping the outer control points of splines.
+    /// The ends of the path, and points with metadata or a lock, are kept.
+    pub fn simplify(&mut self, tolerance: f64) -> usize {
+        let flatten_tolerance = 0.1 * tolerance;
+        let orig = distance::flatten(&self.solve(), flatten_tolerance);
+        // The original element each element ends at.
+        let mut orig_ix: Vec<usize> = (0..self.elements().len()).collect();
+        let mut count = 0;
+        loop {
+            let spline = self.solve().into_owned();
+            let n = self.elements().len();
+            // The last element ends at the end of an open path, or at the
+            // start of a closed one.
+            let best = (1..n.saturating_sub(1))
+                .filter(|&ix| self.metadata(ix).is_none() && self.lock(ix).is_none())
+                .filter_map(|ix| {
+                    let merged = self.merged(&spline, ix)?;
+                    let pts = self.local_solve(&spline, ix, merged, flatten_tolerance);
+                    let replaced = &orig[orig_ix[ix - 1]..orig_ix[ix + 1]];
+                    Some((ix, merged, distance::polylines_distance(&[pts], replaced)))
+                })
+                .filter(|&(_, _, d)| d <= tolerance)
+                .min_by(|a, b| a.2.total_cmp(&b.2));
+            match best {
+                Some((ix, merged, _)) => {
+                    self.remove_element(ix);
+                    self.elements_mut()[ix] = merged;
+                    orig_ix.remove(ix);
+                    count += 1;
+                }
+                None => return count,
+            }
+        }
+    }
+
+    /// The element replacing elements `ix` and `ix + 1` of the solved
+    /// `spline`, if they are of the same kind.
+    fn merged(&self, spline: &Spline, ix: usize) -> Option<Element> {
+        let merged = match (self.elements()[ix], self.elements()[ix + 1]) {
+            (Element::LineTo(..), el @ Element::LineTo(..)) => el,
+            (Element::SplineTo(p1, ..), Element::SplineTo(_, p2, p, is_smooth)) => {
+                // Explicit control points are refit to the tangents and
+                // curvatures at the ends of the merged segment.
+                let (prev, next) = (&spline.segments()[ix - 1], &spline.segments()[ix]);
+                let (q1, q2) = spline::fit_handles(
+                    (prev.p0, prev.tangent_angle(0.0), prev.curvature(0.0)),
+                    (p, next.tangent_angle(1.0), next.curvature(1.0)),
+                );
+                Element::SplineTo(p1.and(Some(q1)), p2.and(Some(q2)), p, is_smooth)
+            }
+            (Element::ArcTo(_, sweep0, _), Element::ArcTo(p, sweep1, is_smooth))
+                if (sweep0 + sweep1).abs() < PI =>
+            {
+                Element::ArcTo(p, sweep0 + sweep1, is_smooth)
+            }
+            _ => return None,
+        };
+        Some(merged)
+    }
+
+    /// Solve `merged`, in place of elements `ix` and `ix + 1`, between the
+    /// segments either side of them, held as they are in the solved
+    /// `spline`, and flatten it.
+    fn local_solve(
+        &self,
+        spline: &Spline,
+        ix: usize,
+        merged: Element,
+        tolerance: f64,
+    ) -> Vec<Point> {
+        let segs = spline.segments();
+        let closed = self.is_closed() && segs.len() > 3;
+        let prev = match ix {
+            1 if closed => Some(segs.len() - 1),
+            1 => None,
+            _ => Some(ix - 2),
+        };
+        let next = match ix + 1 {
+            i if i < segs.len() => Some(i),
+            _ if closed => Some(0),
+            _ => None,
+        };
+        // A neighbor is held by making the control points of a spline
+        // explicit.
+        let held = |i: usize| match self.elements()[i + 1] {
+            Element::SplineTo(_, _, p, is_smooth) => {
+                Element::SplineTo(Some(segs[i].p1), Some(segs[i].p2), p, is_smooth)
+            }
+            el => el,
+        };
+        let mut local = SplineSpec::new();
+        local.move_to(segs[prev.unwrap_or(ix - 1)].p0);
+        local.elements_mut().extend(prev.map(held));
+        local.elements_mut().push(merged);
+        local.elements_mut().extend(next.map(held));
+        let local = local.solve();
+        local.segments()[prev.map_or(0, |_| 1)]
+            .flatten_params(tolerance)
+            .into_iter()
+            .map(|(_, p)| p)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{distance, SplineSpec};
+
+    #[test]
+    fn simplify() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 100.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), true);
+        spec.line_to(Point::new(200., -100.), false);
+        let coarse = spec.solve().into_owned();
+        // Split each curve into four.
+        for seg_ix in [1, 0] {
+            for &t in &[0.75, 2.0 / 3.0, 0.5] {
+                spec.subdivide(seg_ix, t);
+            }
+        }
+        let n = spec.elements().len();
+        assert_eq!(n, 10);
+        let dense = spec.solve().into_owned();
+        let removed = spec.simplify(0.5);
+        assert!(removed >= 6, "{}", removed);
+        assert_eq!(spec.elements().len(), n - removed);
+        // The corner between the curve and the line stays.
+        assert_eq!(
+            spec.elements().last().unwrap().endpoint(),
+            Point::new(200., -100.)
+        );
+        let spline = spec.solve();
+        assert!(distance::spline_distance(&dense, &spline, 0.01) <= 0.5);
+        assert!(distance::spline_distance(&coarse, &spline, 0.01) < 1.0);
+    }
+}
@@ -0,0 +1,160 @@
+//! Streaming the rendered spline to a consumer.
+//!
+//! Font compilers, tessellators and FFI layers often have their own path
+//! representation, and can take the rendered geometry through a
+//! [`PathSink`] rather than from a [`BezPath`] built only to be read back.
+
+use kurbo::{BezPath, PathEl, Point, QuadBez};
+
+use crate::hyperbezier::RenderOptions;
+use crate::spline::Spline;
+
+/// A consumer of rendered path geometry.
+///
+/// Each subpath starts with `move_to`, and closed subpaths end with
+/// `close`.
+pub trait PathSink {
+    /// Start a new subpath at `p`.
+    fn move_to(&mut self, p: Point);
+    /// A straight line to `p`.
+    fn line_to(&mut self, p: Point);
+    /// A cubic Bézier to `p3`, with control points `p1` and `p2`.
+    fn curve_to(&mut self, p1: Point, p2: Point, p3: Point);
+    /// Close the current subpath.
+    fn close(&mut self);
+}
+
+impl PathSink for BezPath {
+    fn move_to(&mut self, p: Point) {
+        BezPath::move_to(self, p);
+    }
+
+    fn line_to(&mut self, p: Point) {
+        BezPath::line_to(self, p);
+    }
+
+    fn curve_to(&mut self, p1: Point, p2: Point, p3: Point) {
+        BezPath::curve_to(self, p1, p2, p3);
+    }
+
+    fn close(&mut self) {
+        self.close_path();
+    }
+}
+
+/// A sink passing each path element to a callback.
+///
+/// The callback is given only `MoveTo`, `LineTo`, `CurveTo` and
+/// `ClosePath` elements.
+#[derive(Clone, Copy, Debug)]
+pub struct CallbackSink<F>(pub F);
+
+impl<F: FnMut(PathEl)> PathSink for CallbackSink<F> {
+    fn move_to(&mut self, p: Point) {
+        (self.0)(PathEl::MoveTo(p));
+    }
+
+    fn line_to(&mut self, p: Point) {
+        (self.0)(PathEl::LineTo(p));
+    }
+
+    fn curve_to(&mut self, p1: Point, p2: Point, p3: Point) {
+        (self.0)(PathEl::CurveTo(p1, p2, p3));
+    }
+
+    fn close(&mut self) {
+        (self.0)(PathEl::ClosePath);
+    }
+}
+
+/// A sink passing each element to two sinks.
+pub(crate) struct Tee<'a, A, B>(pub(crate) &'a mut A, pub(crate) &'a mut B);
+
+impl<'a, A: PathSink, B: PathSink> PathSink for Tee<'a, A, B> {
+    fn move_to(&mut self, p: Point) {
+        self.0.move_to(p);
+        self.1.move_to(p);
+    }
+
+    fn line_to(&mut self, p: Point) {
+        self.0.line_to(p);
+        self.1.line_to(p);
+    }
+
+    fn curve_to(&mut self, p1: Point, p2: Point, p3: Point) {
+        self.0.curve_to(p1, p2, p3);
+        self.1.curve_to(p1, p2, p3);
+    }
+
+    fn close(&mut self) {
+        self.0.close();
+        self.1.close();
+    }
+}
+
+impl<'a> Spline<'a> {
+    /// Render the spline to a sink.
+    ///
+    /// The sink receives the same geometry as [`render`](Spline::render)
+    /// produces. A spline with no segments sends nothing.
+    pub fn render_to(&self, sink: &mut impl PathSink) {
+        self.render_to_with(sink, &RenderOptions::default());
+    }
+
+    /// Render the spline to a sink, with the given options.
+    pub fn render_to_with(&self, sink: &mut impl PathSink, options: &RenderOptions) {
+        let start = match self.segments().first() {
+            Some(seg) => seg.p0,
+            None => return,
+        };
+        sink.move_to(start);
+        let mut last = start;
+        for segment in self.segments() {
+            for el in segment.render_elements_with(options) {
+                match el {
+                    PathEl::MoveTo(p) => sink.move_to(p),
+                    PathEl::LineTo(p) => sink.line_to(p),
+                    PathEl::QuadTo(p1, p2) => {
+                        let c = QuadBez::new(last, p1, p2).raise();
+                        sink.curve_to(c.p1, c.p2, c.p3);
+                    }
+                    PathEl::CurveTo(p1, p2, p3) => sink.curve_to(p1, p2, p3),
+                    PathEl::ClosePath => sink.close(),
+                }
+                last = match el {
+                    PathEl::MoveTo(p) | PathEl::LineTo(p) => p,
+                    PathEl::QuadTo(_, p) | PathEl::CurveTo(_, _, p) => p,
+                    PathEl::ClosePath => last,
+                };
+            }
+        }
+        if self.is_closed() {
+            sink.close();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{BezPath, PathEl, Point};
+
+    use super::CallbackSink;
+    use crate::SplineSpec;
+
+    #[test]
+    fn render_to_sinks() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 60.), true);
+        spec.line_to(Point::new(200., 0.), false);
+        spec.close();
+        let spline = spec.solve();
+        let mut path = BezPath::new();
+        spline.render_to(&mut path);
+        assert_eq!(path, spline.render());
+        let mut els = Vec::new();
+        spline.render_to(&mut CallbackSink(|el| els.push(el)));
+        assert_eq!(els, spline.render().elements());
+        assert_eq!(els.last(), Some(&PathEl::ClosePath));
+    }
+}
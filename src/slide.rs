@@ -0,0 +1,140 @@
+//! Sliding on-curve points along the curve.
+
+use kurbo::Point;
+
+use crate::spline::{self, Element, SplineSpec};
+
+/// The smallest fraction of a segment left when a point slides towards
+/// the other end of it.
+const MIN_FRACTION: f64 = 0.01;
+
+impl SplineSpec {
+    /// Move the on-curve point at the end of element `ix` along the solved
+    /// curve by `delta` in arclength, forwards if positive, keeping the
+    /// shape as far as possible.
+    ///
+    /// The point stays within the segments either side of it, stopping
+    /// just short of the next points. Explicit control points on either
+    /// side are refit to the tangents and curvatures of the curve at the
+    /// ends of the new segments, arcs take or give up the turning the point
+    /// passes over, and lines stay lines. Auto control points follow when
+    /// solved, so smooth curves through auto points change a little. This
+    /// does nothing for the ends of an open path.
+    ///
+    /// For a closed path, the start point is the end of the last element.
+    pub fn slide_point(&mut self, ix: usize, delta: f64) {
+        let (ix, next_ix) = match self.joint_ixs(ix) {
+            (ix, Some(next_ix)) => (ix, next_ix),
+            _ => return,
+        };
+        let spline = self.solve();
+        let prev = spline.segments()[ix - 1].clone();
+        let next = spline.segments()[next_ix - 1].clone();
+        let (prev_len, next_len) = (prev.arclen(), next.arclen());
+        let delta = delta.clamp(
+            -(1.0 - MIN_FRACTION) * prev_len,
+            (1.0 - MIN_FRACTION) * next_len,
+        );
+        if delta == 0.0 {
+            return;
+        }
+        // Where the point goes, and the turning it passes over, counted
+        // positive when sliding forwards.
+        let (p, th, k, turning) = if delta > 0.0 {
+            let t = delta / next_len;
+            let turning = next.hb.compute_theta(t) - next.hb.compute_theta(0.0);
+            (
+                next.eval(t),
+                next.tangent_angle(t),
+                next.curvature(t),
+                turning,
+            )
+        } else {
+            let t = 1.0 + delta / prev_len;
+            let turning = prev.hb.compute_theta(1.0) - prev.hb.compute_theta(t);
+            (
+                prev.eval(t),
+                prev.tangent_angle(t),
+                prev.curvature(t),
+                -turning,
+            )
+        };
+        let start = (prev.p0, prev.tangent_angle(0.0), prev.curvature(0.0));
+        let end = (next.p3, next.tangent_angle(1.0), next.curvature(1.0));
+        let new_prev = reshape(self.elements()[ix], start, (p, th, k), turning);
+        let new_next = reshape(self.elements()[next_ix], (p, th, k), end, -turning);
+        let elements = self.elements_mut();
+        elements[ix] = new_prev;
+        elements[next_ix] = new_next;
+        if ix == elements.len() - 1 && next_ix == 1 {
+            elements[0] = Element::MoveTo(p);
+        }
+    }
+}
+
+/// The element `el` reshaped to run between new ends, one of which has
+/// moved along the curve so that it gains `turning`.
+fn reshape(el: Element, start: (Point, f64, f64), end: (Point, f64, f64), turning: f64) -> Element {
+    let p3 = end.0;
+    match el {
+        Element::MoveTo(_) => el,
+        Element::LineTo(_, is_smooth) => Element::LineTo(p3, is_smooth),
+        Element::ArcTo(_, sweep, is_smooth) => Element::ArcTo(p3, sweep + turning, is_smooth),
+        Element::SplineTo(p1, p2, _, is_smooth) => {
+            let (q1, q2) = spline::fit_handles(start, end);
+            Element::SplineTo(p1.and(Some(q1)), p2.and(Some(q2)), p3, is_smooth)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{distance, SplineSpec};
+
+    #[test]
+    fn slide_point() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(
+            Some(Point::new(0., 60.)),
+            Some(Point::new(40., 100.)),
+            Point::new(100., 100.),
+            true,
+        );
+        spec.spline_to(
+            Some(Point::new(160., 100.)),
+            Some(Point::new(200., 60.)),
+            Point::new(200., 0.),
+            false,
+        );
+        spec.line_to(Point::new(300., 0.), false);
+        let before = spec.solve().into_owned();
+        let len = before.segments()[0].arclen();
+        spec.slide_point(1, -30.0);
+        let after = spec.solve().into_owned();
+        assert!((after.segments()[0].arclen() - (len - 30.0)).abs() < 0.5);
+        assert!(distance::spline_distance(&before, &after, 0.01) < 0.5);
+        // Sliding onto a line stops short of its end, and keeps it straight.
+        spec.slide_point(2, 1000.0);
+        let p = spec.elements()[2].endpoint();
+        assert!(p.y.abs() < 1e-9 && p.x > 298. && p.x < 300., "{:?}", p);
+        // The ends of an open path don't move.
+        spec.slide_point(3, -10.0);
+        assert_eq!(spec.elements()[3].endpoint(), Point::new(300., 0.));
+    }
+
+    #[test]
+    fn slide_point_on_circle() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(100., 0.));
+        spec.arc_to(Point::new(0., 100.), std::f64::consts::FRAC_PI_2, true);
+        spec.arc_to(Point::new(-100., 0.), std::f64::consts::FRAC_PI_2, false);
+        let before = spec.solve().into_owned();
+        spec.slide_point(1, 50.0);
+        let after = spec.solve().into_owned();
+        assert!(distance::spline_distance(&before, &after, 0.01) < 0.1);
+        assert!((after.segments()[0].arclen() - (before.segments()[0].arclen() + 50.)).abs() < 0.5);
+    }
+}
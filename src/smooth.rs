@@ -0,0 +1,257 @@
+//! Making on-curve points smooth.
+
+use std::f64::consts::PI;
+
+use kurbo::{Affine, Point, Vec2};
+
+use crate::hyperbezier::{self, HyperBezier, ThetaParams};
+use crate::spline::{Element, SplineSpec};
+use crate::util;
+
+/// How the tangent on one side of a point can change.
+#[derive(Clone, Copy)]
+enum Side {
+    /// The tangent is fixed, by a line or an arc.
+    Fixed(f64),
+    /// The tangent is set by an explicit control point, which can be
+    /// rotated. The weight is the cost of rotating it.
+    Handle(f64, f64),
+    /// The tangent is chosen by the solver.
+    Auto,
+}
+
+impl SplineSpec {
+    /// Make the on-curve point at the end of element `ix` smooth, changing
+    /// the shape as little as possible.
+    ///
+    /// Explicit control points on either side are rotated about the point
+    /// onto a shared tangent, keeping their distances from it. The tangent
+    /// is the average of the two current tangents, weighted by the square
+    /// of the arclength of each side's segment, so that the area swept by
+    /// the change is small. A line or arc on one side fixes the tangent,
+    /// and auto control points follow whatever the other side chooses.
+    ///
+    /// For a closed path, the start point is the end of the last element.
+    /// Marking the ends of an open path smooth has no effect on the shape.
+    pub fn make_smooth(&mut self, ix: usize) {
+        let (ix, next_ix) = self.joint_ixs(ix);
+        self.elements_mut()[ix].set_smooth(true);
+        let next_ix = match next_ix {
+            Some(next_ix) => next_ix,
+            None => return,
+        };
+        let segs = self.solve().segments().to_vec();
+        let (prev_seg, next_seg) = (&segs[ix - 1], &segs[next_ix - 1]);
+        let elements = self.elements();
+        let p = elements[ix].endpoint();
+        let prev = match elements[ix] {
+            Element::SplineTo(_, Some(p2), _, _) => {
+                Side::Handle((p - p2).atan2(), prev_seg.arclen().powi(2))
+            }
+            Element::SplineTo(_, None, _, _) => Side::Auto,
+            _ => Side::Fixed(prev_seg.tangent_angle(1.0)),
+        };
+        let next = match elements[next_ix] {
+            Element::SplineTo(Some(p1), _, _, _) => {
+                Side::Handle((p1 - p).atan2(), next_seg.arclen().powi(2))
+            }
+            Element::SplineTo(None, _, _, _) => Side::Auto,
+            _ => Side::Fixed(next_seg.tangent_angle(0.0)),
+        };
+        let th = match (prev, next) {
+            (Side::Fixed(th), _) | (_, Side::Fixed(th)) => th,
+            (Side::Handle(th0, w0), Side::Handle(th1, w1)) => {
+                th0 + w1 / (w0 + w1) * util::mod_tau(th1 - th0)
+            }
+            (Side::Handle(th, _), Side::Auto) | (Side::Auto, Side::Handle(th, _)) => th,
+            (Side::Auto, Side::Auto) => return,
+        };
+        let dir = Vec2::from_angle(th);
+        let elements = self.elements_mut();
+        if let Element::SplineTo(_, Some(p2), _, _) = &mut elements[ix] {
+            *p2 = util::rotate_onto(*p2, p, -dir);
+        }
+        if let Element::SplineTo(Some(p1), _, _, _) = &mut elements[next_ix] {
+            *p1 = util::rotate_onto(*p1, p, dir);
+        }
+    }
+
+    /// Adjust the explicit control points either side of the on-curve point
+    /// at the end of element `ix` so that the curvature there is continuous.
+    ///
+    /// The point is first made smooth, as by
+    /// [`make_smooth`](SplineSpec::make_smooth). Then the control points on
+    /// either side are moved along the shared tangent until both segments
+    /// have the average of their curvatures at the point. The other control
+    /// points of the two segments stay where they are.
+    ///
+    /// This does nothing unless there are explicit control points on both
+    /// sides of the point; where one side is automatic, the solver already
+    /// matches curvature.
+    pub fn harmonize_joint(&mut self, ix: usize) {
+        self.make_smooth(ix);
+        let (ix, next_ix) = match self.joint_ixs(ix) {
+            (ix, Some(next_ix)) => (ix, next_ix),
+            _ => return,
+        };
+        let (p, p1) = match (self.elements()[ix], self.elements()[next_ix]) {
+            (Element::SplineTo(_, Some(_), p, _), Element::SplineTo(Some(p1), ..)) => (p, p1),
+            _ => return,
+        };
+        let segs = self.solve().segments().to_vec();
+        let (prev, next) = (&segs[ix - 1], &segs[next_ix - 1]);
+        let k = 0.5 * (prev.curvature(1.0) + next.curvature(0.0));
+        let th = (p1 - p).atan2();
+        let new_p2 = fit_end_handle(prev.p0, prev.p1, p, th, k);
+        let new_p1 = fit_end_handle(next.p3, next.p2, p, th + PI, -k);
+        let elements = self.elements_mut();
+        if let Element::SplineTo(_, Some(p2), _, _) = &mut elements[ix] {
+            *p2 = new_p2;
+        }
+        if let Element::SplineTo(Some(p1), _, _, _) = &mut elements[next_ix] {
+            *p1 = new_p1;
+        }
+    }
+
+    /// The element ending at on-curve point `ix`, and the element after it,
+    /// if the point is not an end of an open path.
+    ///
+    /// For a closed path, the start point is the end of the last element.
+    pub(crate) fn joint_ixs(&self, ix: usize) -> (usize, Option<usize>) {
+        let n = self.elements().len();
+        if self.is_closed() && n > 1 && (ix == 0 || ix == n - 1) {
+            (n - 1, Some(1))
+        } else if ix == 0 || ix + 1 >= n {
+            (ix, None)
+        } else {
+            (ix, Some(ix + 1))
+        }
+    }
+}
+
+/// The end control point for a segment from `p0`, with start control point
+/// `p1`, to `p3`, that gives tangent angle `th3` and curvature `k3` at the
+/// end.
+///
+/// This is like the spline's handle fitting, with the start fixed.
+fn fit_end_handle(p0: Point, p1: Point, p3: Point, th3: f64, k3: f64) -> Point {
+    let v = p3 - p0;
+    let chord_th = v.atan2();
+    let a = Affine::new([v.x, v.y, -v.y, v.x, p0.x, p0.y]);
+    let (th0, bias0) = HyperBezier::params_for_v((a.inverse() * p1).to_vec2());
+    let th1 = util::mod_tau(chord_th - th3);
+    let mut bias1 = 1.0;
+    for _ in 0..4 {
+        let params = ThetaParams {
+            th0: -th0,
+            bias0,
+            th1: -th1,
+            bias1,
+        };
+        let hb = HyperBezier::solve_for_theta(&params);
+        let arclen = v.hypot() / hb.compute().chord;
+        if hb.k1 != 0.0 {
+            bias1 =
+                hyperbezier::compute_k_inv(k3 * arclen / hb.k1).max(hyperbezier::MIN_MATCH_BIAS);
+        }
+    }
+    a * (Point::new(1.0, 0.0) - HyperBezier::v_for_params(-th1, bias1))
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Point, Vec2};
+
+    use crate::{Element, SplineSpec};
+
+    #[test]
+    fn make_smooth_weighted() {
+        // A short segment and a long one meeting at a 20° corner.
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        let a = Point::new(20., 0.);
+        spec.spline_to(
+            Some(Point::new(5., 5.)),
+            Some(Point::new(15., 0.)),
+            a,
+            false,
+        );
+        let p1 = a + 50. * Vec2::from_angle(20f64.to_radians());
+        spec.spline_to(
+            Some(p1),
+            Some(Point::new(150., 50.)),
+            Point::new(200., 0.),
+            false,
+        );
+        spec.make_smooth(1);
+        // The handles are collinear, and the long segment's hardly moves.
+        match (spec.elements()[1], spec.elements()[2]) {
+            (Element::SplineTo(_, Some(q2), ..), Element::SplineTo(Some(q1), ..)) => {
+                assert!((q1 - a).cross(a - q2).abs() < 1e-9);
+                assert!((q1 - a).dot(a - q2) > 0.0);
+                let turn = (q1 - a).atan2() - (p1 - a).atan2();
+                assert!(turn.abs() < 1f64.to_radians(), "{}", turn);
+                assert!(((q1 - a).hypot() - 50.).abs() < 1e-9);
+            }
+            _ => panic!(),
+        }
+        let spline = spec.solve();
+        let joint = spline.iter_joints().next().unwrap();
+        assert!(joint.angle.abs() < 1e-3, "{}", joint.angle);
+    }
+
+    #[test]
+    fn make_smooth_after_line() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), false);
+        spec.spline_to(
+            Some(Point::new(130., 20.)),
+            None,
+            Point::new(150., 80.),
+            false,
+        );
+        spec.make_smooth(1);
+        assert_eq!(
+            spec.elements()[2],
+            Element::SplineTo(
+                Some(Point::new(100. + 20f64.hypot(30.), 0.)),
+                None,
+                Point::new(150., 80.),
+                false
+            )
+        );
+        assert!(spec.elements()[1].is_smooth());
+    }
+
+    #[test]
+    fn harmonize_joint() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        let a = Point::new(100., 50.);
+        spec.spline_to(
+            Some(Point::new(0., 40.)),
+            Some(Point::new(60., 50.)),
+            a,
+            true,
+        );
+        spec.spline_to(
+            Some(Point::new(110., 50.)),
+            Some(Point::new(200., 40.)),
+            Point::new(200., 0.),
+            false,
+        );
+        let before = spec.solve().into_owned();
+        let (prev, next) = (&before.segments()[0], &before.segments()[1]);
+        assert!((prev.curvature(1.0) - next.curvature(0.0)).abs() > 1e-3);
+        spec.harmonize_joint(1);
+        let spline = spec.solve();
+        let (prev, next) = (&spline.segments()[0], &spline.segments()[1]);
+        let (k0, k1) = (prev.curvature(1.0), next.curvature(0.0));
+        assert!((k0 - k1).abs() < 1e-2 * k0.abs(), "{} {}", k0, k1);
+        // The handles stay level, and the outer ones don't move.
+        assert!((prev.p2.y - 50.).abs() < 1e-9 && (next.p1.y - 50.).abs() < 1e-9);
+        assert_eq!(prev.p1, Point::new(0., 40.));
+        assert_eq!(next.p2, Point::new(200., 40.));
+    }
+}
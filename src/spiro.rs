@@ -0,0 +1,217 @@
+//! Export to Spiro control points.
+//!
+//! This is a downgrade path, for moving designs into editors built on
+//! libspiro. Spiro has no notion of tension, so the export is only
+//! approximate, but segments with a bias near 1 are Euler spirals, which
+//! Spiro reproduces closely.
+
+use kurbo::{Affine, Point};
+
+use crate::hyperbezier::{HyperBezier, ThetaParams};
+use crate::spline::{Segment, Spline};
+use crate::util;
+
+/// The largest turn, in radians, for a point to be exported as smooth.
+const ANGLE_TOLERANCE: f64 = 1e-6;
+
+/// The number of samples used to estimate the error of each curved piece.
+const ERROR_SAMPLES: usize = 16;
+
+/// The type of a Spiro control point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpiroType {
+    /// A corner, `'v'`.
+    Corner,
+    /// A point with continuous curvature and its derivatives, `'o'`.
+    G4,
+    /// A point where a curve meets a following straight line, `'['`.
+    Left,
+    /// A point where a straight line meets a following curve, `']'`.
+    Right,
+    /// The start of an open contour, `'{'`.
+    OpenStart,
+    /// The end of an open contour, `'}'`.
+    OpenEnd,
+}
+
+impl SpiroType {
+    /// The character libspiro uses for this type.
+    pub fn as_char(self) -> char {
+        match self {
+            SpiroType::Corner => 'v',
+            SpiroType::G4 => 'o',
+            SpiroType::Left => '[',
+            SpiroType::Right => ']',
+            SpiroType::OpenStart => '{',
+            SpiroType::OpenEnd => '}',
+        }
+    }
+}
+
+/// A Spiro control point.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpiroPoint {
+    pub point: Point,
+    pub ty: SpiroType,
+}
+
+/// The result of exporting a spline to Spiro.
+#[derive(Clone, Debug)]
+pub struct SpiroExport {
+    /// The control points, in order. A closed contour does not repeat its
+    /// first point.
+    pub points: Vec<SpiroPoint>,
+    pub is_closed: bool,
+    /// An estimate of the largest distance between the spline and the
+    /// Spiro curve through the points.
+    ///
+    /// This compares each curved piece with the Euler spiral that has the
+    /// same tangents at its ends. It assumes Spiro reproduces those
+    /// tangents, which holds well when the points are dense, so it is an
+    /// estimate rather than a guarantee.
+    pub error: f64,
+}
+
+impl<'a> Spline<'a> {
+    /// Export as Spiro control points.
+    ///
+    /// Each curved segment is given `subdivisions` pieces, by adding evenly
+    /// spaced G4 points, which reduces the error at the cost of extra
+    /// points. A value of 1 exports only the on-curve points.
+    pub fn to_spiro(&self, subdivisions: usize) -> SpiroExport {
+        let segs = self.segments();
+        let n = segs.len();
+        let subdivisions = subdivisions.max(1);
+        let mut points = Vec::new();
+        let mut error: f64 = 0.0;
+        for (i, seg) in segs.iter().enumerate() {
+            let prev = if i > 0 || self.is_closed() {
+                Some(&segs[(i + n - 1) % n])
+            } else {
+                None
+            };
+            points.push(SpiroPoint {
+                point: seg.p0,
+                ty: point_type(prev, Some(seg)),
+            });
+            if seg.is_line() {
+                continue;
+            }
+            for j in 0..subdivisions {
+                let t0 = j as f64 / subdivisions as f64;
+                let t1 = (j + 1) as f64 / subdivisions as f64;
+                error = error.max(euler_error(seg, t0, t1));
+                if j + 1 < subdivisions {
+                    points.push(SpiroPoint {
+                        point: seg.eval(t1),
+                        ty: SpiroType::G4,
+                    });
+                }
+            }
+        }
+        if !self.is_closed() {
+            if let Some(last) = segs.last() {
+                points.push(SpiroPoint {
+                    point: last.p3,
+                    ty: SpiroType::OpenEnd,
+                });
+            }
+        }
+        SpiroExport {
+            points,
+            is_closed: self.is_closed(),
+            error,
+        }
+    }
+}
+
+fn point_type(prev: Option<&Segment>, next: Option<&Segment>) -> SpiroType {
+    let (prev, next) = match (prev, next) {
+        (None, _) => return SpiroType::OpenStart,
+        (_, None) => return SpiroType::OpenEnd,
+        (Some(prev), Some(next)) => (prev, next),
+    };
+    let angle = util::mod_tau(next.tangent_angle(0.0) - prev.tangent_angle(1.0));
+    if angle.abs() > ANGLE_TOLERANCE {
+        return SpiroType::Corner;
+    }
+    match (prev.is_line(), next.is_line()) {
+        (false, false) => SpiroType::G4,
+        (false, true) => SpiroType::Left,
+        (true, false) => SpiroType::Right,
+        (true, true) => SpiroType::Corner,
+    }
+}
+
+/// The largest distance from the piece of `seg` between arclength
+/// parameters `t0` and `t1` to the Euler spiral with the same end points
+/// and tangents.
+fn euler_error(seg: &Segment, t0: f64, t1: f64) -> f64 {
+    let (p0, p1) = (seg.eval(t0), seg.eval(t1));
+    let chord = p1 - p0;
+    let chord_th = chord.atan2();
+    let euler = HyperBezier::solve_for_theta(&ThetaParams {
+        th0: -util::mod_tau(seg.tangent_angle(t0) - chord_th),
+        bias0: 1.0,
+        th1: util::mod_tau(seg.tangent_angle(t1) - chord_th),
+        bias1: 1.0,
+    });
+    // Map the spiral's own chord onto the piece's chord.
+    let v = euler.integrate(0.0, 1.0, 24);
+    let a = Affine::new([chord.x, chord.y, -chord.y, chord.x, p0.x, p0.y])
+        * Affine::new([v.x, v.y, -v.y, v.x, 0.0, 0.0]).inverse();
+    let spiral: Vec<Point> = (0..=ERROR_SAMPLES)
+        .map(|i| {
+            a * euler
+                .integrate(0.0, i as f64 / ERROR_SAMPLES as f64, 24)
+                .to_point()
+        })
+        .collect();
+    (0..=ERROR_SAMPLES)
+        .map(|i| {
+            let p = seg.eval(t0 + (t1 - t0) * i as f64 / ERROR_SAMPLES as f64);
+            spiral
+                .windows(2)
+                .map(|w| (p - w[0].lerp(w[1], util::nearest_on_line(p, w[0], w[1]))).hypot())
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Point, Rect};
+
+    use super::SpiroType;
+    use crate::SplineSpec;
+
+    #[test]
+    fn rounded_rect_types() {
+        let mut spec = SplineSpec::rounded_rect(Rect::new(0., 0., 100., 50.), 10.);
+        let export = spec.solve().to_spiro(1);
+        let types: String = export.points.iter().map(|p| p.ty.as_char()).collect();
+        assert!(export.is_closed);
+        assert_eq!(types.matches('[').count(), 4);
+        assert_eq!(types.matches(']').count(), 4);
+        // Circular arcs are Euler spirals.
+        assert!(export.error < 1e-6, "{}", export.error);
+    }
+
+    #[test]
+    fn tension_error() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        let handle = Some(Point::new(100., 0.));
+        spec.spline_to(handle, handle, Point::new(100., 100.), false);
+        let spline = spec.solve();
+        let coarse = spline.to_spiro(1);
+        assert_eq!(coarse.points.len(), 2);
+        assert_eq!(coarse.points[0].ty, SpiroType::OpenStart);
+        assert_eq!(coarse.points[1].ty, SpiroType::OpenEnd);
+        // A tight corner is far from an Euler spiral, but extra points help.
+        let fine = spline.to_spiro(8);
+        assert_eq!(fine.points.len(), 9);
+        assert!(coarse.error > 1.0, "{}", coarse.error);
+        assert!(fine.error < 0.25 * coarse.error, "{}", fine.error);
+    }
+}
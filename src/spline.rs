@@ -1,15 +1,34 @@
 //! A general purpose spline with explicit control.
 
-use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::f64::consts::FRAC_PI_2;
+use std::ops::Deref;
+use std::sync::Arc;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
 
-use kurbo::{Affine, BezPath, PathEl, Point, Vec2};
+use kurbo::{Affine, BezPath, Line, PathEl, PathSeg, Point, Rect, Shape, Vec2};
 #[cfg(feature = "serde")]
 use serde_::{Deserialize, Serialize};
 
-use crate::hyperbezier::{self, HyperBezier, ThetaParams};
-use crate::simple_spline;
+use crate::hyperbezier::{self, HyperBezier, RenderOptions, ThetaParams};
+use crate::lock::Lock;
+use crate::metadata::Metadata;
+use crate::simple_spline::{self, BiasLaw, EulerLimit};
 use crate::util;
 
+/// The number of iterations of the solver.
+const N_ITERATIONS: usize = 10;
+
+/// How quickly the steps of the solver grow to full size.
+const STEP_RAMP: f64 = 0.25;
+
+/// The largest change, in radians, to a free tangent angle in one step.
+///
+/// Beyond this, the linearization behind the step is not to be trusted.
+const MAX_STEP: f64 = 0.5;
+
 /// The specification of a spline curve.
 ///
 /// Currently this represents a single subpath.
@@ -21,21 +40,196 @@ use crate::util;
 )]
 pub struct SplineSpec {
     elements: Vec<Element>,
-    is_closed: bool,
+    pub(crate) is_closed: bool,
+    /// User metadata, by element index.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub(crate) metadata: Metadata,
+    /// Locked coordinates of on-curve points, by element index.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub(crate) locks: BTreeMap<usize, Lock>,
+    /// Extra full turns of spline segments, by element index.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "BTreeMap::is_empty")
+    )]
+    pub(crate) windings: BTreeMap<usize, i32>,
     /// The free thetas to solve for.
     ///
     /// There is one of these for each smooth on-curve point with an auto
     /// point on both sides.
     #[cfg_attr(feature = "serde", serde(skip))]
     ths: Vec<f64>,
+    /// The index in `ths` of the free joint at the start of each element,
+    /// if the joint there is free.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    free_joints: Vec<Option<usize>>,
+    /// Thetas to start the next solve from, instead of the usual guess.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    initial_ths: Option<Vec<f64>>,
     #[cfg_attr(feature = "serde", serde(skip))]
     dths: Vec<f64>,
+    /// The thetas of the previous solution, when continuing from it.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    anchor_ths: Option<Vec<f64>>,
     /// The tentative solution.
     #[cfg_attr(feature = "serde", serde(skip))]
     segments: Vec<Segment>,
+    /// The inputs each auto segment was last solved from, to skip solving
+    /// it again when they haven't changed.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    seg_inputs: Vec<Option<SegInputs>>,
+    /// The number of segments solved since the start of the solve.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    n_segment_solves: usize,
     /// `true` if the inputs have changed, and the spline needs to be solved.
     #[cfg_attr(feature = "serde", serde(skip, default = "serde_true"))]
-    dirty: bool,
+    pub(crate) dirty: bool,
+    /// Diagnostics from the last solve.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: Option<SolveStats>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    options: SolveOptions,
+    /// The state of a budgeted solve that ran out of time.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    partial: Option<PartialSolve>,
+}
+
+/// How far a budgeted solve got, so that it can be resumed.
+#[derive(Clone, Debug)]
+struct PartialSolve {
+    /// The inputs being solved for, to tell whether they have changed.
+    elements: Vec<Element>,
+    is_closed: bool,
+    windings: BTreeMap<usize, i32>,
+    locks: BTreeMap<usize, Lock>,
+    metadata: Metadata,
+    /// The number of iterations run so far.
+    iterations: usize,
+    residual: f64,
+    elapsed: Option<Duration>,
+}
+
+impl PartialSolve {
+    /// Whether this solve was started from the current inputs of `spec`.
+    fn is_for(&self, spec: &SplineSpec) -> bool {
+        self.elements == spec.elements
+            && self.is_closed == spec.is_closed
+            && self.windings == spec.windings
+            && self.locks == spec.locks
+            && self.metadata == spec.metadata
+    }
+}
+
+/// Options controlling the solver.
+///
+/// Each iteration of the solver adjusts the free tangent angles, then, for
+/// auto control points next to explicit ones, the tensions. The two passes
+/// interact, and the tension pass can be limited to compare their effect.
+#[derive(Clone, Debug)]
+pub struct SolveOptions {
+    /// The number of iterations.
+    pub iterations: usize,
+    /// The number of iterations, from the first, that adjust tensions.
+    ///
+    /// Zero gives a pure solve for tangent angles.
+    pub tension_passes: usize,
+    /// The bias of auto control points, from their tangent angles.
+    pub bias_law: Arc<dyn BiasLaw>,
+    /// Continue from the previous solution, changing each free tangent
+    /// angle by at most this many radians per solve.
+    ///
+    /// When solving again after the elements move a little, as while
+    /// dragging, the solve starts from the previous tangent angles, and
+    /// the trust region keeps it on the same branch of solutions near
+    /// symmetric configurations, where it would otherwise flicker between
+    /// them. This only applies if the number of free joints hasn't changed;
+    /// tangent angles given by
+    /// [`set_initial_tangents`](SplineSpec::set_initial_tangents) take
+    /// precedence. A large edit may then take a few solves to settle.
+    pub continuation: Option<f64>,
+    /// The largest angle, in radians, between a chord and the initial
+    /// guess for the tangent at either end of it.
+    ///
+    /// The guess for the tangent at a free joint leans towards the shorter
+    /// of the chords either side, but no further than this from the other
+    /// one, as a segment turning more than a right angle away from its
+    /// chord is hard for the solver to recover from. This only matters
+    /// where neighboring chords are very different in length, as in a
+    /// quickly growing spiral. A larger value lets the guess lean further,
+    /// and at `PI` or above it is not limited. The guess never leans past
+    /// an even split, so smaller values only hold at joints bending less
+    /// than twice as much.
+    pub max_chord_angle: f64,
+    /// How quickly the steps of the solver grow to full size.
+    ///
+    /// Iteration `i`, counting from 0, takes `tanh(step_ramp * (i + 1))`
+    /// of each step it computes, for both the tangent angles and the
+    /// tensions. Small steps at first keep the solve stable from a poor
+    /// initial guess, and larger values converge faster from a good one.
+    /// Infinity takes full steps throughout.
+    pub step_ramp: f64,
+    /// The lowest bias the tension pass gives an auto control point when
+    /// matching the curvature of an explicit one.
+    ///
+    /// This defaults to [`MIN_MATCH_BIAS`](crate::MIN_MATCH_BIAS), leaving
+    /// a margin above [`MIN_BIAS`](crate::MIN_BIAS), where the curve is
+    /// poorly behaved. Higher values give up some curvature continuity for
+    /// less negative tension.
+    pub min_match_bias: f64,
+}
+
+impl Default for SolveOptions {
+    fn default() -> SolveOptions {
+        SolveOptions {
+            iterations: N_ITERATIONS,
+            tension_passes: N_ITERATIONS,
+            bias_law: Arc::new(EulerLimit::default()),
+            continuation: None,
+            max_chord_angle: FRAC_PI_2,
+            step_ramp: STEP_RAMP,
+            min_match_bias: hyperbezier::MIN_MATCH_BIAS,
+        }
+    }
+}
+
+/// Diagnostics from solving a spec, for evaluating the solver.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SolveStats {
+    /// The error in curvature continuity at the smooth points being solved,
+    /// as measured by the last iteration.
+    ///
+    /// This is the sum of the errors in the arctangents of the curvatures,
+    /// scaled by the chords, so it is dimensionless.
+    pub residual: f64,
+    /// The number of iterations run.
+    pub iterations: usize,
+    /// The number of times a segment was solved for its hyperbezier.
+    ///
+    /// Segments whose inputs don't change between iterations are not
+    /// solved again.
+    pub segment_solves: usize,
+    /// The time taken by the solve.
+    ///
+    /// This is `None` on targets without a clock, such as wasm32.
+    pub elapsed: Option<Duration>,
+}
+
+/// The inputs to solving an auto segment: its end points and the
+/// parameters of its hyperbezier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct SegInputs {
+    p0: Point,
+    p3: Point,
+    th0: f64,
+    bias0: f64,
+    th1: f64,
+    bias1: f64,
 }
 
 #[cfg(feature = "serde")]
@@ -46,14 +240,51 @@ fn serde_true() -> bool {
 /// A solved spline.
 ///
 /// This can be converted to a Bézier path with [`Spline::render`].
+///
+/// A `Spline<'static>`, such as one returned by [`Spline::into_owned`],
+/// shares its segments by reference counting, so it is cheap to clone and
+/// can be sent between threads, for example to cache a solution for
+/// rendering.
 #[derive(Clone, Debug)]
 pub struct Spline<'spec> {
-    segments: Cow<'spec, [Segment]>,
+    segments: Slice<'spec, Segment>,
+    /// The solved tangent angles at the free joints.
+    joint_tangents: Slice<'spec, f64>,
     is_closed: bool,
 }
 
-/// A single spline segment.
+/// Data of a spline, either borrowed from a spec or shared.
 #[derive(Clone, Debug)]
+enum Slice<'a, T> {
+    Borrowed(&'a [T]),
+    Shared(Arc<[T]>),
+}
+
+impl<'a, T> Slice<'a, T> {
+    fn into_shared(self) -> Slice<'static, T>
+    where
+        T: Clone,
+    {
+        match self {
+            Slice::Borrowed(items) => Slice::Shared(items.into()),
+            Slice::Shared(items) => Slice::Shared(items),
+        }
+    }
+}
+
+impl<'a, T> Deref for Slice<'a, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Slice::Borrowed(items) => items,
+            Slice::Shared(items) => items,
+        }
+    }
+}
+
+/// A single spline segment.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Segment {
     pub p0: Point,
     pub p1: Point,
@@ -73,7 +304,7 @@ pub struct Segment {
 }
 
 /// An imperative description of a spline path.
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 #[cfg_attr(
     feature = "serde",
     derive(Serialize, Deserialize),
@@ -102,6 +333,23 @@ pub enum Element {
     ///
     /// [`LineTo`]: [Element::LineTo]
     SplineTo(Option<Point>, Option<Point>, Point, bool),
+    /// A circular arc.
+    ///
+    /// The arc runs from the previous point to the given point, and the
+    /// `f64` is the sweep angle in radians, that is, the total change in
+    /// tangent angle along the arc. It follows the same sign convention as
+    /// [`Vec2::atan2`], so positive values turn counterclockwise in a y-up
+    /// coordinate system. A sweep of zero is a straight line.
+    ///
+    /// The arc is represented exactly, as a constant curvature hyperbezier.
+    /// Its tangents and curvature are fixed, and auto points on neighboring
+    /// [`SplineTo`] elements are solved to blend into it.
+    ///
+    /// The `bool` indicates whether the operation is smooth, as per [`LineTo`].
+    ///
+    /// [`SplineTo`]: [Element::SplineTo]
+    /// [`LineTo`]: [Element::LineTo]
+    ArcTo(Point, f64, bool),
 }
 
 impl SplineSpec {
@@ -110,10 +358,21 @@ impl SplineSpec {
         SplineSpec {
             elements: Vec::new(),
             is_closed: false,
+            metadata: BTreeMap::new(),
+            locks: BTreeMap::new(),
+            windings: BTreeMap::new(),
             ths: Vec::new(),
+            free_joints: Vec::new(),
+            initial_ths: None,
             dths: Vec::new(),
+            anchor_ths: None,
             segments: Vec::new(),
+            seg_inputs: Vec::new(),
+            n_segment_solves: 0,
             dirty: true,
+            stats: None,
+            options: SolveOptions::default(),
+            partial: None,
         }
     }
 
@@ -135,12 +394,30 @@ impl SplineSpec {
         self.dirty = true;
     }
 
+    /// Add a circular arc to `p`, turning through `sweep` radians.
+    ///
+    /// See [`Element::ArcTo`] for the sign convention.
+    pub fn arc_to(&mut self, p: Point, sweep: f64, is_smooth: bool) {
+        debug_assert!(!self.elements.is_empty());
+        self.elements.push(Element::ArcTo(p, sweep, is_smooth));
+        self.dirty = true;
+    }
+
+    /// Close the path, joining the last point back to the first.
+    ///
+    /// A spec with only a `MoveTo` can be closed. It has no segments, like
+    /// an open one.
     pub fn close(&mut self) {
-        debug_assert!(self.elements.len() > 1);
+        debug_assert!(!self.elements.is_empty());
         self.is_closed = true;
         self.dirty = true;
     }
 
+    /// Whether the spline is a closed path.
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+
     pub fn elements(&self) -> &[Element] {
         &self.elements
     }
@@ -155,11 +432,84 @@ impl SplineSpec {
     ///
     /// It is possible via this method to leave the elements in an inconsistent
     /// state, such as by inserting multiple `MoveTo` elements. Care is advised.
+    /// Metadata is attached by index, so to keep it with its elements, insert
+    /// and remove elements with [`SplineSpec::insert_element`] and
+    /// [`SplineSpec::remove_element`] instead.
     pub fn elements_mut(&mut self) -> &mut Vec<Element> {
         self.dirty = true;
         &mut self.elements
     }
 
+    /// Insert a smooth on-curve point into a segment, preserving its shape.
+    ///
+    /// Segment `i` corresponds to element `i + 1`, and `t` is the arclength
+    /// parameter within the segment, from 0 to 1. Lines and arcs are split
+    /// exactly. A spline segment is replaced by two segments with explicit
+    /// control points, matching the tangents and curvature of the original
+    /// at the ends and the new point, so the shape is preserved closely but
+    /// not exactly.
+    pub fn subdivide(&mut self, seg_ix: usize, t: f64) {
+        let seg = self.solve().segments()[seg_ix].clone();
+        self.split_segment(&seg, seg_ix, &[(t, seg.tangent_angle(t))]);
+    }
+
+    /// Insert a smooth on-curve point at arclength `s` from the start,
+    /// preserving the shape.
+    ///
+    /// This is the same as [`subdivide`](SplineSpec::subdivide), with the
+    /// position given as a distance along the whole spline, such as when
+    /// typed in by the user. This does nothing if `s` is outside the spline
+    /// or at one of its on-curve points, where there is nothing to split.
+    pub fn subdivide_at_arclen(&mut self, s: f64) {
+        if let Some((seg_ix, t)) = self.solve().locate_interior(s) {
+            self.subdivide(seg_ix, t);
+        }
+    }
+
+    /// Replace the element of segment `seg_ix`, which is `seg` in the
+    /// current solution, with pieces ending at smooth points.
+    ///
+    /// The cuts are pairs of arclength parameter and world space tangent
+    /// angle, in increasing order of the parameter.
+    pub(crate) fn split_segment(&mut self, seg: &Segment, seg_ix: usize, cuts: &[(f64, f64)]) {
+        let el_ix = seg_ix + 1;
+        let mut pieces = Vec::with_capacity(cuts.len() + 1);
+        match self.elements[el_ix] {
+            Element::LineTo(p3, is_smooth) => {
+                pieces.extend(
+                    cuts.iter()
+                        .map(|&(t, _)| Element::LineTo(seg.eval(t), true)),
+                );
+                pieces.push(Element::LineTo(p3, is_smooth));
+            }
+            Element::ArcTo(p3, sweep, is_smooth) => {
+                let mut t0 = 0.0;
+                for &(t, _) in cuts {
+                    pieces.push(Element::ArcTo(seg.eval(t), (t - t0) * sweep, true));
+                    t0 = t;
+                }
+                pieces.push(Element::ArcTo(p3, (1.0 - t0) * sweep, is_smooth));
+            }
+            Element::SplineTo(_, _, p3, is_smooth) => {
+                let mut start = (seg.p0, seg.tangent_angle(0.0), seg.curvature(0.0));
+                for &(t, th) in cuts {
+                    let end = (seg.eval(t), th, seg.curvature(t));
+                    let (p1, p2) = fit_handles(start, end);
+                    pieces.push(Element::SplineTo(Some(p1), Some(p2), end.0, true));
+                    start = end;
+                }
+                let end = (p3, seg.tangent_angle(1.0), seg.curvature(1.0));
+                let (p1, p2) = fit_handles(start, end);
+                pieces.push(Element::SplineTo(Some(p1), Some(p2), p3, is_smooth));
+            }
+            Element::MoveTo(_) => unreachable!(),
+        }
+        self.elements[el_ix] = pieces.pop().unwrap();
+        for piece in pieces.into_iter().rev() {
+            self.insert_element(el_ix, piece);
+        }
+    }
+
     /// Returns the current solution, if it is up-to-date.
     ///
     /// If it is not up-to-date, you need to call [`solve`](SplineSpec::solve)
@@ -176,34 +526,216 @@ impl SplineSpec {
     ///
     /// The returned [`Spline`] borrows data from `self`; if you need an
     /// owned version you can call [`Spline::into_owned`].
-    pub fn solve(&mut self) -> Spline {
+    ///
+    /// Solving doesn't change the elements. Locked points, set by
+    /// [`set_lock`](SplineSpec::set_lock), are slid in the solution only,
+    /// at the cost of many more solves.
+    pub fn solve(&mut self) -> Spline<'_> {
         if self.dirty {
-            self.segments = self.initial_segs();
-            self.ths = self.initial_ths();
-            self.dths = vec![0.0; self.ths.len()];
-            self.update_segs();
-            for i in 0..10 {
-                let _err = self.iterate(i);
-                //eprintln!("err = {}", err);
-                self.adjust_tensions(i);
-                self.update_segs();
+            #[cfg(not(target_arch = "wasm32"))]
+            let start = Instant::now();
+            self.n_segment_solves = 0;
+            self.set_anchor();
+            let mut residual = self.solve_segments();
+            if !self.locks.is_empty() {
+                residual = self.slide_locked_points();
             }
+            #[cfg(not(target_arch = "wasm32"))]
+            let elapsed = Some(start.elapsed());
+            #[cfg(target_arch = "wasm32")]
+            let elapsed = None;
+            self.stats = Some(SolveStats {
+                residual,
+                iterations: self.options.iterations,
+                segment_solves: self.n_segment_solves,
+                elapsed,
+            });
+            self.initial_ths = None;
+            self.partial = None;
             self.dirty = false;
         }
 
         Spline {
-            segments: Cow::Borrowed(self.segments.as_slice()),
+            segments: Slice::Borrowed(self.segments.as_slice()),
+            joint_tangents: Slice::Borrowed(self.ths.as_slice()),
             is_closed: self.is_closed,
         }
     }
 
+    /// Solve for as long as `budget` allows, for keeping up a frame rate
+    /// while editing large specs.
+    ///
+    /// Iterations of the solver are run until they are all done or the
+    /// budget is used up, and the spline is returned with whether the solve
+    /// is complete. At least one iteration is run on each call. If the solve
+    /// is not complete, the spline is the best so far, and the spec stays
+    /// dirty: the next call picks up where this one left off. If the
+    /// elements, windings, locks or metadata have changed in between, as
+    /// while dragging, the solve starts over, but from the tangents reached
+    /// so far, as by [`set_initial_tangents`](SplineSpec::set_initial_tangents).
+    ///
+    /// Specs with locked points, and targets without a clock, such as
+    /// wasm32, are always solved completely, as by
+    /// [`solve`](SplineSpec::solve).
+    pub fn solve_budgeted(&mut self, budget: Duration) -> (Spline<'_>, bool) {
+        if !self.dirty || !self.locks.is_empty() || cfg!(target_arch = "wasm32") {
+            return (self.solve(), true);
+        }
+        #[cfg(target_arch = "wasm32")]
+        let _ = budget;
+        #[cfg(not(target_arch = "wasm32"))]
+        let start = Instant::now();
+        let mut partial = match self.partial.take() {
+            Some(partial) if partial.is_for(self) => partial,
+            partial => {
+                if partial.is_some() && self.initial_ths.is_none() {
+                    self.initial_ths = Some(self.ths.clone());
+                }
+                self.n_segment_solves = 0;
+                self.set_anchor();
+                self.start_solve();
+                PartialSolve {
+                    elements: self.elements.clone(),
+                    is_closed: self.is_closed,
+                    windings: self.windings.clone(),
+                    locks: self.locks.clone(),
+                    metadata: self.metadata.clone(),
+                    iterations: 0,
+                    residual: 0.0,
+                    elapsed: Some(Duration::ZERO),
+                }
+            }
+        };
+        while partial.iterations < self.options.iterations {
+            partial.residual = self.solve_step(partial.iterations);
+            partial.iterations += 1;
+            #[cfg(not(target_arch = "wasm32"))]
+            if start.elapsed() >= budget {
+                break;
+            }
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            partial.elapsed = partial.elapsed.map(|elapsed| elapsed + start.elapsed());
+        }
+        let is_complete = partial.iterations >= self.options.iterations;
+        if is_complete {
+            self.stats = Some(SolveStats {
+                residual: partial.residual,
+                iterations: partial.iterations,
+                segment_solves: self.n_segment_solves,
+                elapsed: partial.elapsed,
+            });
+            self.initial_ths = None;
+            self.dirty = false;
+        } else {
+            self.partial = Some(partial);
+        }
+        let spline = Spline {
+            segments: Slice::Borrowed(self.segments.as_slice()),
+            joint_tangents: Slice::Borrowed(self.ths.as_slice()),
+            is_closed: self.is_closed,
+        };
+        (spline, is_complete)
+    }
+
+    /// Diagnostics from the last solve, if the solution is up-to-date.
+    pub fn solve_stats(&self) -> Option<&SolveStats> {
+        if self.dirty {
+            None
+        } else {
+            self.stats.as_ref()
+        }
+    }
+
+    /// The options used by the solver.
+    pub fn solve_options(&self) -> &SolveOptions {
+        &self.options
+    }
+
+    /// Set the options used by the solver.
+    ///
+    /// The options are not part of the spec's identity: they are not
+    /// serialized or compared.
+    pub fn set_solve_options(&mut self, options: SolveOptions) {
+        self.options = options;
+        self.partial = None;
+        self.dirty = true;
+    }
+
+    /// Solve for the segments with the current elements, returning the
+    /// residual of the last iteration.
+    pub(crate) fn solve_segments(&mut self) -> f64 {
+        self.start_solve();
+        let mut residual = 0.0;
+        for i in 0..self.options.iterations {
+            residual = self.solve_step(i);
+        }
+        residual
+    }
+
+    /// Set up the initial state of the solver.
+    fn start_solve(&mut self) {
+        self.segments = self.initial_segs();
+        self.seg_inputs = vec![None; self.segments.len()];
+        let mut n = 0;
+        self.free_joints = (0..self.elements.len())
+            .map(|i| {
+                self.is_free_joint(i).then(|| {
+                    n += 1;
+                    n - 1
+                })
+            })
+            .collect();
+        self.ths = match (&self.initial_ths, &self.anchor_ths) {
+            (Some(ths), _) if ths.len() == n => ths.clone(),
+            (_, Some(ths)) if ths.len() == n => ths.clone(),
+            _ => self.initial_ths(),
+        };
+        self.dths = vec![0.0; self.ths.len()];
+        self.update_segs();
+    }
+
+    /// Keep the previous solution's thetas to continue from, if the
+    /// options ask for it and they still fit.
+    fn set_anchor(&mut self) {
+        self.anchor_ths = match self.options.continuation {
+            Some(_) if self.stats.is_some() && self.ths.len() == self.n_free_joints() => {
+                Some(self.ths.clone())
+            }
+            _ => None,
+        };
+    }
+
+    /// Run iteration `i` of the solver, returning its residual.
+    fn solve_step(&mut self, i: usize) -> f64 {
+        let residual = self.iterate(i);
+        if let (Some(anchor), Some(radius)) = (&self.anchor_ths, self.options.continuation) {
+            for (th, &th0) in self.ths.iter_mut().zip(anchor) {
+                *th = th0 + util::mod_tau(*th - th0).clamp(-radius, radius);
+            }
+        }
+        if i < self.options.tension_passes {
+            self.adjust_tensions(i);
+        }
+        self.update_segs();
+        residual
+    }
+
+    /// The solved segments, without checking whether they are up-to-date.
+    pub(crate) fn raw_segments(&self) -> &[Segment] {
+        &self.segments
+    }
+
     /// Create initial segments.
     fn initial_segs(&self) -> Vec<Segment> {
         if self.elements.len() > 1 {
             let mut p0 = self.elements[0].endpoint();
             (self.elements[1..])
                 .iter()
-                .map(|el| {
+                .enumerate()
+                .map(|(i, el)| {
+                    let ix = i + 1;
                     let p3 = el.endpoint();
                     let seg = if let &Element::SplineTo(Some(p1), Some(p2), _p3, _) = el {
                         // Both points given, we can compute the final spline segment now.
@@ -214,6 +746,8 @@ impl SplineSpec {
                         let (th0, bias0) = HyperBezier::params_for_v((a_inv * p1).to_vec2());
                         let (th1, bias1) =
                             HyperBezier::params_for_v(Point::new(1.0, 0.0) - a_inv * p2);
+                        let (w0, w1) = self.winding_offsets(ix);
+                        let (th0, th1) = (th0 - w0, th1 + w1);
                         // TODO: signs feel reversed here, but it all works out in the end.
                         let theta_params = ThetaParams {
                             th0: -th0,
@@ -221,8 +755,10 @@ impl SplineSpec {
                             th1,
                             bias1,
                         };
-                        let hb = HyperBezier::solve_for_theta(&theta_params);
+                        let hb = self.curve_for_theta(ix, &theta_params);
                         Segment::make(p0, Some(p1), Some(p2), p3, -th0, th1, hb)
+                    } else if let &Element::ArcTo(_p3, sweep, _) = el {
+                        Segment::arc(p0, p3, sweep)
                     } else {
                         Segment::line(p0, p3)
                     };
@@ -235,20 +771,53 @@ impl SplineSpec {
         }
     }
 
+    /// Start the next solve from the given tangent angles at the free
+    /// joints, such as those of the previous frame while dragging.
+    ///
+    /// See [`Spline::joint_tangents`] for the meaning of the angles. The
+    /// angles are ignored if their number doesn't match the free joints
+    /// when solving, and are only used once.
+    pub fn set_initial_tangents(&mut self, ths: &[f64]) {
+        self.initial_ths = Some(ths.to_vec());
+        self.dirty = true;
+    }
+
+    /// Whether the joint at the start of element `i` is free, its tangent
+    /// angle solved for.
+    ///
+    /// This is a smooth point with an auto control point on both sides.
+    fn is_free_joint(&self, i: usize) -> bool {
+        i > 0
+            && self.elements[i].is_auto_p1()
+            && self.prev_el(i).map(Element::is_auto_p2).unwrap_or(false)
+    }
+
+    /// The number of free joints, whose tangent angles are solved for.
+    fn n_free_joints(&self) -> usize {
+        (1..self.elements.len())
+            .filter(|&i| self.is_free_joint(i))
+            .count()
+    }
+
     fn initial_ths(&self) -> Vec<f64> {
         let mut ths = Vec::new();
         for i in 1..self.elements.len() {
-            if self.elements[i].is_auto_p1()
-                && self.prev_el(i).map(Element::is_auto_p2).unwrap_or(false)
-            {
+            if self.is_free_joint(i) {
                 let d0 = self.chord(self.prev_ix(i));
                 let d1 = self.chord(i);
                 let th0 = d0.atan2();
                 let th1 = d1.atan2();
                 let bend = util::mod_tau(th1 - th0);
+                // Lean towards the shorter chord, which is closer to the
+                // tangent. With very different chords, an even split bends
+                // the short segment so far that the solver can't recover.
+                let (ch0, ch1) = (d0.hypot(), d1.hypot());
                 // This is a bit different than the research spline, but is
-                // intended to ensure that the chord angle never exceeds pi/2.
-                let th = util::mod_tau(th0 + 0.5 * bend);
+                // intended to ensure that the chord angle never exceeds the
+                // limit.
+                let lim = (self.options.max_chord_angle / bend.abs()).clamp(0.5, 1.0);
+                let w = (ch0 / (ch0 + ch1)).max(1.0 - lim).min(lim);
+                let th = util::mod_tau(th0 + w * bend);
                 ths.push(th);
             }
         }
@@ -258,7 +827,6 @@ impl SplineSpec {
     /// Generate segments from the spline spec and thetas.
     fn update_segs(&mut self) {
         let n_seg = self.segments.len();
-        let mut th_ix = 0;
         for i in 0..n_seg {
             if let Element::SplineTo(p1, p2, p3, _is_smooth) = self.elements[i + 1] {
                 if p1.is_some() && p2.is_some() {
@@ -274,13 +842,10 @@ impl SplineSpec {
                     let v0 = (a_inv * p1).to_vec2();
                     let (th0, bias0) = HyperBezier::params_for_v(v0);
                     (Some(th0), Some(bias0))
+                } else if let Some(j) = self.free_joints[i + 1] {
+                    (Some(util::mod_tau(self.ths[j] - chord_th)), None)
                 } else {
                     match self.prev_el(i + 1) {
-                        Some(Element::SplineTo(_, None, _, _)) => {
-                            let th0 = util::mod_tau(self.ths[th_ix] - chord_th);
-                            th_ix = (th_ix + 1) % self.ths.len();
-                            (Some(th0), None)
-                        }
                         Some(Element::SplineTo(_, Some(p2), _, _)) => {
                             let prev_ch_th = (p0 - *p2).atan2();
                             let th0 = util::mod_tau(prev_ch_th - chord_th);
@@ -292,6 +857,12 @@ impl SplineSpec {
                             let th0 = util::mod_tau(prev_ch_th - chord_th);
                             (Some(th0), Some(0.0))
                         }
+                        Some(Element::ArcTo(..)) => {
+                            let prev_seg = &self.segments[(i + n_seg - 1) % n_seg];
+                            let prev_tan_th = prev_seg.chord().atan2() - prev_seg.th1;
+                            let th0 = util::mod_tau(prev_tan_th - chord_th);
+                            (Some(th0), Some(self.segments[i].hb.bias0))
+                        }
                         _ => (None, None),
                     }
                 };
@@ -299,12 +870,10 @@ impl SplineSpec {
                     let v1 = Point::new(1.0, 0.0) - (a_inv * p2);
                     let (th1, bias1) = HyperBezier::params_for_v(v1);
                     (Some(-th1), Some(bias1))
+                } else if let Some(j) = self.free_joints[self.next_ix(i + 1)] {
+                    (Some(util::mod_tau(chord_th - self.ths[j])), None)
                 } else {
                     match self.next_el(i + 1) {
-                        Some(Element::SplineTo(None, _, _, _)) => {
-                            let th1 = util::mod_tau(chord_th - self.ths[th_ix]);
-                            (Some(th1), None)
-                        }
                         Some(Element::SplineTo(Some(p1), _, _, _)) => {
                             let next_ch_th = (*p1 - p3).atan2();
                             let th1 = util::mod_tau(chord_th - next_ch_th);
@@ -315,6 +884,12 @@ impl SplineSpec {
                             let th1 = util::mod_tau(chord_th - next_ch_th);
                             (Some(th1), Some(0.0))
                         }
+                        Some(Element::ArcTo(..)) => {
+                            let next_seg = &self.segments[(i + 1) % n_seg];
+                            let next_tan_th = next_seg.chord().atan2() + next_seg.th0;
+                            let th1 = util::mod_tau(chord_th - next_tan_th);
+                            (Some(th1), Some(self.segments[i].hb.bias1))
+                        }
                         _ => (None, None),
                     }
                 };
@@ -324,30 +899,53 @@ impl SplineSpec {
                     (None, Some(th1)) => (simple_spline::endpoint_tangent(th1), th1),
                     (None, None) => continue,
                 };
-                let bias0 = bias0.unwrap_or_else(|| simple_spline::bias_for_theta(th0));
-                let bias1 = bias1.unwrap_or_else(|| simple_spline::bias_for_theta(th1));
+                // The bias law sees the tangent angles without the winding.
+                let bias0 = bias0.unwrap_or_else(|| self.options.bias_law.bias_for_theta(th0));
+                let bias1 = bias1.unwrap_or_else(|| self.options.bias_law.bias_for_theta(th1));
+                let (w0, w1) = self.winding_offsets(i + 1);
+                let (th0, th1) = (th0 - w0, th1 - w1);
+                let inputs = SegInputs {
+                    p0,
+                    p3,
+                    th0,
+                    bias0,
+                    th1,
+                    bias1,
+                };
+                if self.seg_inputs[i] == Some(inputs) {
+                    continue;
+                }
+                self.seg_inputs[i] = Some(inputs);
+                self.n_segment_solves += 1;
                 let params = ThetaParams {
                     th0: -th0,
                     bias0,
                     th1: -th1,
                     bias1,
                 };
-                let hb = HyperBezier::solve_for_theta(&params);
+                let hb = self.curve_for_theta(i + 1, &params);
                 self.segments[i] = Segment::make(p0, p1, p2, p3, th0, th1, hb);
             }
         }
     }
 
+    /// Solve for the curve of the segment of element `ix`, given its theta
+    /// params, which include any winding.
+    fn curve_for_theta(&self, ix: usize, params: &ThetaParams) -> HyperBezier {
+        if self.winding(ix) == 0 {
+            HyperBezier::solve_for_theta(params)
+        } else {
+            HyperBezier::solve_for_theta_wound(params)
+        }
+    }
+
     /// Iterate towards reducing error metric.
     ///
     /// Returns the absolute error (after arctan linearization).
     fn iterate(&mut self, iter_ix: usize) -> f64 {
-        let mut th_ix = 0;
         let mut abs_err = 0.0;
         for i in 1..self.elements.len() {
-            if self.elements[i].is_auto_p1()
-                && self.prev_el(i).map(Element::is_auto_p2).unwrap_or(false)
-            {
+            if let Some(th_ix) = self.free_joints[i] {
                 let prev_seg = &self.segments[self.prev_ix(i) - 1];
                 let prev_ch = prev_seg.chord().hypot();
                 let seg = &self.segments[i - 1];
@@ -359,36 +957,43 @@ impl SplineSpec {
                 abs_err += k_err.abs();
 
                 // Compute error derivative by differencing. A more sophisticated approach
-                // would be analytical derivatives.
+                // would be analytical derivatives. The step follows the sign of the
+                // error, so that a mirrored spline takes the mirrored step.
                 const EPSILON: f64 = 1e-3;
-                let th1p = prev_seg.th1 + EPSILON;
+                let h = EPSILON.copysign(k_err);
+                let th1p = prev_seg.th1 + h;
                 let params0 = ThetaParams {
                     th0: -prev_seg.th0,
                     bias0: prev_seg.hb.bias0,
                     th1: -th1p,
-                    bias1: simple_spline::bias_for_theta(th1p),
+                    bias1: self
+                        .options
+                        .bias_law
+                        .bias_for_theta(th1p + self.winding_offsets(self.prev_ix(i)).1),
                 };
-                let seg0p = HyperBezier::solve_for_theta(&params0);
+                let seg0p = self.curve_for_theta(self.prev_ix(i), &params0);
                 let k0p = seg0p.compute().k1 / prev_ch;
 
-                let th0p = seg.th0 - EPSILON;
+                let th0p = seg.th0 - h;
                 let params1 = ThetaParams {
                     th0: -th0p,
-                    bias0: simple_spline::bias_for_theta(th0p),
+                    bias0: self
+                        .options
+                        .bias_law
+                        .bias_for_theta(th0p + self.winding_offsets(i).0),
                     th1: -seg.th1,
                     bias1: seg.hb.bias1,
                 };
-                let seg1p = HyperBezier::solve_for_theta(&params1);
+                let seg1p = self.curve_for_theta(i, &params1);
                 let k1p = seg1p.compute().k0 / this_ch;
 
                 let k_errp = (k0p * k_scale).atan() - (k1p * k_scale).atan();
-                let derr = (k_errp - k_err) * (1.0 / EPSILON);
+                let derr = (k_errp - k_err) / h;
                 //eprintln!("{}: err = {:.3}, derr = {:.3}", i, k_err, derr);
-                self.dths[th_ix] = k_err / derr;
-                th_ix += 1;
+                self.dths[th_ix] = (k_err / derr).clamp(-MAX_STEP, MAX_STEP);
             }
         }
-        let scale = (0.25 * (iter_ix as f64 + 1.0)).tanh();
+        let scale = (self.options.step_ramp * (iter_ix as f64 + 1.0)).tanh();
         for (th, dth) in self.ths.iter_mut().zip(&self.dths) {
             *th += scale * dth;
         }
@@ -397,8 +1002,7 @@ impl SplineSpec {
 
     /// Iterate towards G2 continuity by adjusting bias values.
     fn adjust_tensions(&mut self, iter_ix: usize) {
-        const MIN_BIAS: f64 = -0.9;
-        let scale = (0.25 * (iter_ix as f64 + 1.0)).tanh();
+        let scale = (self.options.step_ramp * (iter_ix as f64 + 1.0)).tanh();
         for i in 1..self.elements.len() {
             if self.elements[i].is_auto_p1()
                 && self.prev_el(i).map(Element::is_given_p2).unwrap_or(false)
@@ -407,7 +1011,7 @@ impl SplineSpec {
                 let seg = &self.segments[i - 1];
                 let this_ch = seg.chord().hypot();
                 let bias = hyperbezier::compute_k_inv(prev_seg.k1 * this_ch / (seg.hb.k0 * seg.ch));
-                let bias = bias.max(MIN_BIAS);
+                let bias = bias.max(self.options.min_match_bias);
                 let bias = seg.hb.bias0 + scale * (bias - seg.hb.bias0);
                 self.segments[i - 1].hb.bias0 = bias;
             }
@@ -418,7 +1022,7 @@ impl SplineSpec {
                 let seg = &self.segments[i - 1];
                 let this_ch = seg.chord().hypot();
                 let bias = hyperbezier::compute_k_inv(next_seg.k0 * this_ch / (seg.hb.k1 * seg.ch));
-                let bias = bias.max(MIN_BIAS);
+                let bias = bias.max(self.options.min_match_bias);
                 let bias = seg.hb.bias1 + scale * (bias - seg.hb.bias1);
                 self.segments[i - 1].hb.bias1 = bias;
             }
@@ -474,21 +1078,99 @@ impl SplineSpec {
     }
 }
 
+/// Specs are equal when they have the same elements, closedness,
+/// metadata, locks and windings; the solver state is not compared.
+impl PartialEq for SplineSpec {
+    fn eq(&self, other: &SplineSpec) -> bool {
+        self.elements == other.elements
+            && self.is_closed == other.is_closed
+            && self.metadata == other.metadata
+            && self.locks == other.locks
+            && self.windings == other.windings
+    }
+}
+
+/// Splines are equal when they have exactly the same segments and
+/// closedness, whether or not they share storage; joint tangents are not
+/// compared. Use [`Spline::approx_eq`] to allow for rounding.
+impl<'a, 'b> PartialEq<Spline<'b>> for Spline<'a> {
+    fn eq(&self, other: &Spline<'b>) -> bool {
+        self.is_closed == other.is_closed && *self.segments == *other.segments
+    }
+}
+
+impl Default for SplineSpec {
+    fn default() -> SplineSpec {
+        SplineSpec::new()
+    }
+}
+
 impl<'a> Spline<'a> {
+    /// Create a spline from already solved segments.
+    pub(crate) fn from_segments(segments: Vec<Segment>, is_closed: bool) -> Spline<'static> {
+        Spline {
+            segments: Slice::Shared(segments.into()),
+            joint_tangents: Slice::Shared(Arc::new([])),
+            is_closed,
+        }
+    }
+
     /// Return an owned version of this `Spline`, cloning its data if necessary.
+    ///
+    /// The segments of the result are reference counted, so cloning it does
+    /// not copy them.
     pub fn into_owned(self) -> Spline<'static> {
-        let segments = self.segments.into_owned();
         Spline {
-            segments: Cow::Owned(segments),
+            segments: self.segments.into_shared(),
+            joint_tangents: self.joint_tangents.into_shared(),
             is_closed: self.is_closed,
         }
     }
 
+    /// The tangent angles the solver found at the free joints: the smooth
+    /// points with auto control points on both sides, in order of element.
+    ///
+    /// The angles are in world space, in radians. They can be passed to
+    /// [`SplineSpec::set_initial_tangents`] to warm start a later solve.
+    /// Splines not made by solving a spec have none.
+    pub fn joint_tangents(&self) -> &[f64] {
+        &self.joint_tangents
+    }
+
+    /// Whether two splines have the same number of segments, each the same
+    /// within a tolerance, as by [`Segment::approx_eq`], and are both open
+    /// or both closed.
+    pub fn approx_eq(&self, other: &Spline, tolerance: f64) -> bool {
+        self.is_closed == other.is_closed
+            && self.segments.len() == other.segments.len()
+            && (self.segments.iter())
+                .zip(other.segments.iter())
+                .all(|(a, b)| a.approx_eq(b, tolerance))
+    }
+
+    /// The indices of the segments that differ from those of `prev`, such
+    /// as the spline of the previous frame, in order.
+    ///
+    /// Segments are compared exactly, so their rendered elements are
+    /// unchanged unless they are listed, and a retained scene only needs
+    /// those patched. Segments beyond the end of `prev` are listed as
+    /// changed; segments of `prev` beyond the end of this spline are gone.
+    pub fn changed_segments(&self, prev: &Spline) -> Vec<usize> {
+        (0..self.segments.len())
+            .filter(|&i| prev.segments.get(i) != Some(&self.segments[i]))
+            .collect()
+    }
+
     /// The segments of the spline.
     pub fn segments(&self) -> &[Segment] {
         &self.segments
     }
 
+    /// Whether the spline is a closed path.
+    pub fn is_closed(&self) -> bool {
+        self.is_closed
+    }
+
     /// Render the spline to a Bézier path.
     pub fn render(&self) -> BezPath {
         let mut path = BezPath::new();
@@ -498,21 +1180,75 @@ impl<'a> Spline<'a> {
 
     /// Render the spline, appending to the given path.
     pub fn render_extend(&self, path: &mut BezPath) {
-        path.move_to(self.segments[0].p0);
-        for segment in &*self.segments {
-            segment.render(path);
+        self.render_extend_with(path, &RenderOptions::default());
+    }
+
+    /// Render the spline to a Bézier path, with the given options.
+    pub fn render_with(&self, options: &RenderOptions) -> BezPath {
+        let mut path = BezPath::new();
+        self.render_extend_with(&mut path, options);
+        path
+    }
+
+    /// Iterate over the Bézier segments rendering the spline, to within
+    /// `tolerance`.
+    ///
+    /// This yields the segments lazily, without building a [`BezPath`].
+    pub fn path_segments(&self, tolerance: f64) -> impl Iterator<Item = PathSeg> + '_ {
+        self.segments
+            .iter()
+            .flat_map(move |seg| seg.path_segments(tolerance))
+    }
+
+    /// The bounding box of the rendered spline.
+    ///
+    /// As with [`BezPath`], a spline with no segments has the zero
+    /// rectangle as its bounding box.
+    pub fn bounding_box(&self) -> Rect {
+        self.render().bounding_box()
+    }
+
+    /// Render the spline with the given options, appending to the given path.
+    ///
+    /// A spline with no segments adds nothing to the path.
+    pub fn render_extend_with(&self, path: &mut BezPath, options: &RenderOptions) {
+        self.render_to_with(path, options);
+    }
+
+    /// Render the spline approximately, with one cubic Bézier per curved
+    /// segment.
+    ///
+    /// The cubics use the segments' control points directly, so they match
+    /// the end points and tangents but can be visibly off in between. This
+    /// is much cheaper than [`render`](Spline::render), and is meant for
+    /// previews of large drawings, such as while scrolling or zoomed out.
+    pub fn render_coarse(&self) -> BezPath {
+        let mut path = BezPath::new();
+        let start = match self.segments.first() {
+            Some(seg) => seg.p0,
+            None => return path,
+        };
+        path.move_to(start);
+        for seg in &*self.segments {
+            if seg.is_line() {
+                path.line_to(seg.p3);
+            } else {
+                path.curve_to(seg.p1, seg.p2, seg.p3);
+            }
         }
         if self.is_closed {
             path.close_path();
         }
+        path
     }
 }
 
 impl Element {
-    fn is_smooth(&self) -> bool {
+    pub(crate) fn is_smooth(&self) -> bool {
         match self {
             Element::LineTo(_, is_smooth) => *is_smooth,
             Element::SplineTo(_, _, _, is_smooth) => *is_smooth,
+            Element::ArcTo(_, _, is_smooth) => *is_smooth,
             _ => false,
         }
     }
@@ -522,29 +1258,58 @@ impl Element {
             Element::MoveTo(p) => *p,
             Element::LineTo(p, _) => *p,
             Element::SplineTo(_, _, p, _) => *p,
+            Element::ArcTo(p, _, _) => *p,
+        }
+    }
+
+    pub(crate) fn set_smooth(&mut self, smooth: bool) {
+        match self {
+            Element::LineTo(_, is_smooth)
+            | Element::SplineTo(_, _, _, is_smooth)
+            | Element::ArcTo(_, _, is_smooth) => *is_smooth = smooth,
+            Element::MoveTo(_) => (),
+        }
+    }
+
+    pub(crate) fn set_endpoint(&mut self, p: Point) {
+        match self {
+            Element::MoveTo(q)
+            | Element::LineTo(q, _)
+            | Element::SplineTo(_, _, q, _)
+            | Element::ArcTo(q, _, _) => *q = p,
         }
     }
 
-    fn is_auto_p1(&self) -> bool {
+    pub(crate) fn is_auto_p1(&self) -> bool {
         matches!(self, Element::SplineTo(None, _, _, _))
     }
 
-    fn is_auto_p2(&self) -> bool {
+    pub(crate) fn is_auto_p2(&self) -> bool {
         matches!(self, Element::SplineTo(_, None, _, _))
     }
 
+    /// Whether the tangent and tension at the start are fixed.
+    ///
+    /// This is true for explicit control points and for arcs.
     fn is_given_p1(&self) -> bool {
-        matches!(self, Element::SplineTo(Some(_), _, _, _))
+        matches!(
+            self,
+            Element::SplineTo(Some(_), _, _, _) | Element::ArcTo(..)
+        )
     }
 
+    /// Whether the tangent and tension at the end are fixed.
     fn is_given_p2(&self) -> bool {
-        matches!(self, Element::SplineTo(_, Some(_), _, _))
+        matches!(
+            self,
+            Element::SplineTo(_, Some(_), _, _) | Element::ArcTo(..)
+        )
     }
 }
 
 impl Segment {
     /// Create a segment from a hyperbezier.
-    fn make(
+    pub(crate) fn make(
         p0: Point,
         p1: Option<Point>,
         p2: Option<Point>,
@@ -579,7 +1344,29 @@ impl Segment {
         }
     }
 
-    fn line(p0: Point, p3: Point) -> Segment {
+    /// Create a segment from `p0` to `p3` with the given tangent angles and
+    /// bias values.
+    ///
+    /// The angles are measured from the chord to the curve, as returned by
+    /// [`HyperBezier::compute`], so the curve is convex when they have the
+    /// same sign. The result is exact up to the accuracy of
+    /// [`HyperBezier::solve_for_theta`], and doesn't depend on any spec.
+    pub fn solve_theta(p0: Point, p3: Point, params: &ThetaParams) -> Segment {
+        let hb = HyperBezier::solve_for_theta(params);
+        Segment::make(p0, None, None, p3, -params.th0, -params.th1, hb)
+    }
+
+    /// Create a circular arc segment.
+    ///
+    /// When both bias values are 1, the curvature of the hyperbezier is
+    /// `k0 + k1` everywhere, so splitting the sweep evenly gives a circle.
+    fn arc(p0: Point, p3: Point, sweep: f64) -> Segment {
+        let k = 0.5 * sweep;
+        let hb = HyperBezier::new_clamped(k, 1.0, k, 1.0);
+        Segment::make(p0, None, None, p3, -k, -k, hb)
+    }
+
+    pub(crate) fn line(p0: Point, p3: Point) -> Segment {
         Segment {
             p0,
             p1: p0,
@@ -607,6 +1394,181 @@ impl Segment {
         self.p3 - self.p0
     }
 
+    /// Whether two segments are the same within a tolerance.
+    ///
+    /// The points, the tangent angles relative to the chord, and the
+    /// curvatures at the ends are each compared with the tolerance, in
+    /// their own units. This is meant for tests, where exact comparison is
+    /// too fragile.
+    pub fn approx_eq(&self, other: &Segment, tolerance: f64) -> bool {
+        let points = [
+            (self.p0, other.p0),
+            (self.p1, other.p1),
+            (self.p2, other.p2),
+            (self.p3, other.p3),
+        ];
+        let scalars = [
+            (self.th0, other.th0),
+            (self.th1, other.th1),
+            (self.k0, other.k0),
+            (self.k1, other.k1),
+        ];
+        points.iter().all(|(a, b)| a.distance(*b) <= tolerance)
+            && scalars.iter().all(|(a, b)| (a - b).abs() <= tolerance)
+    }
+
+    /// The same curve, traversed from `p3` to `p0`.
+    ///
+    /// The ends swap, along with their tangent angles and biases. Tangent
+    /// angles relative to the chord and curvatures change sign, as the
+    /// curve turns the other way when traversed backwards.
+    pub fn reverse(&self) -> Segment {
+        Segment {
+            p0: self.p3,
+            p1: self.p2,
+            p2: self.p1,
+            p3: self.p0,
+            th0: -self.th1,
+            th1: -self.th0,
+            k0: -self.k1,
+            k1: -self.k0,
+            hb: HyperBezier {
+                k0: -self.hb.k1,
+                bias0: self.hb.bias1,
+                k1: -self.hb.k0,
+                bias1: self.hb.bias0,
+            },
+            ch: self.ch,
+        }
+    }
+
+    /// The transform from the hyperbezier's own coordinates to world space.
+    pub(crate) fn hb_affine(&self) -> Affine {
+        let p = self.p0;
+        let d = self.p3 - p;
+        let a = Affine::new([d.x, d.y, -d.y, d.x, p.x, p.y]);
+        let v = self.hb.integrate(0.0, 1.0, 24);
+        a * Affine::new([v.x, v.y, -v.y, v.x, 0.0, 0.0]).inverse()
+    }
+
+    /// The point at arclength parameter `t`, from 0 to 1.
+    ///
+    /// This is on the curve itself, not on its rendered approximation.
+    pub fn eval(&self, t: f64) -> Point {
+        self.hb_affine() * self.hb.integrate(0.0, t, 24).to_point()
+    }
+
+    /// The unit tangent at arclength parameter `t`, in the direction of
+    /// the curve.
+    pub fn tangent(&self, t: f64) -> Vec2 {
+        Vec2::from_angle(self.tangent_angle(t))
+    }
+
+    /// The world space tangent angle at arclength parameter `t`.
+    pub(crate) fn tangent_angle(&self, t: f64) -> f64 {
+        let v = self.hb.integrate(0.0, 1.0, 24);
+        self.chord().atan2() - v.atan2() + self.hb.compute_theta(t)
+    }
+
+    /// The world space curvature at arclength parameter `t`.
+    ///
+    /// Curvature is positive where the curve turns towards the left of its
+    /// direction in a y-up coordinate system, as [`normal_line`] points.
+    ///
+    /// [`normal_line`]: Segment::normal_line
+    pub fn curvature(&self, t: f64) -> f64 {
+        const EPSILON: f64 = 1e-6;
+        let t0 = (t - EPSILON).max(0.0);
+        let t1 = (t + EPSILON).min(1.0);
+        let dth = self.hb.compute_theta(t1) - self.hb.compute_theta(t0);
+        dth / ((t1 - t0) * self.arclen())
+    }
+
+    /// A line of length `len` from the point at arclength parameter `t`,
+    /// along the direction of the curve.
+    ///
+    /// A negative `len` gives a line pointing backwards.
+    pub fn tangent_line(&self, t: f64, len: f64) -> Line {
+        let p = self.eval(t);
+        Line::new(p, p + len * self.tangent(t))
+    }
+
+    /// A line of length `len` from the point at arclength parameter `t`,
+    /// perpendicular to the curve.
+    ///
+    /// The line points to the left of the direction of the curve in a y-up
+    /// coordinate system, which is the side positive curvature bends
+    /// towards.
+    pub fn normal_line(&self, t: f64, len: f64) -> Line {
+        let p = self.eval(t);
+        Line::new(
+            p,
+            p + len * Vec2::from_angle(self.tangent_angle(t) + FRAC_PI_2),
+        )
+    }
+
+    /// The total arclength of the segment.
+    ///
+    /// Hyperbeziers are parameterized by arclength, so this comes from the
+    /// curve itself rather than from a rendered approximation, and there is
+    /// no accuracy to choose.
+    pub fn arclen(&self) -> f64 {
+        self.chord().hypot() / self.ch
+    }
+
+    /// The arclength parameter at arclength `len` from the start of the
+    /// segment.
+    ///
+    /// The parameter is proportional to arclength, so points at equal
+    /// spacing along the curve are at equal steps of it. Lengths outside
+    /// the segment are clamped to its ends.
+    pub fn inv_arclen(&self, len: f64) -> f64 {
+        let arclen = self.arclen();
+        if arclen > 0.0 {
+            (len / arclen).clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Sample the segment so that straight lines between the samples are
+    /// within `tolerance` of the curve.
+    ///
+    /// Returns pairs of arclength parameter and point.
+    pub(crate) fn flatten_params(&self, tolerance: f64) -> Vec<(f64, Point)> {
+        if self.is_line() {
+            return vec![(0.0, self.p0), (1.0, self.p3)];
+        }
+        let arclen = self.arclen();
+        let mut n = 8;
+        // The deviation of a chord from an arc is about l * dth / 8.
+        while n < 4096 {
+            let step = 1.0 / n as f64;
+            let max_dth = (0..n)
+                .map(|i| {
+                    let th0 = self.hb.compute_theta(i as f64 * step);
+                    let th1 = self.hb.compute_theta((i + 1) as f64 * step);
+                    (th1 - th0).abs()
+                })
+                .fold(0.0, f64::max);
+            if arclen * step * max_dth <= 8.0 * tolerance {
+                break;
+            }
+            n *= 2;
+        }
+        let a = self.hb_affine();
+        let step = 1.0 / n as f64;
+        let mut p = Vec2::ZERO;
+        let mut result = vec![(0.0, self.p0)];
+        for i in 1..=n {
+            let t0 = (i - 1) as f64 * step;
+            let t1 = i as f64 * step;
+            p += self.hb.integrate(t0, t1, 11);
+            result.push((t1, a * p.to_point()));
+        }
+        result
+    }
+
     /// Render the segment to the bezier path.
     ///
     /// This does not include the initial moveto, so the caller needs to
@@ -616,20 +1578,50 @@ impl Segment {
     }
 
     /// Returns an iterator over the bezier elements that render this segment.
-    pub fn render_elements<'a>(&'_ self) -> impl Iterator<Item = PathEl> + '_ {
+    pub fn render_elements(&self) -> impl Iterator<Item = PathEl> + '_ {
+        self.render_elements_with(&RenderOptions::default())
+    }
+
+    /// Returns an iterator over the bezier elements that render this segment,
+    /// with the given options.
+    ///
+    /// With a tolerance and no count, curves are rendered adaptively, giving
+    /// the same cubic Béziers as [`Segment::path_segments`], unless the
+    /// topology is to be kept stable.
+    pub fn render_elements_with<'a>(
+        &'a self,
+        options: &RenderOptions,
+    ) -> impl Iterator<Item = PathEl> + 'a {
         // we need to do some gymnastics to enesure we return the same concrete type in
-        // both cases:
-        let (line_part, spline_part) = if self.is_line() {
-            (Some(PathEl::LineTo(self.p3)), None)
+        // all cases:
+        let (line_part, refined_part, spline_part) = if self.is_line() && !options.stable_topology {
+            (Some(PathEl::LineTo(self.p3)), None, None)
+        } else if let (Some(tolerance), None, false) = (
+            options.tolerance,
+            options.subdivisions,
+            options.stable_topology,
+        ) {
+            let cubics = self.refine_iter(0.0..1.0, tolerance);
+            (
+                None,
+                Some(cubics.map(|(c, _)| PathEl::CurveTo(c.p1, c.p2, c.p3))),
+                None,
+            )
         } else {
             let p = self.p0;
             let d = self.p3 - p;
             let a = Affine::new([d.x, d.y, -d.y, d.x, p.x, p.y]);
+            // The hyperbezier is rendered with a chord of unit length.
+            let options = RenderOptions {
+                tolerance: options.tolerance.map(|tolerance| tolerance / d.hypot()),
+                ..*options
+            };
             (
+                None,
                 None,
                 Some(
                     self.hb
-                        .render_elements(self.hb.render_subdivisions())
+                        .render_elements_with(&options)
                         .skip(1)
                         .map(move |el| a * el),
                 ),
@@ -638,8 +1630,66 @@ impl Segment {
 
         line_part
             .into_iter()
+            .chain(refined_part.into_iter().flatten())
             .chain(spline_part.into_iter().flatten())
     }
+
+    /// Iterate over the Bézier segments rendering this segment, to within
+    /// `tolerance`.
+    ///
+    /// Straight segments yield a single line, and curves yield the cubic
+    /// Béziers of [`Segment::refine`].
+    pub fn path_segments(&self, tolerance: f64) -> impl Iterator<Item = PathSeg> + '_ {
+        let (line, curves) = if self.is_line() {
+            (Some(PathSeg::Line(Line::new(self.p0, self.p3))), None)
+        } else {
+            (None, Some(self.refine_iter(0.0..1.0, tolerance)))
+        };
+        line.into_iter().chain(
+            curves
+                .into_iter()
+                .flatten()
+                .map(|(cubic, _)| PathSeg::Cubic(cubic)),
+        )
+    }
+}
+
+/// Control points for a segment with the given end points, world space
+/// tangent angles and curvatures.
+///
+/// The tangents are matched by construction, and the bias at each end is
+/// refined so the curvature matches.
+pub(crate) fn fit_handles(start: (Point, f64, f64), end: (Point, f64, f64)) -> (Point, Point) {
+    let (p0, th0, k0) = start;
+    let (p3, th3, k3) = end;
+    let v = p3 - p0;
+    let chord_th = v.atan2();
+    let a = Affine::new([v.x, v.y, -v.y, v.x, p0.x, p0.y]);
+    let th0 = util::mod_tau(th0 - chord_th);
+    let th1 = util::mod_tau(chord_th - th3);
+    let mut bias0 = 1.0;
+    let mut bias1 = 1.0;
+    for _ in 0..4 {
+        let params = ThetaParams {
+            th0: -th0,
+            bias0,
+            th1: -th1,
+            bias1,
+        };
+        let hb = HyperBezier::solve_for_theta(&params);
+        let arclen = v.hypot() / hb.compute().chord;
+        if hb.k0 != 0.0 {
+            bias0 =
+                hyperbezier::compute_k_inv(k0 * arclen / hb.k0).max(hyperbezier::MIN_MATCH_BIAS);
+        }
+        if hb.k1 != 0.0 {
+            bias1 =
+                hyperbezier::compute_k_inv(k3 * arclen / hb.k1).max(hyperbezier::MIN_MATCH_BIAS);
+        }
+    }
+    let p1 = a * HyperBezier::v_for_params(th0, bias0).to_point();
+    let p2 = a * (Point::new(1.0, 0.0) - HyperBezier::v_for_params(-th1, bias1));
+    (p1, p2)
 }
 
 #[cfg(test)]
@@ -652,6 +1702,204 @@ mod tests {
         spec.solve();
     }
 
+    fn degenerate_specs() -> Vec<SplineSpec> {
+        let mut single = SplineSpec::new();
+        single.move_to(Point::new(1., 2.));
+        let mut closed = single.clone();
+        closed.close();
+        vec![SplineSpec::new(), single, closed]
+    }
+
+    #[test]
+    fn degenerate_specs_are_empty() {
+        for mut spec in degenerate_specs() {
+            let spline = spec.solve();
+            assert!(spline.segments().is_empty());
+            assert!(spline.render().elements().is_empty());
+            assert_eq!(spline.path_segments(0.1).count(), 0);
+            assert_eq!(spline.bounding_box(), Rect::ZERO);
+            assert_eq!(spline.iter_joints().count(), 0);
+            assert!(spline.pick(Point::new(1., 2.), 10.0).is_none());
+            // Rendering onto a path leaves it unchanged.
+            let mut path = BezPath::new();
+            path.move_to(Point::new(5., 5.));
+            spline.render_extend(&mut path);
+            assert_eq!(path.elements().len(), 1);
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn degenerate_specs_round_trip() {
+        for spec in degenerate_specs() {
+            let json = serde_json::to_string(&spec).unwrap();
+            let mut spec2: SplineSpec = serde_json::from_str(&json).unwrap();
+            assert_eq!(spec2, spec);
+            assert!(spec2.solve().segments().is_empty());
+        }
+    }
+
+    #[test]
+    fn solve_stats() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), true);
+        spec.spline_to(None, None, Point::new(250., 100.), false);
+        assert!(spec.solve_stats().is_none());
+        spec.solve();
+        let stats = *spec.solve_stats().unwrap();
+        assert_eq!(stats.iterations, N_ITERATIONS);
+        assert!(stats.residual < 1e-3, "{}", stats.residual);
+        assert!(stats.elapsed.is_some());
+        spec.line_to(Point::new(300., 0.), false);
+        assert!(spec.solve_stats().is_none());
+    }
+
+    #[test]
+    fn solve_options() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(
+            Some(Point::new(150., 0.)),
+            None,
+            Point::new(200., 0.),
+            false,
+        );
+        let full = spec.solve().into_owned();
+        spec.set_solve_options(SolveOptions {
+            iterations: 4,
+            tension_passes: 0,
+            ..SolveOptions::default()
+        });
+        assert!(spec.solve_stats().is_none());
+        let pure = spec.solve().into_owned();
+        assert_eq!(spec.solve_stats().unwrap().iterations, 4);
+        // Without the tension pass, the auto side keeps its initial bias.
+        assert_eq!(pure.segments()[0].hb.bias1, 1.0);
+        assert_ne!(full.segments()[0].hb.bias1, 1.0);
+        // Full steps still converge on an easy spec, and the tension pass
+        // stays above the given bias.
+        spec.set_solve_options(SolveOptions {
+            step_ramp: f64::INFINITY,
+            min_match_bias: 1.2,
+            ..SolveOptions::default()
+        });
+        let spline = spec.solve().into_owned();
+        assert!(spec.solve_stats().unwrap().residual < 1e-6);
+        assert!(full.segments()[0].hb.bias1 < 1.2);
+        assert!(spline.segments()[0].hb.bias1 >= 1.2);
+    }
+
+    #[test]
+    fn approx_eq() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), false);
+        let spline = spec.solve().into_owned();
+        assert_eq!(spline, spec.solve());
+        spec.elements_mut()[1] = Element::SplineTo(None, None, Point::new(100., 50.001), true);
+        let moved = spec.solve();
+        assert_ne!(spline, moved);
+        assert!(spline.approx_eq(&moved, 1e-2));
+        assert!(!spline.approx_eq(&moved, 1e-6));
+        spec.close();
+        assert!(!spline.approx_eq(&spec.solve(), 1e3));
+    }
+
+    #[test]
+    fn solve_theta() {
+        let (p0, p3) = (Point::new(10., 20.), Point::new(110., 20.));
+        let sweep = 1.0;
+        let params = ThetaParams {
+            th0: 0.5 * sweep,
+            bias0: 1.0,
+            th1: 0.5 * sweep,
+            bias1: 1.0,
+        };
+        let seg = Segment::solve_theta(p0, p3, &params);
+        assert!(seg.approx_eq(&Segment::arc(p0, p3, sweep), 1e-6));
+        let r = seg.hb.compute();
+        assert!((r.th0 - params.th0).abs() < 1e-6 && (r.th1 - params.th1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn warm_start() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), true);
+        spec.spline_to(None, None, Point::new(250., 100.), false);
+        let solved = spec.solve().into_owned();
+        assert_eq!(solved.joint_tangents().len(), 2);
+        // One iteration from the solution stays there, but doesn't get
+        // there from the usual guess.
+        spec.set_solve_options(SolveOptions {
+            iterations: 1,
+            ..SolveOptions::default()
+        });
+        let cold = spec.solve().into_owned();
+        assert!(!cold.approx_eq(&solved, 1e-3));
+        spec.set_initial_tangents(solved.joint_tangents());
+        assert!(spec.solve().approx_eq(&solved, 1e-3));
+    }
+
+    #[test]
+    fn mixed_chord_lengths() {
+        // Points on a circle, with two of them only 0.1 apart: a chord
+        // ratio of more than 1000:1.
+        let pt =
+            |deg: f64| Point::new(100. * deg.to_radians().cos(), 100. * deg.to_radians().sin());
+        let mut spec = SplineSpec::new();
+        spec.move_to(pt(0.));
+        for &deg in &[90., 90. + 0.1f64.to_degrees() / 100., 180., 270., 360.] {
+            spec.spline_to(None, None, pt(deg), true);
+        }
+        spec.close();
+        spec.solve();
+        assert!(spec.solve_stats().unwrap().residual < 1e-6);
+        let spline = spec.solve();
+        for seg in spline.segments() {
+            for i in 0..=8 {
+                let r = seg.eval(i as f64 / 8.).to_vec2().hypot();
+                assert!((r - 100.).abs() < 1.0, "{}", r);
+            }
+        }
+    }
+
+    #[test]
+    fn unchanged_segments_are_not_solved() {
+        // The auto segment between two lines has nothing to solve for, so
+        // it is only solved once.
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), true);
+        spec.spline_to(None, None, Point::new(200., 100.), true);
+        spec.line_to(Point::new(200., 200.), false);
+        spec.solve();
+        assert_eq!(spec.solve_stats().unwrap().segment_solves, 1);
+        // With a free joint, the segments next to it are solved each
+        // iteration.
+        spec.elements_mut()[3] = Element::SplineTo(None, None, Point::new(200., 200.), false);
+        spec.solve();
+        let stats = spec.solve_stats().unwrap();
+        assert!(stats.segment_solves > stats.iterations);
+    }
+
+    #[test]
+    fn owned_spline_is_shared() {
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        let mut spec = SplineSpec::circle(Point::ZERO, 10.0);
+        let spline = spec.solve().into_owned();
+        assert_send_sync(&spline);
+        let copy = spline.clone();
+        assert!(std::ptr::eq(spline.segments(), copy.segments()));
+        let handle = std::thread::spawn(move || copy.render());
+        assert_eq!(handle.join().unwrap(), spline.render());
+    }
+
     #[test]
     fn render_elements_count() {
         let mut spec = SplineSpec::new();
@@ -662,4 +1910,603 @@ mod tests {
         let elements_count = spline.segments().first().unwrap().render_elements().count();
         assert!(elements_count < 64);
     }
+
+    #[test]
+    fn arcs_make_circle() {
+        use kurbo::ParamCurve;
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.arc_to(Point::new(200., 0.), std::f64::consts::PI, true);
+        spec.arc_to(Point::new(0., 0.), std::f64::consts::PI, true);
+        spec.close();
+        let spline = spec.solve();
+        assert!((spline.segments()[0].k0 - 0.01).abs() < 1e-9);
+        let center = Point::new(100., 0.);
+        let path = spline.render();
+        for seg in path.segments() {
+            for i in 0..=8 {
+                let r = (seg.eval(i as f64 / 8.0) - center).hypot();
+                assert!((r - 100.0).abs() < 0.05, "radius {}", r);
+            }
+        }
+        // Positive sweep turns counterclockwise, so the first half is below.
+        assert!(path.segments().next().unwrap().eval(1.0).y < 0.0);
+    }
+
+    #[test]
+    fn path_segments_tolerance() {
+        use kurbo::ParamCurve;
+        let mut spec = SplineSpec::circle(Point::ZERO, 100.0);
+        let spline = spec.solve();
+        let mut last_count = 0;
+        for &tolerance in &[1.0, 0.01, 1e-6] {
+            let segs: Vec<_> = spline.path_segments(tolerance).collect();
+            assert!(segs.len() > last_count);
+            last_count = segs.len();
+            for seg in &segs {
+                for i in 0..=8 {
+                    let r = seg.eval(i as f64 / 8.0).to_vec2().hypot();
+                    assert!((r - 100.0).abs() <= tolerance, "{}: {}", tolerance, r);
+                }
+            }
+            assert_eq!(segs[0].start(), Point::new(100.0, 0.0));
+        }
+    }
+
+    #[test]
+    fn render_tolerance_matches_path_segments() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 60.), true);
+        spec.line_to(Point::new(200., 60.), false);
+        spec.spline_to(
+            Some(Point::new(300., 60.)),
+            None,
+            Point::new(220., -40.),
+            false,
+        );
+        let spline = spec.solve();
+        for &tolerance in &[1.0, 1e-3] {
+            let options = RenderOptions {
+                tolerance: Some(tolerance),
+                ..RenderOptions::default()
+            };
+            let rendered: Vec<_> = spline.render_with(&options).segments().collect();
+            let segs: Vec<_> = spline.path_segments(tolerance).collect();
+            assert_eq!(rendered, segs);
+        }
+    }
+
+    #[test]
+    fn arclen() {
+        use kurbo::ParamCurveArclen;
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 60.), true);
+        spec.spline_to(None, None, Point::new(200., -40.), false);
+        let spline = spec.solve();
+        for seg in spline.segments() {
+            let rendered: f64 = seg.path_segments(1e-6).map(|s| s.arclen(1e-9)).sum();
+            assert!((seg.arclen() - rendered).abs() < 1e-4, "{}", rendered);
+        }
+        // Points at equal spacing around a circle are equally far apart.
+        let mut spec = SplineSpec::circle(Point::ZERO, 100.0);
+        let seg = spec.solve().segments()[0].clone();
+        let step = seg.arclen() / 8.0;
+        let pts: Vec<Point> = (0..=8)
+            .map(|i| seg.eval(seg.inv_arclen(i as f64 * step)))
+            .collect();
+        let chord = 200.0 * (step / 200.0).sin();
+        for w in pts.windows(2) {
+            assert!((w[0].distance(w[1]) - chord).abs() < 1e-6);
+        }
+        assert_eq!(seg.inv_arclen(-1.0), 0.0);
+        assert_eq!(seg.inv_arclen(2.0 * seg.arclen()), 1.0);
+    }
+
+    #[test]
+    fn eval_tangent_curvature() {
+        let mut spec = SplineSpec::circle(Point::ZERO, 100.0);
+        let spline = spec.solve();
+        for seg in spline.segments() {
+            for i in 0..=4 {
+                let t = i as f64 / 4.0;
+                let p = seg.eval(t);
+                assert!((p.to_vec2().hypot() - 100.0).abs() < 1e-6);
+                let d = seg.tangent(t);
+                assert!((d.hypot() - 1.0).abs() < 1e-12);
+                assert!(d.dot(p.to_vec2()).abs() < 1e-6);
+                // The normal points to the center on the side the curve
+                // bends towards.
+                let k = seg.curvature(t);
+                assert!((k.abs() - 0.01).abs() < 1e-6, "{}", k);
+                let normal = seg.normal_line(t, 100.0 * k.signum());
+                assert!(normal.p1.distance(Point::ZERO) < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn render_coarse() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), true);
+        spec.spline_to(None, None, Point::new(150., 80.), true);
+        spec.spline_to(None, None, Point::new(0., 100.), false);
+        spec.close();
+        let spline = spec.solve();
+        let coarse = spline.render_coarse();
+        // Move, one element per segment, and close.
+        assert_eq!(coarse.elements().len(), 2 + spline.segments().len());
+        assert_eq!(coarse.elements()[1], PathEl::LineTo(Point::new(100., 0.)));
+        let fine = spline.render().bounding_box();
+        let rough = coarse.bounding_box();
+        // Close, but not exact.
+        let size = fine.width();
+        assert!((fine.x1 - rough.x1).abs() < 0.05 * size);
+        assert!((fine.y1 - rough.y1).abs() < 0.05 * size);
+    }
+
+    #[test]
+    fn render_options() {
+        use crate::hyperbezier::Parameterization;
+        use kurbo::ParamCurve;
+        let mut spec = SplineSpec::circle(Point::ZERO, 100.0);
+        let spline = spec.solve();
+        for &parameterization in &[
+            Parameterization::FlatSpeed,
+            Parameterization::EndpointSpeed,
+            Parameterization::CurvatureWeighted,
+        ] {
+            let options = RenderOptions {
+                subdivisions: Some(3),
+                parameterization,
+                ..RenderOptions::default()
+            };
+            let path = spline.render_with(&options);
+            let curves = path.elements().iter();
+            assert_eq!(
+                curves
+                    .filter(|el| matches!(el, PathEl::CurveTo(..)))
+                    .count(),
+                12
+            );
+            for seg in path.segments() {
+                let r = seg.eval(0.5).to_vec2().hypot();
+                assert!((r - 100.0).abs() < 0.05, "{:?}: {}", parameterization, r);
+            }
+        }
+        let options = RenderOptions {
+            max_subdivisions: 1,
+            ..RenderOptions::default()
+        };
+        let path = spline.render_with(&options);
+        let curves = path.elements().iter();
+        assert_eq!(
+            curves
+                .filter(|el| matches!(el, PathEl::CurveTo(..)))
+                .count(),
+            4
+        );
+        // A tolerance picks enough cubics to meet it, and no more.
+        let count = |tolerance| {
+            let options = RenderOptions {
+                tolerance: Some(tolerance),
+                ..RenderOptions::default()
+            };
+            let path = spline.render_with(&options);
+            for seg in path.segments() {
+                let r = seg.eval(0.5).to_vec2().hypot();
+                assert!((r - 100.0).abs() <= tolerance, "{}: {}", tolerance, r);
+            }
+            path.segments().count()
+        };
+        assert!(count(1.0) < count(1e-4));
+    }
+
+    #[test]
+    fn spirals() {
+        // Total turning of the solved spline, in turns.
+        let turning = |spline: &Spline| {
+            let mut total = 0.0;
+            let mut prev = None;
+            for seg in spline.segments() {
+                for i in 0..=16 {
+                    let th = seg.tangent_angle(i as f64 / 16.0);
+                    if let Some(prev) = prev {
+                        total += util::mod_tau(th - prev);
+                    }
+                    prev = Some(th);
+                }
+            }
+            total / std::f64::consts::TAU
+        };
+        let spiral = |per_turn: usize, growth: f64, options: SolveOptions| {
+            let mut spec = SplineSpec::new();
+            let n = 3 * per_turn;
+            for i in 0..=n {
+                let th = std::f64::consts::TAU * i as f64 / per_turn as f64;
+                let r = growth.powf(i as f64 / per_turn as f64);
+                let p = (r * Vec2::from_angle(th)).to_point();
+                if i == 0 {
+                    spec.move_to(p);
+                } else {
+                    spec.spline_to(None, None, p, i < n);
+                }
+            }
+            spec.set_solve_options(options);
+            let spline = spec.solve().into_owned();
+            (spline, spec.solve_stats().unwrap().residual)
+        };
+        // Three turns, minus a little at the free ends.
+        for &per_turn in &[3, 4, 6, 8] {
+            let (spline, residual) = spiral(per_turn, 3.0, SolveOptions::default());
+            let turns = turning(&spline);
+            assert!(turns > 2.75 && turns < 3.05, "{}: {}", per_turn, turns);
+            assert!(residual < 1e-2, "{}: {}", per_turn, residual);
+        }
+        // With chords growing tenfold at each point, the limit holds the
+        // guess back, but the shape is the same either way.
+        let (limited, _) = spiral(3, 1000.0, SolveOptions::default());
+        let unlimited = SolveOptions {
+            max_chord_angle: std::f64::consts::PI,
+            ..SolveOptions::default()
+        };
+        let (free, _) = spiral(3, 1000.0, unlimited);
+        assert!((turning(&limited) - turning(&free)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn continuation() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), true);
+        spec.spline_to(None, None, Point::new(300., 50.), false);
+        spec.set_solve_options(SolveOptions {
+            continuation: Some(0.05),
+            ..SolveOptions::default()
+        });
+        let before = spec.solve().joint_tangents().to_vec();
+        spec.move_points(&[(2, Point::new(200., -100.))]);
+        let after = spec.solve().joint_tangents().to_vec();
+        for (th0, th1) in before.iter().zip(&after) {
+            assert!((th1 - th0).abs() <= 0.05 + 1e-12, "{} {}", th0, th1);
+        }
+        // Solving again settles on the usual solution.
+        let mut cold = spec.clone();
+        cold.set_solve_options(SolveOptions::default());
+        let expected = cold.solve().into_owned();
+        for _ in 0..20 {
+            spec.elements_mut();
+            spec.solve();
+        }
+        assert!(spec.solve().approx_eq(&expected, 1e-3));
+    }
+
+    #[test]
+    fn solve_budgeted() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        for i in 1..20 {
+            let y = if i % 2 == 0 { 0. } else { 40. + i as f64 };
+            spec.spline_to(None, None, Point::new(50. * i as f64, y), true);
+        }
+        let expected = spec.clone().solve().into_owned();
+        let n = spec.solve_options().iterations;
+        // With no time to spare, each call runs one iteration.
+        for _ in 1..n {
+            let (_, is_complete) = spec.solve_budgeted(Duration::ZERO);
+            assert!(!is_complete);
+            assert!(spec.solve_stats().is_none());
+        }
+        let (spline, is_complete) = spec.solve_budgeted(Duration::ZERO);
+        assert!(is_complete);
+        assert_eq!(spline, expected);
+        assert_eq!(spec.solve_stats().unwrap().iterations, n);
+    }
+
+    #[test]
+    fn solve_budgeted_restarts_on_winding() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        for i in 1..6 {
+            spec.spline_to(None, None, Point::new(50. * i as f64, 0.), true);
+        }
+        let n = spec.solve_options().iterations;
+        for _ in 0..n / 2 {
+            spec.solve_budgeted(Duration::ZERO);
+        }
+        spec.set_winding(3, 1);
+        // The solve so far was for other inputs, so all the iterations are
+        // run again.
+        for _ in 1..n {
+            let (_, is_complete) = spec.solve_budgeted(Duration::ZERO);
+            assert!(!is_complete);
+        }
+        let (_, is_complete) = spec.solve_budgeted(Duration::ZERO);
+        assert!(is_complete);
+    }
+
+    #[test]
+    fn tangent_and_normal_lines() {
+        let mut spec = SplineSpec::circle(Point::ZERO, 100.0);
+        let spline = spec.solve();
+        for seg in spline.segments() {
+            for i in 0..=4 {
+                let t = i as f64 / 4.0;
+                let tangent = seg.tangent_line(t, 10.0);
+                let radius = tangent.p0.to_vec2();
+                assert!((tangent.length() - 10.0).abs() < 1e-9);
+                assert!(radius.dot(tangent.p1 - tangent.p0).abs() < 1e-2);
+                // The normal towards positive curvature reaches the center.
+                let k = seg.curvature(t);
+                let normal = seg.normal_line(t, 100.0 * k.signum());
+                assert!(normal.p1.to_vec2().hypot() < 1e-2, "{:?}", normal);
+            }
+        }
+    }
+
+    #[test]
+    fn free_joint_matrix() {
+        let (a, b) = (Point::new(0., 0.), Point::new(100., 50.));
+        // Explicit control points are collinear through the points, so
+        // every smooth joint can be smooth whichever side is explicit.
+        let (d, e) = (Vec2::new(30., -10.), Vec2::new(0., 40.));
+        for bits in 0..64 {
+            let given = |i: usize| bits & (1 << i) != 0;
+            let (is_smooth, is_closed) = (given(4), given(5));
+            let mut spec = SplineSpec::new();
+            spec.move_to(a);
+            spec.spline_to(
+                Some(a + e).filter(|_| given(0)),
+                Some(b - d).filter(|_| given(1)),
+                b,
+                is_smooth,
+            );
+            spec.spline_to(
+                Some(b + d).filter(|_| given(2)),
+                Some(a - e).filter(|_| given(3)),
+                a,
+                true,
+            );
+            let mut free = (is_smooth && !given(1) && !given(2)) as usize;
+            let mut joints = vec![(0, 1)];
+            if is_closed {
+                spec.close();
+                free += (!given(3) && !given(0)) as usize;
+                joints.push((1, 0));
+            }
+            let spline = spec.solve();
+            assert_eq!(spline.joint_tangents().len(), free, "{:06b}", bits);
+            let segs = spline.segments();
+            assert!(segs
+                .iter()
+                .all(|seg| seg.p1.is_finite() && seg.p2.is_finite()));
+            for &(i, j) in &joints[(!is_smooth) as usize..] {
+                let turn = segs[j].tangent_angle(0.0) - segs[i].tangent_angle(1.0);
+                assert!(util::mod_tau(turn).abs() < 1e-3, "{:06b}: {}", bits, turn);
+            }
+        }
+    }
+
+    #[test]
+    fn mirror_symmetry() {
+        use rand::{Rng, SeedableRng};
+        let mirror = |p: Point| Point::new(-p.x, p.y);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(2);
+        for _ in 0..50 {
+            // Bowls like those of glyphs, around a circle.
+            let pts: Vec<Point> = (0..4)
+                .map(|i| {
+                    let th = FRAC_PI_2 * i as f64 + rng.gen_range(-0.3, 0.3);
+                    Point::ORIGIN + rng.gen_range(40.0, 60.0) * Vec2::from_angle(th)
+                })
+                .collect();
+            let mut jitter = || Vec2::new(rng.gen_range(-5.0, 5.0), rng.gen_range(-5.0, 5.0));
+            let mut spec = SplineSpec::new();
+            spec.move_to(pts[0]);
+            for i in 0..4 {
+                let (p0, p3) = (pts[i], pts[(i + 1) % 4]);
+                let p1 = p0.lerp(p3, 1.0 / 3.0) + jitter();
+                let p2 = p0.lerp(p3, 2.0 / 3.0) + jitter();
+                // Cover the one-sided cases, where the auto side matches the
+                // curvature of the explicit side by adjusting the bias.
+                match i {
+                    0 => spec.spline_to(None, None, p3, true),
+                    1 => spec.spline_to(Some(p1), None, p3, true),
+                    2 => spec.spline_to(None, Some(p2), p3, true),
+                    _ => spec.spline_to(Some(p1), Some(p2), p3, true),
+                }
+            }
+            spec.close();
+            let mut mirrored = SplineSpec::new();
+            mirrored.elements = spec
+                .elements()
+                .iter()
+                .map(|el| match *el {
+                    Element::MoveTo(p) => Element::MoveTo(mirror(p)),
+                    Element::SplineTo(p1, p2, p3, smooth) => {
+                        Element::SplineTo(p1.map(mirror), p2.map(mirror), mirror(p3), smooth)
+                    }
+                    _ => unreachable!(),
+                })
+                .collect();
+            mirrored.close();
+            let spline = spec.solve().into_owned();
+            for (seg, m) in spline.segments().iter().zip(mirrored.solve().segments()) {
+                let d = mirror(seg.p1)
+                    .distance(m.p1)
+                    .max(mirror(seg.p2).distance(m.p2));
+                assert!(d < 1e-6, "{}", d);
+            }
+        }
+    }
+
+    #[test]
+    fn reverse_segment() {
+        use rand::{Rng, SeedableRng};
+        let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+        let mut point =
+            |scale: f64| Point::new(rng.gen_range(0.0, scale), rng.gen_range(0.0, scale));
+        for _ in 0..8 {
+            // Explicit control points near the chord, as handles close to
+            // a cusp are hard to integrate accurately from that end.
+            let (a, b, c, d) = (point(100.), point(100.), point(100.), point(100.));
+            let p1 = b.lerp(c, 1.0 / 3.0) + point(20.).to_vec2();
+            let p2 = c.lerp(d, 2.0 / 3.0) + point(20.).to_vec2();
+            let mut spec = SplineSpec::new();
+            spec.move_to(a);
+            spec.spline_to(None, None, b, true);
+            spec.spline_to(Some(p1), None, c, true);
+            spec.spline_to(None, Some(p2), d, false);
+            let spline = spec.solve();
+            for seg in spline.segments() {
+                let rev = seg.reverse();
+                assert_eq!(rev.reverse(), *seg);
+                for i in 0..=8 {
+                    let t = i as f64 / 8.0;
+                    let d = rev.eval(t).distance(seg.eval(1.0 - t));
+                    assert!(d < 1e-2, "{}", d);
+                    let turn = rev.tangent_angle(t) - seg.tangent_angle(1.0 - t);
+                    assert!(
+                        util::mod_tau(turn - std::f64::consts::PI).abs() < 1e-6,
+                        "{}",
+                        turn
+                    );
+                    let k = rev.curvature(t) + seg.curvature(1.0 - t);
+                    assert!(k.abs() < 1e-6 * seg.curvature(1.0 - t).abs().max(1.0));
+                }
+                // The rendered cubics stay on the curve.
+                let on_curve = |p: Point| {
+                    (0..=256)
+                        .map(|i| seg.eval(i as f64 / 256.0).distance(p))
+                        .fold(f64::INFINITY, f64::min)
+                };
+                for el in rev.render_elements() {
+                    if let PathEl::CurveTo(_, _, p) | PathEl::LineTo(p) = el {
+                        assert!(on_curve(p) < 0.5, "{}", on_curve(p));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn changed_segments() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), false);
+        spec.spline_to(None, None, Point::new(150., 50.), true);
+        spec.spline_to(None, None, Point::new(200., 0.), false);
+        spec.line_to(Point::new(300., 0.), false);
+        spec.line_to(Point::new(300., 100.), false);
+        let prev = spec.solve().into_owned();
+        assert!(spec.solve().changed_segments(&prev).is_empty());
+        // Segments between corners don't affect each other.
+        spec.move_points(&[(5, Point::new(310., 100.))]);
+        assert_eq!(spec.solve().changed_segments(&prev), vec![4]);
+        spec.move_points(&[(1, Point::new(100., 10.))]);
+        assert_eq!(spec.solve().changed_segments(&prev), vec![0, 1, 2, 4]);
+        spec.line_to(Point::new(0., 100.), false);
+        assert_eq!(spec.solve().changed_segments(&prev), vec![0, 1, 2, 4, 5]);
+    }
+
+    #[test]
+    fn stable_topology() {
+        use crate::hyperbezier::Parameterization;
+        let options = RenderOptions {
+            stable_topology: true,
+            ..RenderOptions::default()
+        };
+        let render = |y: f64, options: &RenderOptions| {
+            let mut spec = SplineSpec::new();
+            spec.move_to(Point::new(0., 0.));
+            spec.spline_to(None, None, Point::new(100., y), true);
+            spec.spline_to(None, None, Point::new(200., 0.), false);
+            spec.solve().render_with(options)
+        };
+        let kinds = |path: &BezPath| {
+            path.elements()
+                .iter()
+                .map(std::mem::discriminant)
+                .collect::<Vec<_>>()
+        };
+        // Dragging the middle point through the straight configuration keeps
+        // the same elements.
+        let reference = kinds(&render(0.0, &options));
+        for &y in &[-2.0, -0.5, 0.5, 2.0] {
+            assert_eq!(kinds(&render(y, &options)), reference, "{}", y);
+        }
+        // The parameterization is ignored.
+        let weighted = RenderOptions {
+            parameterization: Parameterization::CurvatureWeighted,
+            ..options
+        };
+        assert_eq!(render(20.0, &weighted), render(20.0, &options));
+    }
+
+    #[test]
+    fn spline_blends_into_arc() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.arc_to(Point::new(100., 100.), -0.5 * std::f64::consts::PI, true);
+        spec.spline_to(None, None, Point::new(200., 60.), true);
+        spec.spline_to(None, None, Point::new(230., -50.), true);
+        let spline = spec.solve();
+        let arc = &spline.segments()[0];
+        let next = &spline.segments()[1];
+        let th_arc = (arc.p3 - arc.p2).atan2();
+        let th_next = (next.p1 - next.p0).atan2();
+        assert!(util::mod_tau(th_arc - th_next).abs() < 1e-9);
+        assert!((arc.k1 - next.k0).abs() < 1e-3 * arc.k1.abs());
+    }
+
+    #[test]
+    fn subdivide_preserves_shape() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 50.), true);
+        spec.spline_to(None, None, Point::new(200., -20.), true);
+        spec.spline_to(None, None, Point::new(250., 80.), true);
+        let orig = spec.solve().into_owned();
+        spec.subdivide(1, 0.4);
+        assert_eq!(spec.elements().len(), 5);
+        let spline = spec.solve();
+        let seg = &orig.segments()[1];
+        let (first, second) = (&spline.segments()[1], &spline.segments()[2]);
+        assert!((first.arclen() - 0.4 * seg.arclen()).abs() < 0.1);
+        for i in 0..=10 {
+            let s = i as f64 * 0.1;
+            let d0 = first.eval(s) - seg.eval(0.4 * s);
+            let d1 = second.eval(s) - seg.eval(0.4 + 0.6 * s);
+            assert!(
+                d0.hypot() < 0.1 && d1.hypot() < 0.1,
+                "{} {}",
+                d0.hypot(),
+                d1.hypot()
+            );
+        }
+    }
+
+    #[test]
+    fn subdivide_at_arclen() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(100., 0.), false);
+        spec.arc_to(Point::new(100., 100.), std::f64::consts::PI, false);
+        let half_circle = 50.0 * std::f64::consts::PI;
+        spec.subdivide_at_arclen(100.0 + 0.5 * half_circle);
+        assert_eq!(spec.elements().len(), 4);
+        let p = spec.elements()[2].endpoint();
+        assert!((p - Point::new(150., 50.)).hypot() < 1e-9);
+        let spline = spec.solve();
+        assert!((spline.arclen() - (100.0 + half_circle)).abs() < 1e-9);
+        // Outside the spline, and at its points, there is nothing to split.
+        let before = spec.elements().to_vec();
+        for &s in &[-1.0, 0.0, 100.0, 100.0 + half_circle, 1000.0, f64::NAN] {
+            spec.subdivide_at_arclen(s);
+            assert_eq!(spec.elements(), &before[..], "{}", s);
+        }
+    }
 }
@@ -0,0 +1,255 @@
+//! Outlines from a skeleton curve and a width along it.
+//!
+//! This is for pen-stroke based design, where a stem is drawn as a single
+//! skeleton spline, and its two edges are found by offsetting the skeleton
+//! by a varying distance to either side.
+
+use kurbo::{Point, Vec2};
+
+use crate::spline::{Element, Spline, SplineSpec};
+use crate::util;
+
+/// The most samples per skeleton segment used to meet a tolerance.
+const MAX_SAMPLES: usize = 64;
+
+/// The edges and outline of a stem.
+#[derive(Clone, Debug)]
+pub struct Stem {
+    /// The left edge, in the same direction as the skeleton.
+    pub left: SplineSpec,
+    /// The right edge, in the same direction as the skeleton.
+    pub right: SplineSpec,
+    /// The closed outline.
+    ///
+    /// For an open skeleton this is a single contour, with flat caps at the
+    /// ends. For a closed skeleton it is two contours, the left edge and
+    /// the reversed right edge.
+    pub outline: Vec<SplineSpec>,
+}
+
+/// A run of offset points between corners.
+///
+/// Each point after the first is paired with the exact offset midway from
+/// the previous point, for estimating error.
+type Run = Vec<(Point, Point)>;
+
+impl<'a> Spline<'a> {
+    /// Expand the spline as the skeleton of a stem.
+    ///
+    /// The `left` and `right` functions give the distance of each edge from
+    /// the skeleton, as a function of relative arclength from 0 to 1. The
+    /// left side is the side a counterclockwise turn goes towards, in a y-up
+    /// coordinate system. For a closed skeleton, the distances at 0 and 1
+    /// should agree.
+    ///
+    /// The edges are splines through samples of the exact offset curves,
+    /// with corners where the skeleton has corners. Samples are added until
+    /// the edges are within `tolerance` of the exact offsets, up to a limit
+    /// of 64 per skeleton segment.
+    pub fn stem(
+        &self,
+        left: impl Fn(f64) -> f64,
+        right: impl Fn(f64) -> f64,
+        tolerance: f64,
+    ) -> Stem {
+        if self.segments().is_empty() {
+            return Stem {
+                left: SplineSpec::new(),
+                right: SplineSpec::new(),
+                outline: Vec::new(),
+            };
+        }
+        let mut n = 2;
+        loop {
+            let left_runs = self.offset_runs(&left, 1.0, n);
+            let right_runs = self.offset_runs(&right, -1.0, n);
+            let smooth_wrap = self.is_closed() && left_runs.len() == 1;
+            let mut left_spec = runs_to_spec(&left_runs, self.is_closed(), smooth_wrap);
+            let mut right_spec = runs_to_spec(&right_runs, self.is_closed(), smooth_wrap);
+            let err =
+                max_error(&mut left_spec, &left_runs).max(max_error(&mut right_spec, &right_runs));
+            if n >= MAX_SAMPLES || err <= tolerance {
+                let outline = if self.is_closed() {
+                    let reversed: Vec<Run> =
+                        right_runs.iter().rev().map(|r| reverse_run(r)).collect();
+                    vec![
+                        left_spec.clone(),
+                        runs_to_spec(&reversed, true, smooth_wrap),
+                    ]
+                } else {
+                    let mut runs = left_runs;
+                    runs.extend(right_runs.iter().rev().map(|r| reverse_run(r)));
+                    vec![runs_to_spec(&runs, true, false)]
+                };
+                return Stem {
+                    left: left_spec,
+                    right: right_spec,
+                    outline,
+                };
+            }
+            n *= 2;
+        }
+    }
+
+    /// Sample an offset of the spline, with `n` samples per segment.
+    ///
+    /// The offset is to the left for a `sign` of 1 and to the right for -1.
+    /// A new run starts at each corner, and for a closed spline the runs
+    /// start at a corner if there is one.
+    fn offset_runs(&self, dist: &impl Fn(f64) -> f64, sign: f64, n: usize) -> Vec<Run> {
+        let segs = self.segments();
+        let total = self.arclen();
+        let offset = |i: usize, t: f64, s: f64| {
+            let seg = &segs[i];
+            let normal = Vec2::from_angle(seg.tangent_angle(t) + 0.5 * std::f64::consts::PI);
+            seg.eval(t) + sign * dist(s / total) * normal
+        };
+        let is_corner = |i: usize| {
+            let prev = &segs[(i + segs.len() - 1) % segs.len()];
+            util::mod_tau(prev.tangent_angle(1.0) - segs[i].tangent_angle(0.0)).abs() > 1e-6
+        };
+        let mut runs: Vec<Run> = Vec::new();
+        let mut s0 = 0.0;
+        for (i, seg) in segs.iter().enumerate() {
+            let len = seg.arclen();
+            if i == 0 || is_corner(i) {
+                runs.push(vec![(offset(i, 0.0, s0), Point::ZERO)]);
+            }
+            let run = runs.last_mut().unwrap();
+            for j in 1..=n {
+                let t = j as f64 / n as f64;
+                let t_mid = (j as f64 - 0.5) / n as f64;
+                let p = offset(i, t, s0 + t * len);
+                run.push((p, offset(i, t_mid, s0 + t_mid * len)));
+            }
+            s0 += len;
+        }
+        if self.is_closed() && !is_corner(0) {
+            // Join the last run onto the first, so runs start at corners.
+            let last = runs.pop().unwrap();
+            if runs.is_empty() {
+                runs.push(last);
+                let start = runs[0][0].0;
+                runs[0].last_mut().unwrap().0 = start;
+            } else {
+                let first = std::mem::replace(&mut runs[0], last);
+                runs[0].extend(first.into_iter().skip(1));
+            }
+        }
+        runs
+    }
+}
+
+fn reverse_run(run: &[(Point, Point)]) -> Run {
+    let mut result = vec![(run.last().unwrap().0, Point::ZERO)];
+    for j in (1..run.len()).rev() {
+        result.push((run[j - 1].0, run[j].1));
+    }
+    result
+}
+
+/// Build a spec passing smoothly through each run, with lines joining runs.
+///
+/// For a closed spec, `smooth_wrap` means there is a single run, which ends
+/// where it starts and has no corner there.
+fn runs_to_spec(runs: &[Run], is_closed: bool, smooth_wrap: bool) -> SplineSpec {
+    let mut spec = SplineSpec::new();
+    let start = runs[0][0].0;
+    spec.move_to(start);
+    let mut last = start;
+    for run in runs {
+        if run[0].0 != last {
+            spec.line_to(run[0].0, false);
+        }
+        for (j, &(p, _)) in run.iter().enumerate().skip(1) {
+            spec.spline_to(None, None, p, smooth_wrap || j + 1 < run.len());
+        }
+        last = run.last().unwrap().0;
+    }
+    if is_closed {
+        if last != start {
+            spec.line_to(start, false);
+        }
+        spec.close();
+    }
+    spec
+}
+
+/// The largest distance from an exact midpoint to the solved spec.
+///
+/// The spec is built by [`runs_to_spec`] from the same runs, so the spline
+/// segments appear in the same order as the samples.
+fn max_error(spec: &mut SplineSpec, runs: &[Run]) -> f64 {
+    let mids = runs
+        .iter()
+        .flat_map(|run| run[1..].iter().map(|&(_, mid)| mid));
+    let spline = spec.solve().into_owned();
+    let curves = spline
+        .segments()
+        .iter()
+        .zip(&spec.elements()[1..])
+        .filter(|(_, el)| matches!(el, Element::SplineTo(..)))
+        .map(|(seg, _)| seg);
+    curves
+        .zip(mids)
+        .map(|(seg, mid)| {
+            let pts = seg.flatten_params(1e-3);
+            pts.windows(2)
+                .map(|w| {
+                    let t = util::nearest_on_line(mid, w[0].1, w[1].1);
+                    (mid - w[0].1.lerp(w[1].1, t)).hypot()
+                })
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Point, Shape};
+
+    use crate::SplineSpec;
+
+    #[test]
+    fn straight_stem() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.line_to(Point::new(0., 100.), false);
+        let stem = spec.solve().stem(|_| 10.0, |_| 10.0, 0.01);
+        assert_eq!(stem.outline.len(), 1);
+        let area = stem.outline[0].clone().solve().render().area();
+        assert!((area.abs() - 2000.0).abs() < 1e-6);
+        // Going up, the left side is at negative x.
+        assert!(stem.left.elements()[0].endpoint().x < 0.0);
+    }
+
+    #[test]
+    fn ring() {
+        let mut spec = SplineSpec::circle(Point::ZERO, 100.0);
+        let stem = spec
+            .solve()
+            .stem(|_| 10.0, |u| 5.0 + 10.0 * u * (1.0 - u), 0.01);
+        assert_eq!(stem.outline.len(), 2);
+        let mut left = stem.left;
+        for seg in left.solve().segments() {
+            let r = seg.eval(0.5).to_vec2().hypot();
+            assert!((r - 90.0).abs() < 0.01, "radius {}", r);
+        }
+        let outer = stem.outline[1].clone().solve().render().area();
+        let inner = stem.outline[0].clone().solve().render().area();
+        // The contours wind in opposite directions.
+        assert!(outer * inner < 0.0);
+        assert!(outer.abs() > inner.abs());
+    }
+
+    #[test]
+    fn closed_with_corners() {
+        let mut spec = SplineSpec::rect(kurbo::Rect::new(0., 0., 100., 100.));
+        let stem = spec.solve().stem(|_| 5.0, |_| 5.0, 0.01);
+        assert_eq!(stem.outline.len(), 2);
+        // Each side of the outer edge is followed by a bevel at the corner.
+        let bevels = stem.right.elements().iter();
+        let bevels = bevels.filter(|el| matches!(el, crate::Element::LineTo(..)));
+        assert_eq!(bevels.count(), 4);
+    }
+}
@@ -0,0 +1,135 @@
+//! Turning curve segments into lines.
+
+use kurbo::Point;
+
+use crate::edit::EditError;
+use crate::spline::{Element, SplineSpec};
+
+/// The shortest a control point is made, relative to its length before,
+/// when it is turned so far from its old direction that its projection
+/// would leave it too short to shape the segment.
+const MIN_TENSION: f64 = 0.25;
+
+impl SplineSpec {
+    /// Replace segment `seg_ix` with a straight line, keeping its end
+    /// points.
+    ///
+    /// Segment `i` corresponds to element `i + 1`. At each end of the
+    /// segment that is marked smooth, the neighboring segment is made
+    /// tangent to the line, as by [`make_smooth`](SplineSpec::make_smooth),
+    /// and auto control points follow the line when solved. An explicit
+    /// control point is turned onto the line and its tension adjusted: it
+    /// moves to the nearest point on the line's tangent, which keeps the
+    /// neighboring segment closer to its old shape than turning it at its
+    /// full length.
+    ///
+    /// This is the inverse of turning a line into a curve. Fails if there
+    /// is no segment `seg_ix`.
+    pub fn straighten(&mut self, seg_ix: usize) -> Result<(), EditError> {
+        let n_segs = self.elements().len().saturating_sub(1);
+        if n_segs == 0 {
+            return Err(EditError::NoSegments);
+        }
+        if seg_ix >= n_segs {
+            return Err(EditError::Index(seg_ix));
+        }
+        let el_ix = seg_ix + 1;
+        let el = self.elements()[el_ix];
+        let p = el.endpoint();
+        let end_smooth = el.is_smooth();
+        let start_smooth = match (seg_ix, self.is_closed()) {
+            (0, true) => self.elements().last().unwrap().is_smooth(),
+            (0, false) => false,
+            _ => self.elements()[seg_ix].is_smooth(),
+        };
+        self.elements_mut()[el_ix] = Element::LineTo(p, end_smooth);
+        let before = self.elements().to_vec();
+        if start_smooth {
+            self.make_smooth(seg_ix);
+        }
+        if end_smooth {
+            self.make_smooth(el_ix);
+        }
+        for ix in 1..before.len() {
+            let start = before[ix - 1].endpoint();
+            let elements = self.elements_mut();
+            if let (Element::SplineTo(old1, old2, end, _), Element::SplineTo(p1, p2, ..)) =
+                (before[ix], &mut elements[ix])
+            {
+                adjust_tension(p1, old1, start);
+                adjust_tension(p2, old2, end);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Move a control point that was turned about `anchor` from `old` to the
+/// nearest point on its new direction.
+fn adjust_tension(handle: &mut Option<Point>, old: Option<Point>, anchor: Point) {
+    let (h, old) = match (handle, old) {
+        (Some(h), Some(old)) if *h != old => (h, old),
+        _ => return,
+    };
+    let len = (old - anchor).hypot();
+    let dir = (*h - anchor).normalize();
+    let proj = (old - anchor).dot(dir);
+    *h = anchor + dir * proj.max(MIN_TENSION * len);
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{Element, Segment, SplineSpec};
+
+    #[test]
+    fn straighten() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(
+            Some(Point::new(0., 50.)),
+            Some(Point::new(50., 80.)),
+            Point::new(100., 80.),
+            true,
+        );
+        spec.spline_to(None, None, Point::new(200., 60.), true);
+        spec.spline_to(None, None, Point::new(250., 0.), false);
+        let before = spec.solve().segments()[0].clone();
+        let mut rotated = spec.clone();
+        spec.straighten(1).unwrap();
+        assert_eq!(
+            spec.elements()[2],
+            Element::LineTo(Point::new(200., 60.), true)
+        );
+        // The explicit control point before the line is turned onto it,
+        // and moved to the nearest point there.
+        let (a, b) = (Point::new(100., 80.), Point::new(200., 60.));
+        let dir = (b - a).normalize();
+        let p2 = match spec.elements()[1] {
+            Element::SplineTo(_, Some(p2), ..) => p2,
+            _ => panic!(),
+        };
+        assert!((a - p2).cross(dir).abs() < 1e-9);
+        let len = (a - Point::new(50., 80.)).dot(dir);
+        assert!(((a - p2).hypot() - len).abs() < 1e-9);
+        // That changes the neighboring segment less than turning the
+        // control point at its full length.
+        rotated.elements_mut()[1] =
+            Element::SplineTo(Some(Point::new(0., 50.)), Some(a - 50. * dir), a, true);
+        let moved = |seg: &Segment| {
+            (0..=16)
+                .map(|i| {
+                    seg.eval(i as f64 / 16.)
+                        .distance(before.eval(i as f64 / 16.))
+                })
+                .fold(0.0, f64::max)
+        };
+        let spline = spec.solve();
+        assert!(moved(&spline.segments()[0]) < moved(&rotated.solve().segments()[0]));
+        // The auto segment after it follows the line.
+        let seg = &spline.segments()[2];
+        let turn = seg.tangent_angle(0.0) - (b - a).atan2();
+        assert!(turn.abs() < 1e-3, "{}", turn);
+    }
+}
@@ -0,0 +1,167 @@
+//! Writing SVG path data.
+//!
+//! Numbers are rounded to a given number of decimal places, with trailing
+//! zeros dropped, and always use '.' as the decimal separator.
+
+use std::fmt::{self, Write};
+
+use kurbo::Point;
+
+use crate::sink::PathSink;
+use crate::spline::Spline;
+
+/// The most decimal places written.
+///
+/// Rounded coordinates are scaled to integers, and this keeps them exact
+/// for coordinates up to several thousand. Larger ones are written with
+/// trailing zeros.
+const MAX_PRECISION: usize = 12;
+
+impl<'a> Spline<'a> {
+    /// Write the rendered spline as SVG path data, as in the `d` attribute
+    /// of a `path` element, with coordinates rounded to `precision`
+    /// decimal places, up to 12.
+    ///
+    /// The data is written straight to `out`, with no intermediate
+    /// strings. A spline with no segments writes nothing, and the data for
+    /// several splines can be written one after another.
+    pub fn write_svg_path(&self, out: &mut impl Write, precision: usize) -> fmt::Result {
+        let mut sink = SvgSink::new(out, precision);
+        self.render_to(&mut sink);
+        sink.finish()
+    }
+}
+
+/// A sink writing SVG path data, keeping the first error.
+pub(crate) struct SvgSink<'a, W> {
+    out: &'a mut W,
+    precision: usize,
+    result: fmt::Result,
+}
+
+impl<'a, W: Write> SvgSink<'a, W> {
+    /// A sink writing to `out`, as for
+    /// [`write_svg_path`](Spline::write_svg_path).
+    pub(crate) fn new(out: &'a mut W, precision: usize) -> SvgSink<'a, W> {
+        SvgSink {
+            out,
+            precision: precision.min(MAX_PRECISION),
+            result: Ok(()),
+        }
+    }
+
+    /// The first error writing the data, if any.
+    pub(crate) fn finish(self) -> fmt::Result {
+        self.result
+    }
+
+    fn command(&mut self, c: char, points: &[Point]) {
+        if self.result.is_ok() {
+            self.result = self.write_command(c, points);
+        }
+    }
+
+    fn write_command(&mut self, c: char, points: &[Point]) -> fmt::Result {
+        self.out.write_char(c)?;
+        for (i, p) in points.iter().enumerate() {
+            if i > 0 {
+                self.out.write_char(' ')?;
+            }
+            write_number(self.out, p.x, self.precision)?;
+            self.out.write_char(',')?;
+            write_number(self.out, p.y, self.precision)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> PathSink for SvgSink<'a, W> {
+    fn move_to(&mut self, p: Point) {
+        self.command('M', &[p]);
+    }
+
+    fn line_to(&mut self, p: Point) {
+        self.command('L', &[p]);
+    }
+
+    fn curve_to(&mut self, p1: Point, p2: Point, p3: Point) {
+        self.command('C', &[p1, p2, p3]);
+    }
+
+    fn close(&mut self) {
+        self.command('Z', &[]);
+    }
+}
+
+/// Write `x` rounded to `precision` decimal places, without trailing zeros.
+fn write_number(out: &mut impl Write, x: f64, precision: usize) -> fmt::Result {
+    let scale = 10u64.pow(precision as u32);
+    let v = (x * scale as f64).round();
+    // Beyond this, or for infinities and NaN, the scaled value is not an
+    // exact integer.
+    if !v.is_finite() || v.abs() >= 9e15 {
+        return write!(out, "{:.*}", precision, x);
+    }
+    let v = v as i64;
+    if v < 0 {
+        out.write_char('-')?;
+    }
+    let v = v.unsigned_abs();
+    write!(out, "{}", v / scale)?;
+    let (mut frac, mut digits) = (v % scale, precision);
+    if frac != 0 {
+        while frac % 10 == 0 {
+            frac /= 10;
+            digits -= 1;
+        }
+        write!(out, ".{:01$}", frac, digits)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{BezPath, ParamCurve, Point};
+
+    use super::write_number;
+    use crate::SplineSpec;
+
+    #[test]
+    fn numbers() {
+        let cases = [
+            (0.0, 2, "0"),
+            (-0.001, 2, "0"),
+            (1.5, 2, "1.5"),
+            (-2.125, 2, "-2.13"),
+            (100.0, 3, "100"),
+            (0.05, 1, "0.1"),
+            (-7.25, 0, "-7"),
+            (0.000_001, 6, "0.000001"),
+        ];
+        for &(x, precision, expected) in &cases {
+            let mut s = String::new();
+            write_number(&mut s, x, precision).unwrap();
+            assert_eq!(s, expected, "{} at {}", x, precision);
+        }
+    }
+
+    #[test]
+    fn svg_path_matches_render() {
+        let mut spec = SplineSpec::new();
+        spec.move_to(Point::new(0., 0.));
+        spec.spline_to(None, None, Point::new(100., 60.), true);
+        spec.line_to(Point::new(200., 0.), false);
+        spec.close();
+        let spline = spec.solve();
+        let mut d = String::new();
+        spline.write_svg_path(&mut d, 3).unwrap();
+        assert!(d.starts_with("M0,0C"), "{}", d);
+        assert!(d.ends_with("L200,0Z"), "{}", d);
+        let parsed = BezPath::from_svg(&d).unwrap();
+        let rendered = spline.render();
+        assert_eq!(parsed.elements().len(), rendered.elements().len());
+        for (a, b) in parsed.segments().zip(rendered.segments()) {
+            assert!(a.end().distance(b.end()) < 1e-3);
+        }
+    }
+}
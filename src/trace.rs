@@ -0,0 +1,181 @@
+//! Tracing bitmap silhouettes into splines.
+//!
+//! The boundary between inside and outside pixels is followed along pixel
+//! edges, and a closed spec is fitted through the corners of the pixels on
+//! it, as by [`SplineSpec::fit_points`].
+//!
+//! Pixel `(x, y)` covers the square from `(x, y)` to `(x + 1, y + 1)`, with
+//! the first row at the top, so the specs are in y-down coordinates.
+
+use std::collections::HashSet;
+
+use kurbo::Point;
+
+use crate::fit::FitOptions;
+use crate::spline::SplineSpec;
+
+/// Options for tracing.
+#[derive(Clone, Copy, Debug)]
+pub struct TraceOptions {
+    /// The largest distance, in pixels, between the pixel boundary and the
+    /// polygon the spline is fitted through.
+    pub tolerance: f64,
+    /// The smallest turn, in radians, at a vertex of the polygon for it to
+    /// become a corner.
+    pub corner_angle: f64,
+    /// Boundaries enclosing less than this area, in pixels, are dropped as
+    /// specks.
+    pub min_area: f64,
+}
+
+impl Default for TraceOptions {
+    fn default() -> TraceOptions {
+        TraceOptions {
+            tolerance: 1.0,
+            corner_angle: 1.2,
+            min_area: 4.0,
+        }
+    }
+}
+
+/// A point on the pixel grid.
+type Vertex = (i64, i64);
+
+/// Trace the silhouette of a `width` by `height` bitmap, where `inside`
+/// tells whether a pixel is part of the shape.
+///
+/// Each boundary becomes a closed spec. Outer boundaries run
+/// counterclockwise in a y-up coordinate system and holes clockwise, so the
+/// result fills correctly with either fill rule. Pixels touching only at a
+/// corner belong to separate boundaries.
+pub fn trace(
+    width: usize,
+    height: usize,
+    inside: impl Fn(usize, usize) -> bool,
+    options: &TraceOptions,
+) -> Vec<SplineSpec> {
+    let is_inside = |x: i64, y: i64| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && inside(x as usize, y as usize)
+    };
+    // Directed edges with the inside on the right, in y-down coordinates.
+    let mut edges = Vec::new();
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            if !is_inside(x, y) {
+                continue;
+            }
+            if !is_inside(x, y - 1) {
+                edges.push(((x, y), (1, 0)));
+            }
+            if !is_inside(x + 1, y) {
+                edges.push(((x + 1, y), (0, 1)));
+            }
+            if !is_inside(x, y + 1) {
+                edges.push(((x + 1, y + 1), (-1, 0)));
+            }
+            if !is_inside(x - 1, y) {
+                edges.push(((x, y + 1), (0, -1)));
+            }
+        }
+    }
+    let fit_options = FitOptions {
+        tolerance: options.tolerance,
+        corner_angle: options.corner_angle,
+    };
+    let edge_set: HashSet<_> = edges.iter().copied().collect();
+    let mut used = HashSet::new();
+    let mut result = Vec::new();
+    for &start in &edges {
+        if used.contains(&start) {
+            continue;
+        }
+        let mut corners = Vec::new();
+        let mut edge = start;
+        loop {
+            used.insert(edge);
+            let ((x, y), (dx, dy)) = edge;
+            let v = (x + dx, y + dy);
+            // Turning right first keeps diagonal neighbors apart.
+            let next = [(-dy, dx), (dx, dy), (dy, -dx)]
+                .iter()
+                .map(|&d| (v, d))
+                .find(|e| edge_set.contains(e))
+                .unwrap();
+            if next.1 != edge.1 {
+                corners.push(v);
+            }
+            if next == start {
+                break;
+            }
+            edge = next;
+        }
+        if area(&corners).abs() >= options.min_area {
+            let pts: Vec<Point> = corners
+                .iter()
+                .map(|&(x, y)| Point::new(x as f64, y as f64))
+                .collect();
+            result.push(SplineSpec::fit_points(&pts, true, &fit_options));
+        }
+    }
+    result
+}
+
+/// The signed area of a closed polygon.
+fn area(pts: &[Vertex]) -> f64 {
+    let n = pts.len();
+    let twice: i64 = (0..n)
+        .map(|i| {
+            let (a, b) = (pts[i], pts[(i + 1) % n]);
+            a.0 * b.1 - a.1 * b.0
+        })
+        .sum();
+    0.5 * twice as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::{Point, Rect, Shape};
+
+    use super::*;
+    use crate::Element;
+
+    #[test]
+    fn trace_shapes() {
+        // A square with a square hole, and a disk.
+        let inside = |x: usize, y: usize| {
+            let (x, y) = (x as f64 + 0.5, y as f64 + 0.5);
+            let square = Rect::new(2., 2., 22., 22.);
+            let hole = Rect::new(8., 8., 16., 16.);
+            let in_square = square.contains(Point::new(x, y)) && !hole.contains(Point::new(x, y));
+            in_square || Point::new(x, y).distance(Point::new(45., 12.)) < 10.
+        };
+        let mut specs = trace(60, 25, inside, &TraceOptions::default());
+        // Boundaries are found in scan order.
+        assert_eq!(specs.len(), 3);
+        let mut disk = specs.remove(1);
+        for (spec, (lo, hi)) in specs.iter_mut().zip(&[(2., 22.), (8., 16.)]) {
+            assert_eq!(spec.elements().len(), 5);
+            assert!(spec.elements()[1..]
+                .iter()
+                .all(|el| matches!(el, Element::LineTo(_, false))));
+            let bbox = spec.solve().bounding_box();
+            assert_eq!(bbox, Rect::new(*lo, *lo, *hi, *hi));
+        }
+        // The outer boundary and the hole run in opposite directions.
+        let area0 = specs[0].solve().render().area();
+        let area1 = specs[1].solve().render().area();
+        assert!(area0 * area1 < 0.0);
+        assert!(disk.elements()[1..].iter().all(|el| el.is_smooth()));
+        let spline = disk.solve();
+        for seg in spline.segments() {
+            for i in 0..8 {
+                let r = seg.eval(i as f64 / 8.0).distance(Point::new(45., 12.));
+                assert!((r - 10.).abs() < 1.5, "{}", r);
+            }
+        }
+    }
+}
@@ -1,5 +1,20 @@
-/// Normalize an angle to the range -PI..PI.
-pub fn mod_tau(x: f64) -> f64 {
-    // Do this in terms of euclidean remainder instead?
-    x - std::f64::consts::TAU * (x * (1.0 / std::f64::consts::TAU)).round()
+use kurbo::{Point, Vec2};
+
+pub use crate::hyperbezier::mod_tau;
+
+/// The parameter of the nearest point to `p` on the line segment from `p0`
+/// to `p1`, from 0 to 1.
+pub fn nearest_on_line(p: Point, p0: Point, p1: Point) -> f64 {
+    let d = p1 - p0;
+    let d2 = d.hypot2();
+    if d2 == 0.0 {
+        return 0.0;
+    }
+    ((p - p0).dot(d) / d2).clamp(0.0, 1.0)
+}
+
+/// Move `handle` onto the ray from `p` in direction `dir`, keeping its
+/// distance from `p`.
+pub fn rotate_onto(handle: Point, p: Point, dir: Vec2) -> Point {
+    p + (handle - p).hypot() * dir
 }
@@ -0,0 +1,107 @@
+//! Spline segments that wind around more than once.
+
+use std::f64::consts::TAU;
+
+use crate::spline::{Element, SplineSpec};
+
+impl SplineSpec {
+    /// Add `turns` full turns to the spline segment of element `ix`, or
+    /// remove them with zero.
+    ///
+    /// The tangent angles at the ends of a segment are otherwise taken to
+    /// be within a half turn of its chord, so a segment never turns by
+    /// more than a full turn. Positive turns are counterclockwise in a
+    /// y-up coordinate system.
+    ///
+    /// This makes loops and spirals possible with few points, as with
+    /// Spiro. Only spline elements can wind; this does nothing for lines
+    /// and arcs. A lone auto segment, with no tangent from either end,
+    /// stays straight.
+    ///
+    /// Auto segments winding by several turns may not converge as well;
+    /// more points are better there.
+    pub fn set_winding(&mut self, ix: usize, turns: i32) {
+        if !matches!(self.elements()[ix], Element::SplineTo(..)) {
+            return;
+        }
+        if turns == 0 {
+            self.windings.remove(&ix);
+        } else {
+            self.windings.insert(ix, turns);
+        }
+        self.dirty = true;
+    }
+
+    /// The extra full turns of the spline segment of element `ix`.
+    pub fn winding(&self, ix: usize) -> i32 {
+        self.windings.get(&ix).copied().unwrap_or(0)
+    }
+
+    /// The angles, in radians, that the winding of element `ix` adds at
+    /// the start and end of its segment.
+    ///
+    /// These are whole turns, so the tangents at the ends stay the same.
+    pub(crate) fn winding_offsets(&self, ix: usize) -> (f64, f64) {
+        let turns = self.winding(ix);
+        let start = turns.div_euclid(2);
+        (TAU * start as f64, TAU * (turns - start) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use kurbo::Point;
+
+    use crate::{Spline, SplineSpec};
+
+    /// The total turning of the tangent along a segment, in turns.
+    fn turning(spline: &Spline, seg_ix: usize) -> f64 {
+        let seg = &spline.segments()[seg_ix];
+        let th = |t: f64| seg.hb.compute_theta(t);
+        (th(1.0) - th(0.0)) / std::f64::consts::TAU
+    }
+
+    #[test]
+    fn winding() {
+        // The second pair of control points is where matching the end
+        // angles only up to a full turn reverses both tangents.
+        for &(x, y) in &[(30., 30.), (0., 60.)] {
+            let mut spec = SplineSpec::new();
+            spec.move_to(Point::new(0., 0.));
+            spec.spline_to(
+                Some(Point::new(x, y)),
+                Some(Point::new(100. - x, y)),
+                Point::new(100., 0.),
+                false,
+            );
+            let before = spec.solve().into_owned();
+            spec.set_winding(1, 1);
+            let after = spec.solve().into_owned();
+            // The same tangents at the ends, with a full turn in between.
+            let seg0 = &before.segments()[0];
+            let seg1 = &after.segments()[0];
+            for &t in &[0.0, 1.0] {
+                let d = crate::util::mod_tau(seg1.tangent_angle(t) - seg0.tangent_angle(t));
+                assert!(d.abs() < 1e-3, "{}", d);
+            }
+            let turns = turning(&after, 0) - turning(&before, 0);
+            assert!((turns - 1.0).abs() < 1e-6, "{}", turns);
+        }
+
+        for &w in &[1, -1] {
+            let mut spec = SplineSpec::new();
+            spec.move_to(Point::new(0., 0.));
+            spec.spline_to(None, None, Point::new(100., 50.), true);
+            spec.spline_to(None, None, Point::new(200., 0.), false);
+            let before = turning(&spec.solve(), 0);
+            spec.set_winding(1, w);
+            let spline = spec.solve().into_owned();
+            assert!(spec.solve_stats().unwrap().residual < 1e-3);
+            let turns = turning(&spline, 0) - before;
+            assert!((turns - w as f64).abs() < 0.25, "{}: {}", w, turns);
+            // Still smooth at the joint.
+            let joint = spline.iter_joints().next().unwrap();
+            assert!(joint.angle.abs() < 1e-3, "{}", joint.angle);
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Compare the solver with samples from the research spline.
+//!
+//! Each file in `tests/fixtures/research` is a json list of cases, each an
+//! object with a `spec` and the `samples` the research spline produces
+//! for it, in the format of [`Spline::samples`](spline::Spline::samples).
+//! The `samples` example prints the same format from this crate.
+
+#![cfg(feature = "serde")]
+
+use std::f64::consts::PI;
+use std::path::Path;
+
+use serde_json::Value;
+use spline::{Sample, SplineSpec};
+
+/// The largest distance between corresponding points, relative to the size
+/// of the spline.
+const POINT_TOLERANCE: f64 = 1e-3;
+
+/// The largest difference between corresponding tangents, in radians.
+const TANGENT_TOLERANCE: f64 = 1e-2;
+
+#[test]
+fn research_fixtures() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/research");
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let data = std::fs::read_to_string(&path).unwrap();
+        let cases: Vec<Value> = serde_json::from_str(&data).unwrap();
+        for (i, case) in cases.into_iter().enumerate() {
+            let mut spec: SplineSpec = serde_json::from_value(case["spec"].clone()).unwrap();
+            let expected: Vec<Sample> = serde_json::from_value(case["samples"].clone()).unwrap();
+            let first = expected.iter().filter(|s| s.segment == 0).count();
+            assert!(
+                first >= 2,
+                "{:?} case {} needs two samples per segment",
+                path,
+                i
+            );
+            let per_segment = first - 1;
+            let spline = spec.solve();
+            let actual = spline.samples(per_segment);
+            assert_eq!(actual.len(), expected.len(), "{:?} case {}", path, i);
+            let bbox = spline.bounding_box();
+            let size = bbox.width().max(bbox.height());
+            for (a, e) in actual.iter().zip(&expected) {
+                let d = (a.point - e.point).hypot();
+                let dth = (a.tangent - e.tangent + PI).rem_euclid(2.0 * PI) - PI;
+                assert!(
+                    d <= POINT_TOLERANCE * size && dth.abs() <= TANGENT_TOLERANCE,
+                    "{:?} case {}: segment {} at {}: {:?} vs {:?}",
+                    path,
+                    i,
+                    e.segment,
+                    e.t,
+                    a,
+                    e
+                );
+            }
+        }
+    }
+}